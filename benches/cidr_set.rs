@@ -0,0 +1,70 @@
+//! Compares matching a rule with 10k `cidr` values against a linear scan over the networks.
+//!
+//! Run with `cargo bench --bench cidr_set`
+use cidr::IpCidr;
+use sigma_rust::{rule_from_yaml, Event};
+use std::net::{IpAddr, Ipv4Addr};
+use std::str::FromStr;
+use std::time::Instant;
+
+const NETWORKS: u32 = 10_000;
+const LOOKUPS: u32 = 100_000;
+
+fn main() {
+    let cidrs: Vec<String> = (0..NETWORKS)
+        .map(|i| {
+            let length = 16 + (i % 17);
+            let address = i.wrapping_mul(2_654_435_761) & (u32::MAX << (32 - length));
+            format!("{}/{}", Ipv4Addr::from(address), length)
+        })
+        .collect();
+    let values: Vec<String> = cidrs
+        .iter()
+        .map(|c| format!("            - '{}'", c))
+        .collect();
+    let rule = rule_from_yaml(&format!(
+        r#"
+title: Threat intel networks
+logsource:
+detection:
+    selection:
+        SourceIp|cidr:
+{}
+    condition: selection
+"#,
+        values.join("\n")
+    ))
+    .unwrap();
+    let networks: Vec<IpCidr> = cidrs.iter().map(|c| IpCidr::from_str(c).unwrap()).collect();
+    let addresses: Vec<String> = (0..LOOKUPS)
+        .map(|i| Ipv4Addr::from(i.wrapping_mul(40_503) << 7).to_string())
+        .collect();
+
+    let start = Instant::now();
+    let mut linear_matches = 0;
+    for address in &addresses {
+        let ip = IpAddr::from_str(address).unwrap();
+        if networks.iter().any(|n| n.contains(&ip)) {
+            linear_matches += 1;
+        }
+    }
+    let linear = start.elapsed();
+
+    let start = Instant::now();
+    let mut rule_matches = 0;
+    for address in &addresses {
+        let event = Event::from([("SourceIp", address.as_str())]);
+        if rule.is_match(&event) {
+            rule_matches += 1;
+        }
+    }
+    let rule_time = start.elapsed();
+
+    assert_eq!(linear_matches, rule_matches);
+    println!(
+        "{} lookups in {} networks, {} matches",
+        LOOKUPS, NETWORKS, rule_matches
+    );
+    println!("linear scan: {:?}", linear);
+    println!("rule:        {:?} (including event creation)", rule_time);
+}
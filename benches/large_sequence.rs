@@ -0,0 +1,54 @@
+//! Measures matching a field with 1000 values and `all` against a list of 1000 elements,
+//! with literal values, which are looked up in a set, and with wildcards, which are
+//! compared to every element.
+//!
+//! Run with `cargo bench --bench large_sequence`
+use sigma_rust::{rule_from_yaml, Event, Rule};
+use std::time::Instant;
+
+const VALUES: usize = 1000;
+const ITERATIONS: u32 = 100;
+
+fn rule(values: &[String]) -> Rule {
+    let values: Vec<String> = values
+        .iter()
+        .map(|v| format!("            - '{}'", v))
+        .collect();
+    rule_from_yaml(&format!(
+        r#"
+title: Large list
+logsource:
+detection:
+    selection:
+        Values|all:
+{}
+    condition: selection
+"#,
+        values.join("\n")
+    ))
+    .unwrap()
+}
+
+fn main() {
+    let elements: Vec<String> = (0..VALUES).map(|i| format!("Value{}", i)).collect();
+    let yaml = format!("Values: [{}]", elements.join(", "));
+    let event = Event::try_from(serde_yml::from_str::<serde_yml::Value>(&yaml).unwrap()).unwrap();
+
+    let literal: Vec<String> = (0..VALUES).rev().map(|i| format!("value{}", i)).collect();
+    let wildcard: Vec<String> = (0..VALUES).rev().map(|i| format!("val*{}", i)).collect();
+    for (name, values) in [("literal", literal), ("wildcard", wildcard)] {
+        let rule = rule(&values);
+        assert!(rule.is_match(&event));
+        let start = Instant::now();
+        for _ in 0..ITERATIONS {
+            assert!(rule.is_match(&event));
+        }
+        println!(
+            "{} values against {} elements, {}: {:?} per event",
+            VALUES,
+            elements.len(),
+            name,
+            start.elapsed() / ITERATIONS
+        );
+    }
+}
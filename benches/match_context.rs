@@ -0,0 +1,92 @@
+//! Compares the allocations and the time of matching a million events with
+//! `Rule::is_match` and with `Rule::is_match_with` reusing a `MatchContext`.
+//! Neither allocates a buffer for the selection results per event, `is_match` reuses one of
+//! the thread. Both count the allocations of matching itself, e.g. lowercasing event values.
+//!
+//! Run with `cargo bench --bench match_context`
+use sigma_rust::{rule_from_yaml, Event, MatchContext};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+
+const EVENTS: usize = 1_000_000;
+
+/// Counts the allocations to show those saved by reusing the context
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+fn main() {
+    let rule = rule_from_yaml(
+        r#"
+title: Suspicious process
+logsource:
+detection:
+    selection_id:
+        EventID: 4688
+    selection_image:
+        Image|endswith: '\cmd.exe'
+    filter_user:
+        User: SYSTEM
+    condition: 1 of selection_* and not filter_user
+"#,
+    )
+    .unwrap();
+    let events: Vec<Event> = (0..100)
+        .map(|i| {
+            Event::from([
+                ("EventID", if i % 2 == 0 { "4688" } else { "1" }),
+                ("Image", "C:\\Windows\\System32\\cmd.exe"),
+                ("User", if i % 3 == 0 { "SYSTEM" } else { "user" }),
+            ])
+        })
+        .collect();
+
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed);
+    let start = Instant::now();
+    let mut matches = 0;
+    for event in events.iter().cycle().take(EVENTS) {
+        if rule.is_match(event) {
+            matches += 1;
+        }
+    }
+    let fresh_time = start.elapsed();
+    let fresh_allocations = ALLOCATIONS.load(Ordering::Relaxed) - allocations;
+
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed);
+    let start = Instant::now();
+    let mut context = MatchContext::new();
+    let mut context_matches = 0;
+    for event in events.iter().cycle().take(EVENTS) {
+        if rule.is_match_with(event, &mut context) {
+            context_matches += 1;
+        }
+    }
+    let context_time = start.elapsed();
+    let context_allocations = ALLOCATIONS.load(Ordering::Relaxed) - allocations;
+
+    assert_eq!(matches, context_matches);
+    println!("{} events, {} matches", EVENTS, matches);
+    println!(
+        "is_match:      {:?}, {} allocations",
+        fresh_time, fresh_allocations
+    );
+    println!(
+        "is_match_with: {:?}, {} allocations",
+        context_time, context_allocations
+    );
+}
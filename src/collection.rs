@@ -0,0 +1,236 @@
+use crate::error::ParserError;
+use crate::rule::Rule;
+use serde::Deserialize;
+use serde_yml::{Mapping, Value};
+
+const ACTION_KEY: &str = "action";
+
+/// Remove a leading UTF-8 byte order mark, e.g. of rules saved with Windows editors.
+/// serde_yml would otherwise treat it as part of the first key.
+/// Line endings need no handling, `\r\n` is already a line break in YAML.
+#[inline(always)]
+pub(crate) fn strip_bom(yaml: &str) -> &str {
+    yaml.strip_prefix('\u{feff}').unwrap_or(yaml)
+}
+
+/// Split a YAML string into its documents, skipping empty documents.
+pub(crate) fn yaml_documents(yaml: &str) -> Result<Vec<Value>, serde_yml::Error> {
+    let mut documents = vec![];
+    for document in serde_yml::Deserializer::from_str(strip_bom(yaml)) {
+        let value = Value::deserialize(document)?;
+        if !value.is_null() {
+            documents.push(value);
+        }
+    }
+    Ok(documents)
+}
+
+/// Recursively merge `other` into `base`. Mappings are merged key by key,
+/// any other value in `other` replaces the value in `base`.
+fn deep_merge(base: &mut Value, other: &Value) {
+    match (base, other) {
+        (Value::Mapping(base), Value::Mapping(other)) => {
+            for (key, value) in other {
+                match base.get_mut(key) {
+                    Some(existing) => deep_merge(existing, value),
+                    None => {
+                        base.insert(key.clone(), value.clone());
+                    }
+                }
+            }
+        }
+        (base, other) => *base = other.clone(),
+    }
+}
+
+/// Parse a Sigma rule collection, i.e. a YAML string with multiple documents
+/// separated by `---`, into standalone rules.
+///
+/// The `action` key of a document controls how it is processed:
+/// * `global`: the document is merged into all following documents
+/// * `reset`: all global values defined so far are discarded
+/// * `repeat`: the document is merged into the previous rule to create a new rule
+///
+/// Documents without an `action` are merged with the global values and parsed as rules.
+///
+/// # Example
+/// ```rust
+/// use sigma_rust::parse_rule_collection;
+/// let rules = parse_rule_collection(r#"
+/// action: global
+/// title: Suspicious shells
+/// logsource:
+///     category: process_creation
+///     product: windows
+/// ---
+/// detection:
+///     selection:
+///         Image|endswith: '\cmd.exe'
+///     condition: selection
+/// ---
+/// action: repeat
+/// detection:
+///     selection:
+///         Image|endswith: '\powershell.exe'
+/// "#).unwrap();
+/// assert_eq!(rules.len(), 2);
+/// assert_eq!(rules[1].logsource.product.as_deref(), Some("windows"));
+/// ```
+pub fn parse_rule_collection(yaml: &str) -> Result<Vec<Rule>, ParserError> {
+    let documents = yaml_documents(yaml).map_err(ParserError::YamlParsing)?;
+
+    let mut global = Value::Mapping(Mapping::new());
+    let mut previous: Option<Value> = None;
+    let mut rules = vec![];
+    for mut document in documents {
+        let action = match &mut document {
+            Value::Mapping(mapping) => mapping.remove(ACTION_KEY),
+            _ => None,
+        };
+        let resolved = match action.as_ref().map(|a| a.as_str()) {
+            None => {
+                let mut resolved = global.clone();
+                deep_merge(&mut resolved, &document);
+                resolved
+            }
+            Some(Some("global")) => {
+                deep_merge(&mut global, &document);
+                continue;
+            }
+            Some(Some("reset")) => {
+                global = Value::Mapping(Mapping::new());
+                continue;
+            }
+            Some(Some("repeat")) => {
+                let mut resolved = previous.clone().ok_or(ParserError::RepeatWithoutRule())?;
+                deep_merge(&mut resolved, &document);
+                resolved
+            }
+            Some(action) => {
+                return Err(ParserError::UnsupportedCollectionAction(
+                    action.unwrap_or_default().to_string(),
+                ))
+            }
+        };
+        rules.push(serde_yml::from_value(resolved.clone()).map_err(ParserError::YamlParsing)?);
+        previous = Some(resolved);
+    }
+    Ok(rules)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event::Event;
+
+    #[test]
+    fn test_parse_rule_collection() {
+        let collection = r#"
+action: global
+title: Suspicious Windows shells
+status: experimental
+logsource:
+    product: windows
+detection:
+    filter:
+        User: SYSTEM
+    condition: selection and not filter
+level: high
+---
+logsource:
+    category: process_creation
+detection:
+    selection:
+        Image|endswith: '\cmd.exe'
+---
+logsource:
+    category: file_event
+detection:
+    selection:
+        TargetFilename|endswith: '.bat'
+level: medium
+"#;
+        let rules = parse_rule_collection(collection).unwrap();
+        assert_eq!(rules.len(), 2);
+        for rule in &rules {
+            assert_eq!(rule.title, "Suspicious Windows shells");
+            assert_eq!(rule.logsource.product.as_deref(), Some("windows"));
+            assert!(rule.custom_fields.is_empty());
+        }
+        assert_eq!(
+            rules[0].logsource.category.as_deref(),
+            Some("process_creation")
+        );
+        assert_eq!(rules[0].level, Some(crate::rule::Level::High));
+        assert_eq!(rules[1].logsource.category.as_deref(), Some("file_event"));
+        assert_eq!(rules[1].level, Some(crate::rule::Level::Medium));
+
+        let event = Event::from([("Image", "C:\\Windows\\System32\\cmd.exe"), ("User", "bob")]);
+        assert!(rules[0].is_match(&event));
+        assert!(!rules[1].is_match(&event));
+        let event = Event::from([
+            ("Image", "C:\\Windows\\System32\\cmd.exe"),
+            ("User", "SYSTEM"),
+        ]);
+        assert!(!rules[0].is_match(&event));
+    }
+
+    #[test]
+    fn test_parse_rule_collection_repeat_and_reset() {
+        let collection = r#"
+action: global
+logsource:
+    category: process_creation
+---
+title: First
+detection:
+    selection:
+        Image|endswith: '\cmd.exe'
+    condition: selection
+---
+action: repeat
+title: Repeated
+detection:
+    selection:
+        Image|endswith: '\powershell.exe'
+---
+action: reset
+---
+title: Without global
+logsource:
+    product: linux
+detection:
+    selection:
+        Image|endswith: '/bash'
+    condition: selection
+"#;
+        let rules = parse_rule_collection(collection).unwrap();
+        let titles: Vec<&str> = rules.iter().map(|r| r.title.as_str()).collect();
+        assert_eq!(titles, vec!["First", "Repeated", "Without global"]);
+        assert_eq!(
+            rules[1].logsource.category.as_deref(),
+            Some("process_creation")
+        );
+        assert!(rules[2].logsource.category.is_none());
+
+        let event = Event::from([("Image", "C:\\Windows\\System32\\powershell.exe")]);
+        assert!(!rules[0].is_match(&event));
+        assert!(rules[1].is_match(&event));
+    }
+
+    #[test]
+    fn test_parse_rule_collection_errors() {
+        assert!(matches!(
+            parse_rule_collection("action: repeat\ntitle: Nothing to repeat"),
+            Err(ParserError::RepeatWithoutRule())
+        ));
+        assert!(matches!(
+            parse_rule_collection("action: unknown\ntitle: Unknown"),
+            Err(ParserError::UnsupportedCollectionAction(ref a)) if a == "unknown"
+        ));
+        assert!(matches!(
+            parse_rule_collection("action: global\ntitle: Only global\n---\nlevel: high"),
+            Err(ParserError::YamlParsing(_))
+        ));
+    }
+}
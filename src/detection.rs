@@ -1,23 +1,71 @@
 mod ast;
+mod condition;
 mod lexer;
 
-use crate::detection::ast::Ast;
+use crate::detection::ast::{at_least, Ast};
+pub use crate::detection::condition::CompiledCondition;
 use crate::error::ParserError;
 use crate::event::Event;
+use crate::field::{MatchModifier, RegexLimits};
+use crate::lint::LintWarning;
+use crate::screen::{Screen, Terms};
 use crate::selection::Selection;
 use glob_match::glob_match;
 use serde::Deserialize;
 use serde_yml::Value;
-use std::collections::HashMap;
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, HashSet};
+
+thread_local! {
+    /// Whether conditions parsed on this thread may contain patterns of `1 of` and `all of`
+    /// that match no selection, see [`with_lenient_conditions`]
+    static LENIENT_CONDITIONS: Cell<bool> = const { Cell::new(false) };
+    /// The buffer of selection results reused by [`Detection::evaluate`] on this thread
+    static LOOKUP: RefCell<Vec<Option<bool>>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Restores the leniency of the thread when dropped, also if parsing panics
+struct RestoreLenient(bool);
+
+impl Drop for RestoreLenient {
+    fn drop(&mut self) {
+        LENIENT_CONDITIONS.with(|l| l.set(self.0));
+    }
+}
+
+/// Run `f` allowing unmatched patterns in the conditions of the detections it parses.
+/// Detections are parsed while deserializing a rule, so the flag cannot be passed to them directly.
+pub(crate) fn with_lenient_conditions<T>(f: impl FnOnce() -> T) -> T {
+    let _restore = RestoreLenient(LENIENT_CONDITIONS.with(|l| l.replace(true)));
+    f()
+}
 
 #[derive(Deserialize, Debug)]
 struct DetectionProxy {
     #[serde(flatten)]
     selections: HashMap<String, Value>,
-    condition: String,
+    condition: Option<String>,
+}
+
+/// Simple complexity metrics of a detection, see [`Detection::metrics`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DetectionMetrics {
+    /// The number of selections, including those not used in the condition
+    pub selections: usize,
+    /// The number of fields of all selections
+    pub fields: usize,
+    /// The number of fields with the `contains` modifier
+    pub contains_fields: usize,
+    /// The number of fields with the `re` modifier
+    pub regex_fields: usize,
+    /// The depth of the parsed condition, 1 for a single selection, each `and`, `or`
+    /// and `not` adds a level
+    pub condition_depth: usize,
+    /// Whether the condition contains `all of them`
+    pub uses_all_of_them: bool,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize)]
 #[serde(try_from = "DetectionProxy")]
 pub struct Detection {
     #[serde(flatten)]
@@ -25,6 +73,18 @@ pub struct Detection {
     condition: String,
     #[serde(skip)]
     ast: Ast,
+    #[serde(skip)]
+    screen: Option<Screen>,
+    #[serde(skip)]
+    whole_word_keywords: bool,
+    /// Patterns of `1 of` and `all of` that match no selection, only allowed if the
+    /// detection was created with [`Detection::with_lenient_condition`]
+    #[serde(skip)]
+    unmatched_quantifiers: Vec<String>,
+    /// The index of every selection in the buffer of selection results, see
+    /// [`Detection::evaluate_with`]
+    #[serde(skip)]
+    selection_indices: HashMap<String, usize>,
 }
 
 impl TryFrom<DetectionProxy> for Detection {
@@ -32,8 +92,26 @@ impl TryFrom<DetectionProxy> for Detection {
 
     #[inline(always)]
     fn try_from(other: DetectionProxy) -> Result<Self, Self::Error> {
-        let mut selections = HashMap::with_capacity(other.selections.len());
-        for (name, selection) in other.selections {
+        let condition = other.condition.ok_or(ParserError::MissingCondition())?;
+        // Expand YAML merge keys (`<<: *anchor`), both of whole selections merged into the
+        // detection and of fields merged into a selection. Existing keys take precedence.
+        let mut merged = Value::Mapping(
+            other
+                .selections
+                .into_iter()
+                .map(|(name, selection)| (Value::String(name), selection))
+                .collect(),
+        );
+        merged.apply_merge().map_err(ParserError::YamlParsing)?;
+        let Value::Mapping(merged) = merged else {
+            unreachable!("merging keys keeps the mapping")
+        };
+
+        let mut selections = HashMap::with_capacity(merged.len());
+        for (name, selection) in merged {
+            let Value::String(name) = name else {
+                return Err(ParserError::InvalidFieldName(format!("{:?}", name)));
+            };
             match Selection::try_from(selection) {
                 Ok(selection) => {
                     selections.insert(name, selection);
@@ -48,7 +126,8 @@ impl TryFrom<DetectionProxy> for Detection {
                 }
             }
         }
-        let result = Self::new(selections, other.condition)?;
+        let lenient = LENIENT_CONDITIONS.with(|l| l.get());
+        let result = Self::new_with(selections, condition, lenient)?;
         Ok(result)
     }
 }
@@ -64,22 +143,359 @@ impl Detection {
         &self.condition
     }
 
+    /// The number of selections, including those not used in the condition
+    pub fn selection_count(&self) -> usize {
+        self.selections.len()
+    }
+
+    /// Computes simple complexity metrics of the selections and the condition
+    ///
+    /// # Example
+    /// ```rust
+    /// use sigma_rust::rule_from_yaml;
+    /// let rule = rule_from_yaml(r#"
+    /// title: Some test title
+    /// logsource:
+    ///     category: test
+    /// detection:
+    ///     selection:
+    ///         CommandLine|contains: 'whoami'
+    ///     filter:
+    ///         User|re: '^SYSTEM$'
+    ///     condition: selection and not filter
+    /// "#).unwrap();
+    /// let metrics = rule.detection.metrics();
+    /// assert_eq!(metrics.selections, 2);
+    /// assert_eq!(metrics.contains_fields, 1);
+    /// assert_eq!(metrics.regex_fields, 1);
+    /// assert_eq!(metrics.condition_depth, 3);
+    /// ```
+    pub fn metrics(&self) -> DetectionMetrics {
+        let mut metrics = DetectionMetrics {
+            selections: self.selections.len(),
+            condition_depth: self.ast.depth(),
+            uses_all_of_them: self.ast.contains_all_of_them(),
+            ..Default::default()
+        };
+        for field in self.selections.values().flat_map(|s| s.fields()) {
+            metrics.fields += 1;
+            match field.modifier.match_modifier {
+                Some(MatchModifier::Contains) => metrics.contains_fields += 1,
+                Some(MatchModifier::Re) => metrics.regex_fields += 1,
+                _ => {}
+            }
+        }
+        metrics
+    }
+
+    /// Returns the patterns of all regexes the selections compile, sorted and without
+    /// duplicates, see [`Rule::compiled_patterns`](crate::Rule::compiled_patterns)
+    pub fn compiled_patterns(&self) -> Vec<String> {
+        let mut patterns: Vec<String> = self
+            .selections
+            .values()
+            .flat_map(|s| s.fields())
+            .flat_map(|field| field.compiled_patterns())
+            .collect();
+        patterns.sort();
+        patterns.dedup();
+        patterns
+    }
+
+    /// Returns the fingerprints of all fields of the selections, sorted and without
+    /// duplicates, see [`Rule::field_fingerprints`](crate::Rule::field_fingerprints)
+    pub fn field_fingerprints(&self) -> Vec<u64> {
+        let mut fingerprints: Vec<u64> = self
+            .selections
+            .values()
+            .flat_map(|s| s.fields())
+            .map(|field| field.fingerprint())
+            .collect();
+        fingerprints.sort_unstable();
+        fingerprints.dedup();
+        fingerprints
+    }
+
+    /// If enabled, keywords of keyword selections only match whole words,
+    /// e.g. the keyword `cat` matches `/bin/cat file` but not `certificate`.
+    /// By default, keywords match any substring of an event value.
+    pub fn set_whole_word_keywords(&mut self, enabled: bool) {
+        self.whole_word_keywords = enabled;
+    }
+
+    /// If enabled, booleans are equal to the strings `true` and `false` ignoring case and to
+    /// the numbers `1` and `0`, both as values of the rule and of the event, e.g. the value
+    /// `true` matches the event values `"True"` and `1`.
+    /// By default, booleans are only equal to booleans.
+    pub fn set_coerce_booleans(&mut self, enabled: bool) {
+        for selection in self.selections.values_mut() {
+            selection.set_coerce_booleans(enabled);
+        }
+        // coerced values do not occur literally in the event
+        self.screen = self.required_terms(&self.ast).and_then(Screen::new);
+    }
+
+    /// Returns the condition as an indented tree with one node per line. The contents
+    /// of the selections are shown below their names as the fields with their modifiers
+    /// and the number of values.
+    ///
+    /// # Example
+    /// ```rust
+    /// use sigma_rust::rule_from_yaml;
+    /// let rule = rule_from_yaml(r#"
+    /// title: Some test title
+    /// logsource:
+    ///     category: test
+    /// detection:
+    ///     selection:
+    ///         Image|endswith:
+    ///             - '\cmd.exe'
+    ///             - '\powershell.exe'
+    ///     filter:
+    ///         User: SYSTEM
+    ///     condition: selection and not filter
+    /// "#).unwrap();
+    /// assert_eq!(
+    ///     rule.detection.to_tree_string(),
+    ///     "and\n  selection\n    Image|endswith (2 values)\n  not\n    filter\n      User (1 value)\n"
+    /// );
+    /// ```
+    pub fn to_tree_string(&self) -> String {
+        let mut result = String::new();
+        self.write_tree(&self.ast, 0, &mut result);
+        result
+    }
+
+    fn write_tree(&self, ast: &Ast, depth: usize, out: &mut String) {
+        let indent = "  ".repeat(depth);
+        let write_selections = |names: Vec<&String>, out: &mut String| {
+            let mut names = names;
+            names.sort();
+            for name in names {
+                out.push_str(&format!("{}  {}\n", indent, name));
+                self.selections[name].write_tree(depth + 2, out);
+            }
+        };
+        let matching = |pattern: &str| {
+            self.selections
+                .keys()
+                .filter(|name| glob_match(pattern, name))
+                .collect::<Vec<_>>()
+        };
+
+        match ast {
+            Ast::Selection(s) => {
+                out.push_str(&format!("{}{}\n", indent, s));
+                if let Some(selection) = self.selections.get(s) {
+                    selection.write_tree(depth + 1, out);
+                }
+            }
+            Ast::OneOf(s) | Ast::AllOf(s) | Ast::NOf(_, s) => {
+                out.push_str(&format!("{}{}\n", indent, ast));
+                write_selections(matching(s), out);
+            }
+            Ast::OneOfThem | Ast::AllOfThem | Ast::NOfThem(_) => {
+                out.push_str(&format!("{}{}\n", indent, ast));
+                write_selections(self.selections.keys().collect(), out);
+            }
+            Ast::OneOfList(names) | Ast::AllOfList(names) | Ast::NOfList(_, names) => {
+                out.push_str(&format!("{}{}\n", indent, ast));
+                write_selections(names.iter().collect(), out);
+            }
+            Ast::Not(operand) => {
+                out.push_str(&format!("{}not\n", indent));
+                self.write_tree(operand, depth + 1, out);
+            }
+            Ast::And(left, right) => {
+                out.push_str(&format!("{}and\n", indent));
+                self.write_tree(left, depth + 1, out);
+                self.write_tree(right, depth + 1, out);
+            }
+            Ast::Or(left, right) => {
+                out.push_str(&format!("{}or\n", indent));
+                self.write_tree(left, depth + 1, out);
+                self.write_tree(right, depth + 1, out);
+            }
+        }
+    }
+
+    /// Returns the names of all selections the condition refers to, including those
+    /// matched by the patterns of `1 of` and `all of` and by `them`. A selection that is
+    /// not in the result can never influence whether the detection matches.
+    pub fn reachable_selections(&self) -> HashSet<String> {
+        let mut result = HashSet::new();
+        self.reachable_selections_recursive(&self.ast, &mut result);
+        result
+    }
+
+    /// Returns the names of the selections the condition does not refer to, sorted by name.
+    /// These are usually mistakes, e.g. a filter that was defined but never negated.
+    pub fn unused_selections(&self) -> Vec<String> {
+        let reachable = self.reachable_selections();
+        let mut result: Vec<String> = self
+            .selections
+            .keys()
+            .filter(|name| !reachable.contains(*name))
+            .cloned()
+            .collect();
+        result.sort();
+        result
+    }
+
+    fn reachable_selections_recursive(&self, ast: &Ast, acc: &mut HashSet<String>) {
+        match ast {
+            Ast::Selection(s) => _ = acc.insert(s.clone()),
+            Ast::OneOf(s) | Ast::AllOf(s) | Ast::NOf(_, s) => acc.extend(
+                self.selections
+                    .keys()
+                    .filter(|name| glob_match(s, name))
+                    .cloned(),
+            ),
+            Ast::OneOfThem | Ast::AllOfThem | Ast::NOfThem(_) => {
+                acc.extend(self.selections.keys().cloned())
+            }
+            Ast::OneOfList(names) | Ast::AllOfList(names) | Ast::NOfList(_, names) => {
+                acc.extend(names.iter().cloned())
+            }
+            Ast::Not(operand) => self.reachable_selections_recursive(operand, acc),
+            Ast::And(left, right) | Ast::Or(left, right) => {
+                self.reachable_selections_recursive(left, acc);
+                self.reachable_selections_recursive(right, acc);
+            }
+        }
+    }
+
+    /// Evaluate a single selection against the event regardless of the condition, e.g. to
+    /// find out which selection of a rule does or does not match. Returns `None` if the
+    /// detection has no selection with that name.
+    ///
+    /// # Example
+    /// ```rust
+    /// use sigma_rust::{rule_from_yaml, Event};
+    /// let rule = rule_from_yaml(r#"
+    /// title: Some test title
+    /// logsource:
+    ///     category: test
+    /// detection:
+    ///     selection:
+    ///         Image|endswith: '\cmd.exe'
+    ///     filter:
+    ///         User: SYSTEM
+    ///     condition: selection and not filter
+    /// "#).unwrap();
+    /// let event = Event::from([("Image", "C:\\Windows\\cmd.exe"), ("User", "SYSTEM")]);
+    /// assert!(!rule.is_match(&event));
+    /// assert_eq!(rule.detection.evaluate_selection_by_name("selection", &event), Some(true));
+    /// assert_eq!(rule.detection.evaluate_selection_by_name("filter", &event), Some(true));
+    /// assert_eq!(rule.detection.evaluate_selection_by_name("other", &event), None);
+    /// ```
+    pub fn evaluate_selection_by_name(&self, name: &str, event: &Event) -> Option<bool> {
+        self.selections
+            .get(name)
+            .map(|selection| selection.evaluate(event, self.whole_word_keywords))
+    }
+
+    /// Returns the condition as it was parsed with every operation in parentheses,
+    /// e.g. `a or b and c` is returned as `(a or (b and c))`.
+    ///
+    /// # Example
+    /// ```rust
+    /// use sigma_rust::rule_from_yaml;
+    /// let rule = rule_from_yaml(r#"
+    /// title: Some test title
+    /// logsource:
+    ///     category: test
+    /// detection:
+    ///     a:
+    ///         EventID: 1
+    ///     b:
+    ///         EventID: 2
+    ///     c:
+    ///         User: SYSTEM
+    ///     condition: a or b and not c
+    /// "#).unwrap();
+    /// assert_eq!(rule.detection.normalized_condition(), "(a or (b and not (c)))");
+    /// ```
+    pub fn normalized_condition(&self) -> String {
+        self.ast.to_string()
+    }
+
+    /// Set the limits for compiling and matching the regexes of all selections.
+    /// Returns an error if a `re` pattern exceeds the size limit.
+    pub fn set_regex_limits(&mut self, limits: RegexLimits) -> Result<(), ParserError> {
+        for selection in self.selections.values_mut() {
+            selection.set_regex_limits(limits)?;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn validate_regex_complexity(
+        &self,
+        max_complexity: usize,
+    ) -> Result<(), ParserError> {
+        for selection in self.selections.values() {
+            selection.validate_regex_complexity(max_complexity)?;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn validate_value_variants(&self, max_variants: usize) -> Result<(), ParserError> {
+        for selection in self.selections.values() {
+            selection.validate_value_variants(max_variants)?;
+        }
+        Ok(())
+    }
+
+    /// Create a copy of the detection with the same selections but a different condition.
+    /// Returns an error if the condition is invalid or refers to undefined selections.
+    pub fn with_condition<S: AsRef<str>>(&self, condition: S) -> Result<Self, ParserError> {
+        let mut result = Self::new(self.selections.clone(), condition)?;
+        result.whole_word_keywords = self.whole_word_keywords;
+        Ok(result)
+    }
+
+    /// Like [`Detection::with_condition`] but patterns of `1 of` and `all of` that match no
+    /// selection are allowed. They are reported by [`Detection::lint`] instead.
+    pub fn with_lenient_condition<S: AsRef<str>>(&self, condition: S) -> Result<Self, ParserError> {
+        let mut result = Self::new_with(self.selections.clone(), condition, true)?;
+        result.whole_word_keywords = self.whole_word_keywords;
+        Ok(result)
+    }
+
     #[inline(always)]
     pub(crate) fn new<S: AsRef<str>>(
         selections: HashMap<String, Selection>,
         condition: S,
+    ) -> Result<Self, ParserError> {
+        Self::new_with(selections, condition, false)
+    }
+
+    fn new_with<S: AsRef<str>>(
+        selections: HashMap<String, Selection>,
+        condition: S,
+        allow_unmatched_quantifiers: bool,
     ) -> Result<Self, ParserError> {
         let mut result = Self {
             selections,
             condition: condition.as_ref().into(),
             ast: Ast::default(),
+            screen: None,
+            whole_word_keywords: false,
+            unmatched_quantifiers: vec![],
+            selection_indices: HashMap::new(),
         };
-        result.parse_ast()?;
+        result.parse_ast(allow_unmatched_quantifiers)?;
         Ok(result)
     }
 
+    /// Parse the condition and check that it only refers to defined selections.
+    /// A pattern of `1 of` or `all of` matching no selection is most likely a typo,
+    /// it is an error unless `allow_unmatched_quantifiers` is set.
     #[inline(always)]
-    pub(crate) fn parse_ast(&mut self) -> Result<(), ParserError> {
+    pub(crate) fn parse_ast(
+        &mut self,
+        allow_unmatched_quantifiers: bool,
+    ) -> Result<(), ParserError> {
         let ast = Ast::new(self.condition.as_str())?;
         let identifiers = ast.selections();
 
@@ -93,36 +509,147 @@ impl Detection {
             return Err(ParserError::UndefinedIdentifiers(missing));
         }
 
+        let mut unmatched: Vec<String> = ast
+            .quantifier_patterns()
+            .into_iter()
+            .filter(|p| !self.selections.keys().any(|name| glob_match(p, name)))
+            .map(|p| p.to_string())
+            .collect();
+        unmatched.sort();
+        if !unmatched.is_empty() && !allow_unmatched_quantifiers {
+            return Err(ParserError::UnmatchedQuantifiers(unmatched));
+        }
+        self.unmatched_quantifiers = unmatched;
+
+        self.screen = self.required_terms(&ast).and_then(Screen::new);
         self.ast = ast;
+        self.selection_indices = self
+            .selections
+            .keys()
+            .enumerate()
+            .map(|(i, name)| (name.clone(), i))
+            .collect();
         Ok(())
     }
 
+    /// Collects literal terms of which at least one must occur in an event for the AST to
+    /// evaluate to true. Returns `None` if the AST may evaluate to true without any of them.
+    fn required_terms(&self, ast: &Ast) -> Option<Terms> {
+        let all_of = |names: Vec<&String>| {
+            // every selection must match, so the terms of a single one are sufficient
+            names
+                .into_iter()
+                .find_map(|name| self.selections[name].literal_terms())
+        };
+        let one_of = |names: Vec<&String>| {
+            let mut result = Terms::default();
+            for name in names {
+                result.merge(self.selections[name].literal_terms()?);
+            }
+            Some(result)
+        };
+        let matching = |pattern: &str| {
+            self.selections
+                .keys()
+                .filter(|name| glob_match(pattern, name))
+                .collect::<Vec<_>>()
+        };
+
+        match ast {
+            Ast::Selection(s) => self.selections.get(s)?.literal_terms(),
+            Ast::OneOf(s) => one_of(matching(s)),
+            Ast::OneOfThem => one_of(self.selections.keys().collect()),
+            Ast::AllOf(s) => all_of(matching(s)),
+            Ast::AllOfThem => all_of(self.selections.keys().collect()),
+            Ast::OneOfList(names) => one_of(names.iter().collect()),
+            Ast::AllOfList(names) => all_of(names.iter().collect()),
+            // `0 of` is always true, otherwise any n of the selections may match, so the
+            // terms of all of them are candidates
+            Ast::NOf(0, _) | Ast::NOfThem(0) | Ast::NOfList(0, _) => None,
+            Ast::NOf(_, s) => one_of(matching(s)),
+            Ast::NOfThem(_) => one_of(self.selections.keys().collect()),
+            Ast::NOfList(_, names) => one_of(names.iter().collect()),
+            Ast::Not(_) => None,
+            Ast::Or(left, right) => {
+                let mut result = self.required_terms(left)?;
+                result.merge(self.required_terms(right)?);
+                Some(result)
+            }
+            Ast::And(left, right) => self
+                .required_terms(left)
+                .or_else(|| self.required_terms(right)),
+        }
+    }
+
+    /// Reports possible improvements of the selections, ordered by selection name,
+    /// followed by the selections the condition does not use and the patterns in the
+    /// condition that match no selection
+    pub fn lint(&self) -> Vec<LintWarning> {
+        let mut names: Vec<&String> = self.selections.keys().collect();
+        names.sort();
+        names
+            .into_iter()
+            .flat_map(|name| self.selections[name].lint(name))
+            .chain(
+                self.unused_selections()
+                    .into_iter()
+                    .map(|selection| LintWarning::UnusedSelection { selection }),
+            )
+            .chain(self.unmatched_quantifiers.iter().map(|pattern| {
+                LintWarning::UnmatchedQuantifier {
+                    pattern: pattern.clone(),
+                }
+            }))
+            .collect()
+    }
+
+    /// Cheap pre-filter for [`Detection::evaluate`]. Returns false only if the event
+    /// contains none of the literal strings the detection requires and thus cannot match.
+    #[inline(always)]
+    pub(crate) fn quick_screen(&self, event: &Event) -> bool {
+        match &self.screen {
+            Some(screen) => screen.is_candidate(event),
+            None => true,
+        }
+    }
+
+    /// Evaluate the detection reusing a buffer of the thread for the selection results
     #[inline(always)]
     pub(crate) fn evaluate(&self, event: &Event) -> bool {
-        self.eval(event, &self.ast, &mut HashMap::new())
+        LOOKUP.with(|lookup| match lookup.try_borrow_mut() {
+            Ok(mut lookup) => self.evaluate_with(event, &mut lookup),
+            // the buffer is in use if a custom decoder matches rules during the evaluation
+            Err(_) => self.evaluate_with(event, &mut Vec::new()),
+        })
     }
 
+    /// Evaluate the detection using a caller-owned buffer for the selection results.
+    /// The buffer holds the result of every selection by its index and is reset in place
+    /// before use, so it can be reused across events, and detections, without allocating.
     #[inline(always)]
-    fn evaluate_selection(
-        &self,
-        name: &str,
-        lookup: &mut HashMap<String, bool>,
-        event: &Event,
-    ) -> bool {
-        if let Some(e) = lookup.get(name) {
-            *e
-        } else if let Some(selection) = self.selections.get(name) {
-            let eval = selection.evaluate(event);
-            lookup.insert(name.to_string(), eval);
-            eval
-        } else {
+    pub(crate) fn evaluate_with(&self, event: &Event, lookup: &mut Vec<Option<bool>>) -> bool {
+        lookup.clear();
+        lookup.resize(self.selection_indices.len(), None);
+        self.eval(event, &self.ast, lookup)
+    }
+
+    #[inline(always)]
+    fn evaluate_selection(&self, name: &str, lookup: &mut [Option<bool>], event: &Event) -> bool {
+        let Some(&index) = self.selection_indices.get(name) else {
             // should never happen because we check before evaluate
             // whether all selections in the condition are covered
-            false
+            return false;
+        };
+        if let Some(e) = lookup[index] {
+            e
+        } else {
+            let eval = self.selections[name].evaluate(event, self.whole_word_keywords);
+            lookup[index] = Some(eval);
+            eval
         }
     }
 
-    fn eval(&self, event: &Event, ast: &Ast, lookup: &mut HashMap<String, bool>) -> bool {
+    fn eval(&self, event: &Event, ast: &Ast, lookup: &mut [Option<bool>]) -> bool {
         match ast {
             Ast::Selection(s) => self.evaluate_selection(s, lookup, event),
             Ast::OneOf(s) => self
@@ -147,6 +674,31 @@ impl Detection {
                 .keys()
                 .map(|name| self.evaluate_selection(name, lookup, event))
                 .all(|b| b),
+            Ast::OneOfList(names) => names
+                .iter()
+                .any(|name| self.evaluate_selection(name, lookup, event)),
+            Ast::AllOfList(names) => names
+                .iter()
+                .all(|name| self.evaluate_selection(name, lookup, event)),
+            Ast::NOf(n, s) => at_least(
+                *n,
+                self.selections
+                    .keys()
+                    .filter(|name| glob_match(s, name))
+                    .map(|name| self.evaluate_selection(name, lookup, event)),
+            ),
+            Ast::NOfThem(n) => at_least(
+                *n,
+                self.selections
+                    .keys()
+                    .map(|name| self.evaluate_selection(name, lookup, event)),
+            ),
+            Ast::NOfList(n, names) => at_least(
+                *n,
+                names
+                    .iter()
+                    .map(|name| self.evaluate_selection(name, lookup, event)),
+            ),
             Ast::Not(ref operand) => !self.eval(event, operand, lookup),
             Ast::Or(ref left, ref right) => {
                 self.eval(event, left, lookup) || self.eval(event, right, lookup)
@@ -161,6 +713,7 @@ impl Detection {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::event::EventValue;
 
     #[test]
     fn test_missing_identifier() {
@@ -168,6 +721,40 @@ mod tests {
         assert!(matches!(err, ParserError::UndefinedIdentifiers(_)));
     }
 
+    #[test]
+    fn test_empty_condition() {
+        let detection_yaml = r#"
+    selection:
+        EventID: 4104
+    condition: ""
+"#;
+        let err = serde_yml::from_str::<Detection>(detection_yaml).unwrap_err();
+        let message = err.to_string();
+        assert!(message.contains("The condition is empty"), "{}", message);
+
+        let err = Detection::new(HashMap::new(), " ").unwrap_err();
+        assert!(matches!(err, ParserError::EmptyCondition()));
+    }
+
+    #[test]
+    fn test_missing_condition() {
+        let detection_yaml = r#"
+    selection:
+        EventID: 4104
+"#;
+        let err = serde_yml::from_str::<Detection>(detection_yaml).unwrap_err();
+        let message = err.to_string();
+        assert!(
+            message.contains("The detection has no condition"),
+            "{}",
+            message
+        );
+
+        let proxy: DetectionProxy = serde_yml::from_str(detection_yaml).unwrap();
+        let err = Detection::try_from(proxy).unwrap_err();
+        assert!(matches!(err, ParserError::MissingCondition()));
+    }
+
     #[test]
     fn test_evaluate() {
         let detection_yaml = r#"
@@ -196,6 +783,256 @@ mod tests {
         assert!(!result);
     }
 
+    #[test]
+    fn test_merge_keys() {
+        let rule_yaml = r#"
+title: Merge keys
+logsource:
+    category: process_creation
+x-shared: &shared
+    selection_shell:
+        Image|endswith: '\cmd.exe'
+detection:
+    <<: *shared
+    selection_base: &base
+        User: SYSTEM
+        ParentImage|endswith: '\services.exe'
+    selection_whoami:
+        <<: *base
+        CommandLine|contains: whoami
+    selection_override:
+        <<: *base
+        User: Administrator
+    condition: selection_shell and selection_whoami
+"#;
+        let rule = crate::rule_from_yaml(rule_yaml).unwrap();
+        let detection = &rule.detection;
+        let mut names: Vec<&String> = detection.selections.keys().collect();
+        names.sort();
+        assert_eq!(
+            names,
+            vec![
+                "selection_base",
+                "selection_override",
+                "selection_shell",
+                "selection_whoami"
+            ]
+        );
+
+        let mut event = Event::from([
+            ("Image", "C:\\Windows\\System32\\cmd.exe"),
+            ("ParentImage", "C:\\Windows\\System32\\services.exe"),
+            ("CommandLine", "cmd.exe /c whoami"),
+            ("User", "SYSTEM"),
+        ]);
+        assert!(rule.is_match(&event));
+
+        // the fields merged into the selection must match as well
+        event.insert("ParentImage", "C:\\Windows\\explorer.exe");
+        assert!(!rule.is_match(&event));
+
+        // keys of the selection take precedence over merged keys
+        let event = Event::from([
+            ("ParentImage", "C:\\Windows\\System32\\services.exe"),
+            ("User", "Administrator"),
+        ]);
+        let rule = rule.with_condition("selection_override").unwrap();
+        assert!(rule.is_match(&event));
+    }
+
+    #[test]
+    fn test_to_tree_string() {
+        let detection_yaml = r#"
+    selection_proc:
+        - Image|endswith:
+            - '\cmd.exe'
+            - '\powershell.exe'
+          CommandLine|contains|all:
+            - ' -enc '
+            - ' -nop '
+        - OriginalFileName: 'Cmd.Exe'
+    selection_user:
+        User|cased: SYSTEM
+    keywords:
+        - whoami
+        - net user
+    condition: 1 of selection_* and not (keywords or all of them)
+"#;
+        let detection: Detection = serde_yml::from_str(detection_yaml).unwrap();
+        let expected = "\
+and
+  1 of selection_*
+    selection_proc
+      field group 1
+        Image|endswith (2 values)
+        CommandLine|contains|all (2 values)
+      field group 2
+        OriginalFileName (1 value)
+    selection_user
+      User|cased (1 value)
+  not
+    or
+      keywords
+        keywords (2 values)
+      all of them
+        keywords
+          keywords (2 values)
+        selection_proc
+          field group 1
+            Image|endswith (2 values)
+            CommandLine|contains|all (2 values)
+          field group 2
+            OriginalFileName (1 value)
+        selection_user
+          User|cased (1 value)
+";
+        assert_eq!(detection.to_tree_string(), expected);
+    }
+
+    #[test]
+    fn test_evaluate_selection_by_name() {
+        let detection_yaml = r#"
+    selection:
+        Image|endswith: '\cmd.exe'
+    keywords:
+        - whoami
+    condition: selection and keywords
+"#;
+        let mut detection: Detection = serde_yml::from_str(detection_yaml).unwrap();
+        let event = Event::from([("Image", "C:\\cmd.exe"), ("CommandLine", "nowhoamis")]);
+        assert_eq!(
+            detection.evaluate_selection_by_name("selection", &event),
+            Some(true)
+        );
+        assert_eq!(
+            detection.evaluate_selection_by_name("keywords", &event),
+            Some(true)
+        );
+        assert_eq!(
+            detection.evaluate_selection_by_name("condition", &event),
+            None
+        );
+
+        // the keyword setting of the detection applies
+        detection.set_whole_word_keywords(true);
+        assert_eq!(
+            detection.evaluate_selection_by_name("keywords", &event),
+            Some(false)
+        );
+    }
+
+    #[test]
+    fn test_reachable_selections() {
+        let detection_yaml = r#"
+    selection_proc:
+        Image|endswith: '\cmd.exe'
+    selection_net:
+        DestinationPort: 445
+    filter_user:
+        User: SYSTEM
+    filter_path:
+        Image|startswith: 'C:\Windows\'
+    unused:
+        EventID: 1
+    condition: 1 of selection_* and not (filter_user or all of filter_p*)
+"#;
+        let detection: Detection = serde_yml::from_str(detection_yaml).unwrap();
+        let names = |names: &[&str]| names.iter().map(|n| n.to_string()).collect();
+        assert_eq!(
+            detection.reachable_selections(),
+            names(&[
+                "selection_proc",
+                "selection_net",
+                "filter_user",
+                "filter_path"
+            ])
+        );
+
+        let detection = detection.with_condition("unused").unwrap();
+        assert_eq!(detection.reachable_selections(), names(&["unused"]));
+
+        let detection = detection.with_condition("unused or 1 of them").unwrap();
+        assert_eq!(detection.reachable_selections().len(), 5);
+    }
+
+    #[test]
+    fn test_metrics() {
+        let detection_yaml = r#"
+    selection_proc:
+        - Image|endswith: '\cmd.exe'
+          CommandLine|contains|all:
+              - '/c'
+              - 'whoami'
+        - CommandLine|re: 'who.mi'
+    selection_net:
+        DestinationPort: 445
+        DestinationHostname|contains: 'corp'
+    keywords:
+        - 'mimikatz'
+    filter:
+        User|re: '^SYSTEM$'
+    condition: (selection_proc or (selection_net and keywords)) and not filter
+"#;
+        let detection: Detection = serde_yml::from_str(detection_yaml).unwrap();
+        assert_eq!(detection.selection_count(), 4);
+        assert_eq!(
+            detection.metrics(),
+            DetectionMetrics {
+                selections: 4,
+                fields: 6,
+                contains_fields: 2,
+                regex_fields: 2,
+                // and -> or -> and -> selection_net
+                condition_depth: 4,
+                uses_all_of_them: false,
+            }
+        );
+
+        let detection = detection.with_condition("keywords").unwrap();
+        assert_eq!(detection.metrics().condition_depth, 1);
+        let detection = detection
+            .with_condition("not not (keywords or not all of them)")
+            .unwrap();
+        assert_eq!(detection.metrics().condition_depth, 5);
+        assert!(detection.metrics().uses_all_of_them);
+    }
+
+    #[test]
+    fn test_unused_selections() {
+        let detection_yaml = r#"
+    selection_proc:
+        Image|endswith: '\cmd.exe'
+    selection_net:
+        DestinationPort: 445
+    filter_user:
+        User: SYSTEM
+    filter_path:
+        Image|startswith: 'C:\Windows\'
+    condition: 1 of selection_* and not filter_user
+"#;
+        let detection: Detection = serde_yml::from_str(detection_yaml).unwrap();
+        assert_eq!(detection.unused_selections(), vec!["filter_path"]);
+        assert_eq!(
+            detection.lint(),
+            vec![LintWarning::UnusedSelection {
+                selection: "filter_path".to_string()
+            }]
+        );
+
+        let detection = detection.with_condition("selection_net").unwrap();
+        assert_eq!(
+            detection.unused_selections(),
+            vec!["filter_path", "filter_user", "selection_proc"]
+        );
+
+        let detection = detection
+            .with_condition("1 of (selection_proc, selection_net) and not 1 of filter_*")
+            .unwrap();
+        assert!(detection.unused_selections().is_empty());
+        let detection = detection.with_condition("all of them").unwrap();
+        assert!(detection.unused_selections().is_empty());
+    }
+
     #[test]
     fn test_evaluate_one_all_of_them() {
         let detection_yaml = r#"
@@ -245,7 +1082,9 @@ mod tests {
         let result = detection.evaluate(&event);
         assert!(result);
 
-        let detection = Detection::new(detection.selections, "1 of nothing*").unwrap();
+        let err = Detection::new(detection.selections.clone(), "1 of nothing*").unwrap_err();
+        assert!(matches!(err, ParserError::UnmatchedQuantifiers(ref p) if p == &["nothing*"]));
+        let detection = detection.with_lenient_condition("1 of nothing*").unwrap();
         let result = detection.evaluate(&event);
         assert!(!result);
     }
@@ -276,8 +1115,197 @@ mod tests {
         let result = detection.evaluate(&event);
         assert!(result);
 
-        let detection = Detection::new(detection.selections, "all of nothing*").unwrap();
+        let err = Detection::new(detection.selections.clone(), "all of nothing*").unwrap_err();
+        assert!(matches!(err, ParserError::UnmatchedQuantifiers(ref p) if p == &["nothing*"]));
+        let detection = detection.with_lenient_condition("all of nothing*").unwrap();
         let result = detection.evaluate(&event);
         assert!(result);
+        assert_eq!(
+            detection.lint(),
+            vec![
+                LintWarning::UnusedSelection {
+                    selection: "selection_1".to_string()
+                },
+                LintWarning::UnusedSelection {
+                    selection: "selection_2".to_string()
+                },
+                LintWarning::UnmatchedQuantifier {
+                    pattern: "nothing*".to_string()
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn test_evaluate_list_of() {
+        let detection_yaml = r#"
+    selection_a:
+        EventID: 6416
+    selection_b:
+        Image|endswith: '\cmd.exe'
+    selection_c:
+        EventID: 5555
+    condition: 1 of (selection_a, selection_c) and not all of (selection_b, selection_c)
+"#;
+        let detection: Detection = serde_yml::from_str(detection_yaml).unwrap();
+        let event = Event::from([("EventID", 6416)]);
+        assert!(detection.evaluate(&event));
+
+        let mut event = Event::from([("EventID", 5555)]);
+        event.insert("Image", "C:\\Windows\\System32\\cmd.exe");
+        assert!(!detection.evaluate(&event));
+
+        let event = Event::from([("Image", "C:\\Windows\\System32\\cmd.exe")]);
+        assert!(!detection.evaluate(&event));
+
+        let detection =
+            Detection::new(detection.selections.clone(), "all of (selection_a)").unwrap();
+        assert!(detection.evaluate(&Event::from([("EventID", 6416)])));
+
+        let err =
+            Detection::new(detection.selections.clone(), "1 of (selection_a, other)").unwrap_err();
+        assert!(matches!(err, ParserError::UndefinedIdentifiers(ref i) if i == &["other"]));
+    }
+
+    #[test]
+    fn test_coerce_booleans() {
+        let detection_yaml = r#"
+    selection_bool:
+        Elevated: true
+    selection_str:
+        Signed: 'FALSE'
+    selection_num:
+        Enabled: 1
+    condition: 1 of selection_*
+"#;
+        let mut detection: Detection = serde_yml::from_str(detection_yaml).unwrap();
+        let events = [
+            Event::from([("Elevated", "True")]),
+            Event::from([("Elevated", 1)]),
+            Event::from([("Signed", false)]),
+            Event::from([("Enabled", true)]),
+        ];
+        for event in events.iter() {
+            assert!(!detection.evaluate(event));
+        }
+
+        detection.set_coerce_booleans(true);
+        for event in events.iter() {
+            assert!(detection.evaluate(event), "{:?}", event);
+        }
+        assert!(!detection.evaluate(&Event::from([("Elevated", "yes")])));
+        assert!(!detection.evaluate(&Event::from([("Elevated", 2)])));
+        assert!(!detection.evaluate(&Event::from([("Signed", true)])));
+        assert!(!detection.evaluate(&Event::from([("Enabled", false)])));
+
+        // elements of lists are coerced as well
+        let list = |v: Vec<EventValue>| Event::from([("Signed", EventValue::Sequence(v))]);
+        assert!(detection.evaluate(&list(vec![EventValue::from(false)])));
+        assert!(detection.evaluate(&list(vec![EventValue::from(true), EventValue::from(false)])));
+        assert!(!detection.evaluate(&list(vec![EventValue::from(true)])));
+
+        detection.set_coerce_booleans(false);
+        assert!(!detection.evaluate(&events[0]));
+    }
+
+    #[test]
+    fn test_evaluate_n_of() {
+        let detection_yaml = r#"
+    selection_a:
+        EventID: 6416
+    selection_b:
+        Image|endswith: '\cmd.exe'
+    selection_c:
+        User: SYSTEM
+    condition: 2 of selection_*
+"#;
+        let detection: Detection = serde_yml::from_str(detection_yaml).unwrap();
+        let mut event = Event::from([("EventID", 6416)]);
+        assert!(!detection.evaluate(&event));
+
+        event.insert("Image", "C:\\Windows\\System32\\cmd.exe");
+        assert!(detection.evaluate(&event));
+
+        event.insert("User", "SYSTEM");
+        assert!(detection.evaluate(&event));
+
+        let detection = Detection::new(
+            detection.selections.clone(),
+            "3 of (selection_a, selection_c)",
+        )
+        .unwrap();
+        assert!(!detection.evaluate(&event));
+        let detection = Detection::new(detection.selections.clone(), "3 of them").unwrap();
+        assert!(detection.evaluate(&event));
+        let detection = Detection::new(detection.selections.clone(), "0 of them").unwrap();
+        assert!(detection.evaluate(&Event::default()));
+    }
+
+    #[test]
+    fn test_evaluate_with_reused_lookup() {
+        let detection_yaml = r#"
+    selection_1:
+        EventID: 6416
+    selection_2:
+        EventID: 5555
+    condition: 1 of selection_*
+"#;
+        let detection: Detection = serde_yml::from_str(detection_yaml).unwrap();
+        let mut lookup = vec![];
+
+        let event = Event::from([("EventID", 6416)]);
+        assert!(detection.evaluate_with(&event, &mut lookup));
+        assert!(lookup.contains(&Some(true)));
+        let buffer = lookup.as_ptr();
+
+        // stale results of the previous event must not leak into the next evaluation
+        let event = Event::from([("EventID", 1)]);
+        assert!(!detection.evaluate_with(&event, &mut lookup));
+        assert_eq!(lookup, vec![Some(false); 2]);
+        assert_eq!(lookup.as_ptr(), buffer);
+
+        let event = Event::from([("EventID", 5555)]);
+        assert!(detection.evaluate_with(&event, &mut lookup));
+    }
+
+    #[test]
+    fn test_quick_screen() {
+        let detection_yaml = r#"
+    selection_1:
+        Image|endswith: '\cmd.exe'
+        EventID: 1
+    selection_2:
+        CommandLine|contains|cased: 'Invoke-'
+    filter:
+        User: SYSTEM
+    condition: 1 of selection_* and not filter
+"#;
+        let detection: Detection = serde_yml::from_str(detection_yaml).unwrap();
+        assert!(detection.screen.is_some());
+
+        let event = Event::from([("Image", "C:\\Windows\\CMD.EXE")]);
+        assert!(detection.quick_screen(&event));
+        let event = Event::from([("CommandLine", "invoke-webrequest")]);
+        assert!(!detection.quick_screen(&event));
+        let event = Event::from([("User", "SYSTEM"), ("EventID", "1")]);
+        assert!(!detection.quick_screen(&event));
+
+        // a negated condition does not require any terms, so nothing is screened out
+        let detection = Detection::new(detection.selections, "not filter").unwrap();
+        assert!(detection.screen.is_none());
+        assert!(detection.quick_screen(&Event::from([("a", "b")])));
+
+        // all selections of an or must have terms
+        let detection = Detection::new(detection.selections, "selection_1 or filter").unwrap();
+        assert!(detection.screen.is_some());
+        let mut selections = detection.selections;
+        selections.insert(
+            "numeric".to_string(),
+            serde_yml::from_str("EventID|gt: 4").unwrap(),
+        );
+        let detection = Detection::new(selections, "selection_1 or numeric").unwrap();
+        assert!(detection.screen.is_none());
+        let detection = Detection::new(detection.selections, "selection_1 and numeric").unwrap();
+        assert!(detection.screen.is_some());
     }
 }
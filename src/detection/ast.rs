@@ -29,13 +29,19 @@ impl InfixOperator {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub(crate) enum Ast {
     Selection(String),
     OneOf(String),
     OneOfThem,
     AllOf(String),
     AllOfThem,
+    OneOfList(Vec<String>),
+    AllOfList(Vec<String>),
+    /// At least the given number of the selections match
+    NOf(usize, String),
+    NOfThem(usize),
+    NOfList(usize, Vec<String>),
     Not(Box<Ast>),
     And(Box<Ast>, Box<Ast>),
     Or(Box<Ast>, Box<Ast>),
@@ -49,7 +55,10 @@ impl Default for Ast {
 
 impl Ast {
     pub(crate) fn new(input: &str) -> Result<Self, ParserError> {
-        let mut lexer = Lexer::new(input);
+        let mut lexer = Lexer::new(input)?;
+        if lexer.peek() == Token::End {
+            return Err(ParserError::EmptyCondition());
+        }
         Self::parse_token_stream(&mut lexer, 0)
     }
 
@@ -60,6 +69,11 @@ impl Ast {
             Token::OneOfThem => Self::OneOfThem,
             Token::AllOf(s) => Self::AllOf(s),
             Token::AllOfThem => Self::AllOfThem,
+            Token::OneOfList(names) => Self::OneOfList(names),
+            Token::AllOfList(names) => Self::AllOfList(names),
+            Token::NOf(n, s) => Self::NOf(n, s),
+            Token::NOfThem(n) => Self::NOfThem(n),
+            Token::NOfList(n, names) => Self::NOfList(n, names),
             Token::OpeningParenthesis => {
                 let left = Self::parse_token_stream(lexer, 0)?;
                 if lexer.next() != Token::ClosingParenthesis {
@@ -100,33 +114,107 @@ impl Ast {
         Ok(left)
     }
 
+    /// The selections the condition refers to by name, including those listed explicitly
+    /// in `1 of (a, b)` and `all of (a, b)`
     pub(crate) fn selections(&self) -> HashSet<&str> {
         let mut result: HashSet<&str> = HashSet::new();
         Self::selections_recursive(self, &mut result);
         result
     }
 
+    /// The number of nodes on the longest path from the root to a leaf, e.g. 1 for a single
+    /// selection and 3 for `a and not b`
+    pub(crate) fn depth(&self) -> usize {
+        match self {
+            Self::Not(operand) => 1 + operand.depth(),
+            Self::And(left, right) | Self::Or(left, right) => 1 + left.depth().max(right.depth()),
+            _ => 1,
+        }
+    }
+
+    /// Whether the condition contains `all of them`
+    pub(crate) fn contains_all_of_them(&self) -> bool {
+        match self {
+            Self::AllOfThem => true,
+            Self::Not(operand) => operand.contains_all_of_them(),
+            Self::And(left, right) | Self::Or(left, right) => {
+                left.contains_all_of_them() || right.contains_all_of_them()
+            }
+            _ => false,
+        }
+    }
+
+    /// The glob patterns of `1 of`, `N of` and `all of` quantifiers, e.g. `selection_*`
+    pub(crate) fn quantifier_patterns(&self) -> HashSet<&str> {
+        let mut result: HashSet<&str> = HashSet::new();
+        Self::quantifier_patterns_recursive(self, &mut result);
+        result
+    }
+
+    fn quantifier_patterns_recursive<'a>(current: &'a Self, acc: &mut HashSet<&'a str>) {
+        match current {
+            Self::OneOf(s) | Self::AllOf(s) | Self::NOf(_, s) => _ = acc.insert(s),
+            Self::Not(s) => Self::quantifier_patterns_recursive(s, acc),
+            Self::Or(left, right) | Self::And(left, right) => {
+                Self::quantifier_patterns_recursive(left, acc);
+                Self::quantifier_patterns_recursive(right, acc);
+            }
+            Self::Selection(_)
+            | Self::OneOfThem
+            | Self::AllOfThem
+            | Self::NOfThem(_)
+            | Self::OneOfList(_)
+            | Self::AllOfList(_)
+            | Self::NOfList(_, _) => {}
+        }
+    }
+
     fn selections_recursive<'a>(current: &'a Self, acc: &mut HashSet<&'a str>) {
         match current {
             Self::Selection(s) => _ = acc.insert(s),
+            Self::OneOfList(names) | Self::AllOfList(names) | Self::NOfList(_, names) => {
+                acc.extend(names.iter().map(|name| name.as_str()))
+            }
             Self::Not(s) => Self::selections_recursive(s, acc),
             Self::Or(left, right) | Self::And(left, right) => {
                 Self::selections_recursive(left, acc);
                 Self::selections_recursive(right, acc);
             }
-            Self::OneOf(_) | Self::OneOfThem | Self::AllOf(_) | Self::AllOfThem => {}
+            Self::OneOf(_)
+            | Self::OneOfThem
+            | Self::AllOf(_)
+            | Self::AllOfThem
+            | Self::NOf(_, _)
+            | Self::NOfThem(_) => {}
         }
     }
 }
 
+/// Returns true if at least `n` of the results are true, stops evaluating as soon as
+/// enough of them are. `0 of` is always true.
+pub(crate) fn at_least<I: IntoIterator<Item = bool>>(n: usize, results: I) -> bool {
+    n == 0 || results.into_iter().filter(|b| *b).nth(n - 1).is_some()
+}
+
 impl fmt::Display for Ast {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
+            // quoted, so that the condition can be parsed again
+            Self::Selection(s)
+                if s.contains(|c: char| c.is_whitespace() || "()!&|".contains(c)) =>
+            {
+                write!(f, "\"{}\"", s)
+            }
             Self::Selection(s) => write!(f, "{}", s),
             Self::OneOf(s) => write!(f, "1 of {}", s),
             Self::OneOfThem => write!(f, "1 of them"),
             Self::AllOf(s) => write!(f, "all of {}", s),
             Self::AllOfThem => write!(f, "all of them"),
+            Self::OneOfList(names) => write!(f, "1 of ({})", names.join(", ")),
+            Self::AllOfList(names) => write!(f, "all of ({})", names.join(", ")),
+            Self::NOf(n, s) => write!(f, "{} of {}", n, s),
+            Self::NOfThem(n) => write!(f, "{} of them", n),
+            Self::NOfList(n, names) => write!(f, "{} of ({})", n, names.join(", ")),
             Self::Not(a) => write!(f, "not ({})", a),
             Self::And(a, b) => write!(f, "({} and {})", a, b),
             Self::Or(a, b) => write!(f, "({} or {})", a, b),
@@ -171,6 +259,70 @@ mod tests {
         assert_eq!(ast.to_string(), "((a and not (b)) or not (not (c)))");
     }
 
+    #[test]
+    fn test_symbolic_operators() {
+        let ast = Ast::new("a||b&&!c").unwrap();
+        assert_eq!(ast.to_string(), "(a or (b and not (c)))");
+    }
+
+    #[test]
+    fn test_parse_list_of() {
+        let ast = Ast::new("1 of (a, b) and not all of (c,d) or e").unwrap();
+        assert_eq!(
+            ast.to_string(),
+            "((1 of (a, b) and not (all of (c, d))) or e)"
+        );
+        assert_eq!(ast.selections(), HashSet::from(["a", "b", "c", "d", "e"]));
+        assert!(ast.quantifier_patterns().is_empty());
+    }
+
+    #[test]
+    fn test_empty_condition() {
+        for condition in ["", "  ", "\n\t", "# only a comment"] {
+            let err = Ast::new(condition).unwrap_err();
+            assert!(
+                matches!(err, ParserError::EmptyCondition()),
+                "{:?}",
+                condition
+            );
+        }
+        let err = Ast::new("()").unwrap_err();
+        assert!(matches!(err, ParserError::UnexpectedToken(ref t) if t == ")"));
+    }
+
+    #[test]
+    fn test_parse_quoted_selection() {
+        let ast = Ast::new(r#""my selection" and not "filter (old)" or x"#).unwrap();
+        assert_eq!(
+            ast.to_string(),
+            r#"(("my selection" and not ("filter (old)")) or x)"#
+        );
+        assert_eq!(
+            ast.selections(),
+            HashSet::from(["my selection", "filter (old)", "x"])
+        );
+        let reparsed = Ast::new(&ast.to_string()).unwrap();
+        assert_eq!(reparsed.to_string(), ast.to_string());
+
+        let ast = Ast::new(r#""a # b" and "c||d""#).unwrap();
+        assert_eq!(ast.to_string(), r#"("a # b" and "c||d")"#);
+        assert_eq!(ast.selections(), HashSet::from(["a # b", "c||d"]));
+
+        let err = Ast::new(r#"a or "b"#).unwrap_err();
+        assert!(matches!(err, ParserError::MissingClosingQuote(_)));
+    }
+
+    #[test]
+    fn test_parse_n_of() {
+        let ast = Ast::new("2 of sel_* and not 3 of them or 2 of (a, b, c)").unwrap();
+        assert_eq!(
+            ast.to_string(),
+            "((2 of sel_* and not (3 of them)) or 2 of (a, b, c))"
+        );
+        assert_eq!(ast.selections(), HashSet::from(["a", "b", "c"]));
+        assert_eq!(ast.quantifier_patterns(), HashSet::from(["sel_*"]));
+    }
+
     #[test]
     fn test_mismatching_parentheses() {
         let err = Ast::new("x and ( y or z ").unwrap_err();
@@ -182,6 +334,7 @@ mod tests {
         let ast = Ast::new("x1 and x2 or x3 and 1 of x4* or all of x5* or x1").unwrap();
         let identifiers = ast.selections();
         assert_eq!(identifiers, HashSet::from(["x1", "x2", "x3"]));
+        assert_eq!(ast.quantifier_patterns(), HashSet::from(["x4*", "x5*"]));
     }
 
     #[test]
@@ -0,0 +1,138 @@
+use crate::detection::ast::{at_least, Ast};
+use crate::error::ParserError;
+use glob_match::glob_match;
+use std::collections::{HashMap, HashSet};
+
+/// A condition parsed once and evaluated against the results of its selections,
+/// independent of events and rules. Useful for backends that evaluate the selections
+/// themselves, e.g. in batches, and only need the boolean logic of the condition.
+///
+/// # Example
+/// ```rust
+/// use sigma_rust::CompiledCondition;
+/// use std::collections::{HashMap, HashSet};
+/// let known = HashSet::from(["selection_a".to_string(), "selection_b".to_string(), "filter".to_string()]);
+/// let condition = CompiledCondition::new("1 of selection_* and not filter", &known).unwrap();
+/// let results = HashMap::from([("selection_b".to_string(), true), ("filter".to_string(), false)]);
+/// assert!(condition.eval(&results));
+/// ```
+#[derive(Debug, Clone)]
+pub struct CompiledCondition {
+    ast: Ast,
+    selections: Vec<String>,
+}
+
+impl CompiledCondition {
+    /// Parse the condition and check that it only refers to the known selections.
+    /// Patterns of `1 of` and `all of` are expanded against the known selections and
+    /// must match at least one of them.
+    pub fn new(condition: &str, selections: &HashSet<String>) -> Result<Self, ParserError> {
+        let ast = Ast::new(condition)?;
+
+        let mut missing: Vec<String> = ast
+            .selections()
+            .into_iter()
+            .filter(|s| !selections.contains(*s))
+            .map(|s| s.to_string())
+            .collect();
+        if !missing.is_empty() {
+            missing.sort();
+            return Err(ParserError::UndefinedIdentifiers(missing));
+        }
+
+        let mut unmatched: Vec<String> = ast
+            .quantifier_patterns()
+            .into_iter()
+            .filter(|p| !selections.iter().any(|name| glob_match(p, name)))
+            .map(|p| p.to_string())
+            .collect();
+        if !unmatched.is_empty() {
+            unmatched.sort();
+            return Err(ParserError::UnmatchedQuantifiers(unmatched));
+        }
+
+        let mut selections: Vec<String> = selections.iter().cloned().collect();
+        selections.sort();
+        Ok(Self { ast, selections })
+    }
+
+    /// Evaluate the condition with the given results of the selections.
+    /// A selection without a result is treated as not matching.
+    pub fn eval(&self, results: &HashMap<String, bool>) -> bool {
+        self.eval_ast(&self.ast, results)
+    }
+
+    fn eval_ast(&self, ast: &Ast, results: &HashMap<String, bool>) -> bool {
+        let result = |name: &str| results.get(name).copied().unwrap_or_default();
+        match ast {
+            Ast::Selection(s) => result(s),
+            Ast::OneOf(s) => self.matching(s).any(|name| result(name)),
+            Ast::OneOfThem => self.selections.iter().any(|name| result(name)),
+            Ast::AllOf(s) => self.matching(s).all(|name| result(name)),
+            Ast::AllOfThem => self.selections.iter().all(|name| result(name)),
+            Ast::OneOfList(names) => names.iter().any(|name| result(name)),
+            Ast::AllOfList(names) => names.iter().all(|name| result(name)),
+            Ast::NOf(n, s) => at_least(*n, self.matching(s).map(|name| result(name))),
+            Ast::NOfThem(n) => at_least(*n, self.selections.iter().map(|name| result(name))),
+            Ast::NOfList(n, names) => at_least(*n, names.iter().map(|name| result(name))),
+            Ast::Not(operand) => !self.eval_ast(operand, results),
+            Ast::Or(left, right) => self.eval_ast(left, results) || self.eval_ast(right, results),
+            Ast::And(left, right) => self.eval_ast(left, results) && self.eval_ast(right, results),
+        }
+    }
+
+    fn matching<'a>(&'a self, pattern: &'a str) -> impl Iterator<Item = &'a String> {
+        self.selections
+            .iter()
+            .filter(move |name| glob_match(pattern, name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn known(names: &[&str]) -> HashSet<String> {
+        names.iter().map(|n| n.to_string()).collect()
+    }
+
+    fn results(matching: &[&str]) -> HashMap<String, bool> {
+        matching.iter().map(|n| (n.to_string(), true)).collect()
+    }
+
+    #[test]
+    fn test_eval_many_results() {
+        let names = known(&["sel_proc", "sel_net", "filter_user", "filter_path"]);
+        let condition = CompiledCondition::new("1 of sel_* and not 1 of filter_*", &names).unwrap();
+        assert!(condition.eval(&results(&["sel_net"])));
+        assert!(condition.eval(&results(&["sel_proc", "sel_net"])));
+        assert!(!condition.eval(&results(&["sel_proc", "filter_path"])));
+        assert!(!condition.eval(&results(&[])));
+
+        let condition = CompiledCondition::new("all of sel_* or all of them", &names).unwrap();
+        assert!(condition.eval(&results(&["sel_proc", "sel_net"])));
+        assert!(!condition.eval(&results(&["sel_proc", "filter_user", "filter_path"])));
+        assert!(condition.eval(&results(&[
+            "sel_proc",
+            "sel_net",
+            "filter_user",
+            "filter_path"
+        ])));
+
+        // explicit false results are the same as missing ones
+        let mut r = results(&["sel_proc"]);
+        r.insert("sel_net".to_string(), false);
+        assert!(!condition.eval(&r));
+    }
+
+    #[test]
+    fn test_invalid_condition() {
+        let names = known(&["selection", "filter"]);
+        let err = CompiledCondition::new("selection and not other", &names).unwrap_err();
+        assert!(matches!(err, ParserError::UndefinedIdentifiers(ref i) if i == &["other"]));
+        let err = CompiledCondition::new("1 of sel_*", &names).unwrap_err();
+        assert!(matches!(err, ParserError::UnmatchedQuantifiers(ref p) if p == &["sel_*"]));
+        let err = CompiledCondition::new("selection and (filter", &names).unwrap_err();
+        assert!(matches!(err, ParserError::MissingClosingParenthesis()));
+    }
+}
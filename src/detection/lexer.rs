@@ -1,3 +1,5 @@
+use crate::error::ParserError;
+use std::borrow::Cow;
 use std::fmt;
 use std::fmt::Display;
 
@@ -11,8 +13,13 @@ pub(crate) enum Token {
     ClosingParenthesis,
     OneOf(String),
     AllOf(String),
+    OneOfList(Vec<String>),
+    AllOfList(Vec<String>),
+    NOf(usize, String),
+    NOfList(usize, Vec<String>),
     OneOfThem,
     AllOfThem,
+    NOfThem(usize),
     End,
 }
 
@@ -27,8 +34,13 @@ impl Display for Token {
             Self::ClosingParenthesis => write!(f, ")"),
             Self::OneOf(ref s) => write!(f, "1 of {}", s),
             Self::AllOf(ref s) => write!(f, "all of {}", s),
+            Self::OneOfList(ref names) => write!(f, "1 of ({})", names.join(", ")),
+            Self::AllOfList(ref names) => write!(f, "all of ({})", names.join(", ")),
+            Self::NOf(n, ref s) => write!(f, "{} of {}", n, s),
+            Self::NOfList(n, ref names) => write!(f, "{} of ({})", n, names.join(", ")),
             Self::OneOfThem => write!(f, "1 of them"),
             Self::AllOfThem => write!(f, "all them"),
+            Self::NOfThem(n) => write!(f, "{} of them", n),
             Self::End => write!(f, "<END>"),
         }
     }
@@ -37,6 +49,7 @@ impl Display for Token {
 enum Quantifier {
     One,
     All(String),
+    Count(usize),
 }
 
 impl Display for Quantifier {
@@ -44,6 +57,7 @@ impl Display for Quantifier {
         match self {
             Self::One => write!(f, "1"),
             Self::All(case) => write!(f, "{}", case),
+            Self::Count(n) => write!(f, "{}", n),
         }
     }
 }
@@ -52,10 +66,10 @@ pub(crate) struct Lexer {
 }
 
 impl Lexer {
-    pub(crate) fn new(input: &str) -> Self {
-        let mut tokens = Self::tokenize(input);
+    pub(crate) fn new(input: &str) -> Result<Self, ParserError> {
+        let mut tokens = Self::tokenize(input)?;
         tokens.reverse();
-        Self { tokens }
+        Ok(Self { tokens })
     }
 
     #[inline(always)]
@@ -67,17 +81,35 @@ impl Lexer {
         self.tokens.last().cloned().unwrap_or(Token::End)
     }
 
-    fn tokenize(input: &str) -> Vec<Token> {
+    fn tokenize(input: &str) -> Result<Vec<Token>, ParserError> {
+        let input = Self::strip_comments(input);
+        let input = Self::replace_symbolic_operators(&input);
+        let input = input.as_ref();
         let mut tokens: Vec<Token> = Vec::new();
 
         let mut start = 0_usize;
 
         let mut quanitifer: Option<Quantifier> = None;
         let mut of_keyword = false;
+        // the end of an explicit list of selections, e.g. `1 of (a, b)`, that was already consumed
+        let mut skip_to = 0_usize;
         let input_len = input.len();
 
-        for (i, char) in input.chars().enumerate() {
-            let is_last = i == input_len - 1;
+        for (i, char) in input.char_indices() {
+            if i < skip_to {
+                continue;
+            }
+            // a double-quoted selection name may contain whitespace and parentheses
+            if char == '"' && start == i && quanitifer.is_none() {
+                let Some(close) = input[i + 1..].find('"') else {
+                    return Err(ParserError::MissingClosingQuote(input[i..].to_string()));
+                };
+                tokens.push(Token::Selection(input[i + 1..i + 1 + close].to_string()));
+                skip_to = i + close + 2;
+                start = skip_to;
+                continue;
+            }
+            let is_last = i + char.len_utf8() == input_len;
             let is_whitespace = char.is_ascii_whitespace();
             let is_opening_parenthesis = char == '(';
             let is_closing_parenthesis = char == ')';
@@ -92,11 +124,28 @@ impl Lexer {
             // if we didn't continue, we are either at a space, a parenthesis or at the last character
 
             let end = if is_last && !is_whitespace && !is_parenthesis {
-                i + 1
+                input_len
             } else {
                 i
             };
 
+            if is_opening_parenthesis && quanitifer.is_some() {
+                let word = input[start..end].to_lowercase();
+                let after_of = (of_keyword && word.is_empty()) || (!of_keyword && word == "of");
+                let list = Self::selection_list(&input[i..]).filter(|_| after_of);
+                if let Some((names, len)) = list {
+                    tokens.push(match quanitifer.take() {
+                        Some(Quantifier::One) => Token::OneOfList(names),
+                        Some(Quantifier::Count(n)) => Token::NOfList(n, names),
+                        _ => Token::AllOfList(names),
+                    });
+                    of_keyword = false;
+                    skip_to = i + len;
+                    start = skip_to;
+                    continue;
+                }
+            }
+
             if let Some(q) = &quanitifer {
                 if !of_keyword && input[start..end].to_lowercase() == "of" {
                     of_keyword = true;
@@ -121,6 +170,10 @@ impl Lexer {
                 ")" => tokens.push(Token::ClosingParenthesis),
                 "1" => quanitifer = Some(Quantifier::One),
                 "all" => quanitifer = Some(Quantifier::All(input[start..end].to_string())),
+                // other counts than 1, written without leading zeros or signs
+                c if !of_keyword && c.parse::<usize>().is_ok_and(|n| n.to_string() == c) => {
+                    quanitifer = c.parse().ok().map(Quantifier::Count)
+                }
                 c if quanitifer.is_some() && of_keyword => {
                     match quanitifer {
                         Some(Quantifier::One) => {
@@ -137,6 +190,13 @@ impl Lexer {
                                 tokens.push(Token::AllOf(input[start..end].to_string()));
                             }
                         }
+                        Some(Quantifier::Count(n)) => {
+                            if c == "them" {
+                                tokens.push(Token::NOfThem(n));
+                            } else {
+                                tokens.push(Token::NOf(n, input[start..end].to_string()));
+                            }
+                        }
                         None => {}
                     }
 
@@ -152,7 +212,122 @@ impl Lexer {
             }
             start = i + 1;
         }
-        tokens
+        Ok(tokens)
+    }
+
+    /// Parses an explicit list of selection names at the start of the input, e.g. `(a, b)`,
+    /// and returns the names with the length of the list including the parentheses.
+    /// Returns `None` if the input does not start with such a list, e.g. for `(a or b)`.
+    fn selection_list(input: &str) -> Option<(Vec<String>, usize)> {
+        let close = input.find(')')?;
+        let names: Vec<String> = input[1..close]
+            .split(',')
+            .map(|name| name.trim().to_string())
+            .collect();
+        if names
+            .iter()
+            .any(|name| name.is_empty() || name.contains(|c: char| c.is_whitespace() || c == '('))
+        {
+            return None;
+        }
+        Some((names, close + 1))
+    }
+
+    /// Replace the operators `&&`, `||` and `!` of other rule languages with `and`, `or` and
+    /// `not`. They are separated by spaces so they need no surrounding whitespace, like
+    /// parentheses. A `!` within brackets is kept, it negates a character class of a pattern.
+    /// Operators within double-quoted selection names are kept as well.
+    fn replace_symbolic_operators(input: &str) -> Cow<'_, str> {
+        if !input.contains(['&', '|', '!']) {
+            return Cow::Borrowed(input);
+        }
+        let mut result = String::with_capacity(input.len() + 8);
+        let mut chars = input.chars().peekable();
+        let mut in_brackets = false;
+        let mut quotes = Quotes::default();
+        while let Some(c) = chars.next() {
+            if quotes.next(c) {
+                result.push(c);
+                continue;
+            }
+            match c {
+                '[' => in_brackets = true,
+                ']' => in_brackets = false,
+                _ => {}
+            }
+            match (c, chars.peek()) {
+                ('&', Some('&')) => {
+                    chars.next();
+                    result.push_str(" and ");
+                }
+                ('|', Some('|')) => {
+                    chars.next();
+                    result.push_str(" or ");
+                }
+                ('!', _) if !in_brackets => result.push_str(" not "),
+                _ => result.push(c),
+            }
+        }
+        Cow::Owned(result)
+    }
+
+    /// Conditions do not support comments, but some authors annotate them anyway.
+    /// A `#` at the start of a line or after whitespace starts a comment up to the end
+    /// of the line, a `#` within a selection name, e.g. `selection#1` or `"a # b"`, is kept.
+    fn strip_comments(input: &str) -> Cow<'_, str> {
+        if !input.contains('#') {
+            return Cow::Borrowed(input);
+        }
+        let mut result = String::with_capacity(input.len());
+        let mut quotes = Quotes::default();
+        let mut in_comment = false;
+        for c in input.chars() {
+            if in_comment && c != '\n' {
+                continue;
+            }
+            in_comment = false;
+            let after_whitespace = quotes.previous.is_whitespace();
+            if !quotes.next(c) && c == '#' && after_whitespace {
+                in_comment = true;
+                continue;
+            }
+            result.push(c);
+        }
+        Cow::Owned(result)
+    }
+}
+
+/// Tracks whether the characters of a condition are within a double-quoted selection name.
+/// Like in [`Lexer::tokenize`], a quote only opens a name at the start of a word, quotes
+/// within a name, e.g. `a"b`, are part of it.
+struct Quotes {
+    quoted: bool,
+    /// The previous character of the condition
+    previous: char,
+}
+
+impl Default for Quotes {
+    fn default() -> Self {
+        Self {
+            quoted: false,
+            previous: ' ',
+        }
+    }
+}
+
+impl Quotes {
+    /// Returns true if the character is a quote or within quotes
+    fn next(&mut self, c: char) -> bool {
+        let starts_word = self.previous.is_whitespace() || matches!(self.previous, '(' | ')' | '!');
+        if self.quoted || (c == '"' && starts_word) {
+            if c == '"' {
+                self.quoted = !self.quoted;
+            }
+            self.previous = c;
+            return true;
+        }
+        self.previous = c;
+        false
     }
 }
 
@@ -162,15 +337,15 @@ mod tests {
 
     #[test]
     fn test_tokenize_empty() {
-        assert_eq!(Lexer::tokenize(""), vec![]);
-        assert_eq!(Lexer::tokenize(" "), vec![]);
-        assert_eq!(Lexer::tokenize("   "), vec![]);
+        assert_eq!(Lexer::tokenize("").unwrap(), vec![]);
+        assert_eq!(Lexer::tokenize(" ").unwrap(), vec![]);
+        assert_eq!(Lexer::tokenize("   ").unwrap(), vec![]);
     }
 
     #[test]
     fn test_tokenize_and_or() {
         let input = " selection_1 and selection_2   OR  selection_3 ";
-        let tokens = Lexer::tokenize(input);
+        let tokens = Lexer::tokenize(input).unwrap();
         assert_eq!(
             tokens,
             vec![
@@ -186,7 +361,7 @@ mod tests {
     #[test]
     fn test_tokenize_1_of() {
         let input = "selection_1 and 1 OF ms*";
-        let tokens = Lexer::tokenize(input);
+        let tokens = Lexer::tokenize(input).unwrap();
         assert_eq!(
             tokens,
             vec![
@@ -208,17 +383,17 @@ mod tests {
             Token::Or,
             Token::AllOfThem,
         ];
-        let tokens = Lexer::tokenize("( selection_1 and selection_2 ) or all of them");
+        let tokens = Lexer::tokenize("( selection_1 and selection_2 ) or all of them").unwrap();
         assert_eq!(tokens, expected);
 
-        let tokens = Lexer::tokenize("( selection_1 and selection_2 ) or all   of   them");
+        let tokens = Lexer::tokenize("( selection_1 and selection_2 ) or all   of   them").unwrap();
         assert_eq!(tokens, expected);
     }
 
     #[test]
     fn test_tokenize_wrong_all_of() {
         let input = "( selection_1 and selection_2 ) or aLL   oof thEm";
-        let tokens = Lexer::tokenize(input);
+        let tokens = Lexer::tokenize(input).unwrap();
         assert_eq!(
             tokens,
             vec![
@@ -238,7 +413,7 @@ mod tests {
     #[test]
     fn test_tokenize_no_spaces_around_parenthesis() {
         let input = "(selection_1 and selection_2) or all of them";
-        let tokens = Lexer::tokenize(input);
+        let tokens = Lexer::tokenize(input).unwrap();
         assert_eq!(
             tokens,
             vec![
@@ -256,7 +431,7 @@ mod tests {
     #[test]
     fn test_tokenize_long_expression() {
         let input = " write TargetLogonId from selection1 (if not selection2)";
-        let tokens = Lexer::tokenize(input);
+        let tokens = Lexer::tokenize(input).unwrap();
         assert_eq!(
             tokens,
             vec![
@@ -272,4 +447,163 @@ mod tests {
             ]
         );
     }
+
+    #[test]
+    fn test_tokenize_list_of() {
+        let names = |n: &[&str]| n.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        assert_eq!(
+            Lexer::tokenize("1 of (selection_a, selection_b) and not all of(filter_1,filter_2)")
+                .unwrap(),
+            vec![
+                Token::OneOfList(names(&["selection_a", "selection_b"])),
+                Token::And,
+                Token::Not,
+                Token::AllOfList(names(&["filter_1", "filter_2"])),
+            ]
+        );
+        assert_eq!(
+            Lexer::tokenize("(ALL OF ( a ) or b)").unwrap(),
+            vec![
+                Token::OpeningParenthesis,
+                Token::AllOfList(names(&["a"])),
+                Token::Or,
+                Token::Selection("b".to_string()),
+                Token::ClosingParenthesis,
+            ]
+        );
+        assert_eq!(
+            Lexer::tokenize("1 of (auswahl_ä, b) or c_ü").unwrap(),
+            vec![
+                Token::OneOfList(names(&["auswahl_ä", "b"])),
+                Token::Or,
+                Token::Selection("c_ü".to_string()),
+            ]
+        );
+        // not a list of selection names
+        assert_eq!(
+            Lexer::tokenize("1 of (a or b)").unwrap()[0],
+            Token::OpeningParenthesis
+        );
+    }
+
+    #[test]
+    fn test_tokenize_n_of() {
+        assert_eq!(
+            Lexer::tokenize("2 of selection_* and not 3 OF them or 10 of (a, b)").unwrap(),
+            vec![
+                Token::NOf(2, "selection_*".to_string()),
+                Token::And,
+                Token::Not,
+                Token::NOfThem(3),
+                Token::Or,
+                Token::NOfList(10, vec!["a".to_string(), "b".to_string()]),
+            ]
+        );
+        // numbers not followed by of are selection names
+        assert_eq!(
+            Lexer::tokenize("2 and 02 of x").unwrap(),
+            vec![
+                Token::Selection("2".to_string()),
+                Token::And,
+                Token::Selection("02".to_string()),
+                Token::Selection("of".to_string()),
+                Token::Selection("x".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tokenize_quoted_selection() {
+        assert_eq!(
+            Lexer::tokenize(r#""my selection" and not ("filter (old)" or other)"#).unwrap(),
+            vec![
+                Token::Selection("my selection".to_string()),
+                Token::And,
+                Token::Not,
+                Token::OpeningParenthesis,
+                Token::Selection("filter (old)".to_string()),
+                Token::Or,
+                Token::Selection("other".to_string()),
+                Token::ClosingParenthesis,
+            ]
+        );
+        // quotes within a name are part of it
+        assert_eq!(
+            Lexer::tokenize(r#"a"b c"#).unwrap(),
+            vec![
+                Token::Selection("a\"b".to_string()),
+                Token::Selection("c".to_string()),
+            ]
+        );
+        // comments and symbolic operators are not recognized within quotes
+        assert_eq!(
+            Lexer::tokenize(r#""a # b" or "c || !d" # comment"#).unwrap(),
+            vec![
+                Token::Selection("a # b".to_string()),
+                Token::Or,
+                Token::Selection("c || !d".to_string()),
+            ]
+        );
+        assert_eq!(
+            Lexer::tokenize("(\"a # b\")\n# \"comment\"\nor c").unwrap(),
+            vec![
+                Token::OpeningParenthesis,
+                Token::Selection("a # b".to_string()),
+                Token::ClosingParenthesis,
+                Token::Or,
+                Token::Selection("c".to_string()),
+            ]
+        );
+
+        let err = Lexer::tokenize(r#"a and "b or c"#).unwrap_err();
+        assert!(matches!(err, ParserError::MissingClosingQuote(ref s) if s == "\"b or c"));
+    }
+
+    #[test]
+    fn test_tokenize_comments() {
+        assert_eq!(
+            Lexer::tokenize("selection # foo").unwrap(),
+            vec![Token::Selection("selection".to_string())]
+        );
+        assert_eq!(Lexer::tokenize("# only a comment").unwrap(), vec![]);
+
+        let input = "selection_1 # the process\nand not filter#1 # known good\n# end";
+        assert_eq!(
+            Lexer::tokenize(input).unwrap(),
+            vec![
+                Token::Selection("selection_1".to_string()),
+                Token::And,
+                Token::Not,
+                Token::Selection("filter#1".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tokenize_symbolic_operators() {
+        let expected = vec![
+            Token::OpeningParenthesis,
+            Token::Selection("a".to_string()),
+            Token::Or,
+            Token::Selection("b".to_string()),
+            Token::ClosingParenthesis,
+            Token::And,
+            Token::Not,
+            Token::Selection("c".to_string()),
+        ];
+        assert_eq!(Lexer::tokenize("(a||b)&&!c").unwrap(), expected);
+        assert_eq!(Lexer::tokenize("( a || b ) && ! c").unwrap(), expected);
+        assert_eq!(Lexer::tokenize("(a or b) and not c").unwrap(), expected);
+
+        assert_eq!(
+            Lexer::tokenize("!!a&&1 of sel_[!x]*").unwrap(),
+            vec![
+                Token::Not,
+                Token::Not,
+                Token::Selection("a".to_string()),
+                Token::And,
+                Token::OneOf("sel_[!x]*".to_string()),
+            ]
+        );
+    }
 }
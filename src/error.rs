@@ -19,12 +19,23 @@ pub enum ParserError {
     RegexParsing(fancy_regex::Error),
     // RegexParsing(regex::Error),
 
+    #[error(
+        "The regular expression '{0}' references no field, it must contain a named capture group"
+    )]
+    RegexWithoutFieldReference(String),
+
     #[error("The 'exists' modifier must not be combined with any other modifiers")]
     ExistsNotStandalone(),
 
     #[error("The 'exists' modifier requires a single boolean value")]
     InvalidValueForExists(),
 
+    #[error("The 'date' modifier must be combined with one of the modifiers gt, gte, lt or lte")]
+    DateWithoutComparison(),
+
+    #[error("Failed to parse date '{0}'")]
+    DateParsing(String),
+
     #[error(
         "The modifier '{0}' must not be combined with other modifiers except 'all' and 'fieldref'"
     )]
@@ -36,9 +47,21 @@ pub enum ParserError {
     #[error("Provided YAML is not a valid field representation: '{0}'")]
     InvalidYAML(String),
 
+    #[error("The number '{0}' is not a valid field value")]
+    InvalidNumber(String),
+
+    #[error("The condition is empty")]
+    EmptyCondition(),
+
+    #[error("The detection has no condition")]
+    MissingCondition(),
+
     #[error("Missing closing parenthesis in condition")]
     MissingClosingParenthesis(),
 
+    #[error("Missing closing quote of selection name '{0}' in condition")]
+    MissingClosingQuote(String),
+
     #[error("Encountered unexpected token '{0}' in condition")]
     UnexpectedToken(String),
 
@@ -57,6 +80,34 @@ pub enum ParserError {
     #[error("The modifiers contains, startswith and endswith must be used with string values, got: '{0}'"
     )]
     InvalidValueForStringModifier(String),
+
+    #[error("Rule contains unknown keys: '{0:?}'")]
+    UnknownRuleKeys(Vec<String>),
+
+    #[error("The regular expression '{0}' has a complexity of {1} which exceeds the maximum of {2}"
+    )]
+    RegexTooComplex(String, usize, usize),
+
+    #[error("Rule collection documents with 'action: {0}' are not supported")]
+    UnsupportedCollectionAction(String),
+
+    #[error("The 'repeat' action of a rule collection requires a preceding rule")]
+    RepeatWithoutRule(),
+
+    #[error("The patterns '{0:?}' of '1 of' or 'all of' in the condition match no selection")]
+    UnmatchedQuantifiers(Vec<String>),
+
+    #[error("The modifiers of field '{0}' generate more than {1} values")]
+    TooManyValueVariants(String, usize),
+
+    #[error("Failed to parse YAML: '{0}'")]
+    YamlParsing(serde_yml::Error),
+
+    #[error("Invalid timeframe '{0}', expected a positive number followed by s, m, h or d")]
+    InvalidTimeframe(String),
+
+    #[error("Failed to read rule file: '{0}'")]
+    ReadingFile(std::io::Error),
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -1,5 +1,7 @@
+use crate::error::ParserError;
 use crate::field::FieldValue;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
 use std::hash::Hash;
 
 #[cfg(feature = "serde_json")]
@@ -9,13 +11,29 @@ struct EventProxy {
     value: serde_json::Value,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(PartialEq)]
 pub enum EventValue {
     Value(FieldValue),
     Sequence(Vec<EventValue>),
     Map(HashMap<String, EventValue>),
 }
 
+/// Formats maps with their keys sorted, so that the output is reproducible
+impl fmt::Debug for EventValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Value(v) => f.debug_tuple("Value").field(v).finish(),
+            Self::Sequence(seq) => f.debug_tuple("Sequence").field(seq).finish(),
+            Self::Map(m) => f.debug_tuple("Map").field(&sorted(m)).finish(),
+        }
+    }
+}
+
+/// The entries of the map sorted by key
+fn sorted(map: &HashMap<String, EventValue>) -> BTreeMap<&String, &EventValue> {
+    map.iter().collect()
+}
+
 #[cfg(feature = "serde_json")]
 impl TryFrom<serde_json::Value> for EventValue {
     type Error = crate::error::JSONError;
@@ -44,16 +62,79 @@ impl TryFrom<serde_json::Value> for EventValue {
     }
 }
 
+impl TryFrom<serde_yml::Value> for EventValue {
+    type Error = ParserError;
+
+    fn try_from(value: serde_yml::Value) -> Result<Self, Self::Error> {
+        match value {
+            serde_yml::Value::Sequence(seq) => {
+                let mut result = Vec::with_capacity(seq.len());
+                for item in seq {
+                    result.push(Self::try_from(item)?);
+                }
+                Ok(Self::Sequence(result))
+            }
+            serde_yml::Value::Mapping(data) => {
+                let mut result = HashMap::with_capacity(data.len());
+                for (key, value) in data {
+                    let serde_yml::Value::String(key) = key else {
+                        return Err(ParserError::InvalidFieldName(format!("{:?}", key)));
+                    };
+                    result.insert(key, Self::try_from(value)?);
+                }
+                Ok(Self::Map(result))
+            }
+            serde_yml::Value::Tagged(tagged) => Self::try_from(tagged.value),
+            _ => Ok(Self::Value(FieldValue::try_from(value)?)),
+        }
+    }
+}
+
 impl EventValue {
-    pub(crate) fn contains(&self, s: &str) -> bool {
+    /// Returns true if the value contains any of the lowercase keywords ignoring case, the
+    /// value is lowercased once for all keywords. If `whole_words` is set, an occurrence
+    /// starting or ending with a word character must not be adjacent to another word character.
+    pub(crate) fn contains_keyword(&self, keywords: &[String], whole_words: bool) -> bool {
         match self {
-            Self::Value(v) => v.value_to_string().contains(s),
-            Self::Sequence(seq) => seq.iter().any(|v| v.contains(s)),
-            Self::Map(m) => m.values().any(|v| v.contains(s)),
+            Self::Value(v) => {
+                let haystack = v.value_to_string().to_lowercase();
+                if whole_words {
+                    keywords.iter().any(|kw| contains_word(&haystack, kw))
+                } else {
+                    keywords.iter().any(|kw| haystack.contains(kw.as_str()))
+                }
+            }
+            Self::Sequence(seq) => seq
+                .iter()
+                .any(|v| v.contains_keyword(keywords, whole_words)),
+            Self::Map(m) => m
+                .values()
+                .any(|v| v.contains_keyword(keywords, whole_words)),
         }
     }
 }
 
+#[inline(always)]
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+fn contains_word(haystack: &str, word: &str) -> bool {
+    let (Some(first), Some(last)) = (word.chars().next(), word.chars().last()) else {
+        return true;
+    };
+    haystack.match_indices(word).any(|(i, _)| {
+        let joined_before = haystack[..i].chars().next_back().is_some_and(is_word_char);
+        let joined_after = haystack[i + word.len()..]
+            .chars()
+            .next()
+            .is_some_and(is_word_char);
+        let splits_word_start = is_word_char(first) && joined_before;
+        let splits_word_end = is_word_char(last) && joined_after;
+        !splits_word_start && !splits_word_end
+    })
+}
+
 impl<T> From<T> for EventValue
 where
     T: Into<FieldValue>,
@@ -63,16 +144,41 @@ where
     }
 }
 
+/// The default separator for accessing nested fields, e.g. `User.Name`
+const DEFAULT_SEPARATOR: char = '.';
+
 /// The `Event` struct represents a log event.
 ///
 /// It is a collection of key-value pairs
 /// where the key is a string and the value is a string, number, or boolean
 /// The value may also be `None` to represent a null value.
-#[derive(Debug, Default)]
+///
+/// The fields are stored in a hash map for fast lookups while matching, so the order of
+/// [`Event::iter`] and [`Event::values`] is unspecified. Use [`Event::iter_sorted`] for a
+/// reproducible order, the `Debug` output is sorted by key as well.
 #[cfg_attr(feature = "serde_json", derive(serde::Deserialize))]
 #[cfg_attr(feature = "serde_json", serde(try_from = "EventProxy"))]
 pub struct Event {
     inner: HashMap<String, EventValue>,
+    separator: char,
+}
+
+impl fmt::Debug for Event {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Event")
+            .field("inner", &sorted(&self.inner))
+            .field("separator", &self.separator)
+            .finish()
+    }
+}
+
+impl Default for Event {
+    fn default() -> Self {
+        Self {
+            inner: HashMap::new(),
+            separator: DEFAULT_SEPARATOR,
+        }
+    }
 }
 
 #[cfg(feature = "serde_json")]
@@ -94,7 +200,10 @@ where
         for (k, v) in values {
             data.insert(k.into(), v.into());
         }
-        Self { inner: data }
+        Self {
+            inner: data,
+            separator: DEFAULT_SEPARATOR,
+        }
     }
 }
 
@@ -124,20 +233,110 @@ impl Event {
         self.inner.insert(key.into(), value.into());
     }
 
+    /// Remove a key from the event and return its value.
+    ///
+    /// Only top-level keys are removed, the key is not split at the separator, i.e.
+    /// removing `User.Name` removes a key named `User.Name` but not the field `Name`
+    /// nested in `User`.
+    ///
+    /// # Example
+    /// ```rust
+    /// use sigma_rust::Event;
+    /// let mut event = Event::from([("name", "John Doe"), ("User.Name", "Chuck")]);
+    /// assert!(event.remove("name").is_some());
+    /// assert!(event.remove("name").is_none());
+    /// assert!(event.remove("User.Name").is_some());
+    /// ```
+    pub fn remove(&mut self, key: &str) -> Option<EventValue> {
+        self.inner.remove(key)
+    }
+
+    /// Merge the top-level keys of another event into this event.
+    /// Values of keys present in both events are replaced by the values of `other`,
+    /// nested maps are not merged recursively. The separator of this event is kept.
+    ///
+    /// # Example
+    /// ```rust
+    /// use sigma_rust::Event;
+    /// let mut event = Event::from([("Image", "cmd.exe"), ("User", "Chuck")]);
+    /// event.merge(Event::from([("User", "Norris"), ("Host", "srv01")]));
+    /// assert_eq!(event.get_str("Image"), Some("cmd.exe"));
+    /// assert_eq!(event.get_str("User"), Some("Norris"));
+    /// assert_eq!(event.get_str("Host"), Some("srv01"));
+    /// ```
+    pub fn merge(&mut self, other: Event) {
+        self.inner.extend(other.inner);
+    }
+
     /// Iterate over the key-value pairs in the event
     pub fn iter(&self) -> impl Iterator<Item = (&String, &EventValue)> {
         self.inner.iter()
     }
 
+    /// Iterate over the top-level keys of the event, nested maps are not descended into
+    pub fn keys(&self) -> impl Iterator<Item = &String> {
+        self.inner.keys()
+    }
+
+    /// The number of top-level keys in the event
+    ///
+    /// # Example
+    /// ```rust
+    /// use sigma_rust::Event;
+    /// let event = Event::from([("Image", "cmd.exe"), ("User", "Chuck")]);
+    /// assert_eq!(event.len(), 2);
+    /// assert!(!event.is_empty());
+    /// assert!(Event::new().is_empty());
+    /// ```
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    /// Whether the event has no keys
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
+    /// Iterate over the key-value pairs in the event sorted by key.
+    /// Unlike [`Event::iter`] the order is deterministic, at the cost of sorting the keys.
+    pub fn iter_sorted(&self) -> impl Iterator<Item = (&String, &EventValue)> {
+        sorted(&self.inner).into_iter()
+    }
+
+    /// Set the separator used to access nested fields, the default is `.`.
+    ///
+    /// # Example
+    /// ```rust
+    /// # #[cfg(feature = "serde_json")]
+    /// # {
+    /// use sigma_rust::event_from_json;
+    /// let mut event = event_from_json(r#"{"User": {"Name": "Chuck"}}"#).unwrap();
+    /// assert!(event.get("User.Name").is_some());
+    /// event.set_separator('/');
+    /// assert!(event.get("User/Name").is_some());
+    /// assert!(event.get("/User/Name").is_some());
+    /// assert!(event.get("User.Name").is_none());
+    /// # }
+    /// ```
+    pub fn set_separator(&mut self, separator: char) {
+        self.separator = separator;
+    }
+
     /// Get the value for a key in the event
+    ///
+    /// Nested fields are accessed by joining the keys with the separator of the event.
+    /// A leading separator, as in JSON pointers like `/User/Name`, is ignored.
     pub fn get(&self, key: &str) -> Option<&EventValue> {
         if let Some(ev) = self.inner.get(key) {
             return Some(ev);
         }
 
-        let mut nested_key = key;
+        let mut nested_key = key.strip_prefix(self.separator).unwrap_or(key);
+        if let Some(ev) = self.inner.get(nested_key) {
+            return Some(ev);
+        }
         let mut current = &self.inner;
-        while let Some((head, tail)) = nested_key.split_once('.') {
+        while let Some((head, tail)) = nested_key.split_once(self.separator) {
             if let Some(EventValue::Map(map)) = current.get(head) {
                 if let Some(value) = map.get(tail) {
                     return Some(value);
@@ -151,9 +350,294 @@ impl Event {
         None
     }
 
+    /// Get the values for a nested key of which a parent is a list of maps, e.g. `Hashes.value`
+    /// of the event `{"Hashes": [{"type": "SHA256", "value": "..."}]}` yields the value of
+    /// every map in the list. Elements that are not maps or lack the key are skipped.
+    pub(crate) fn get_all(&self, key: &str) -> Vec<&EventValue> {
+        fn collect<'a>(
+            map: &'a HashMap<String, EventValue>,
+            key: &str,
+            separator: char,
+            result: &mut Vec<&'a EventValue>,
+        ) {
+            if let Some(value) = map.get(key) {
+                result.push(value);
+                return;
+            }
+            let Some((head, tail)) = key.split_once(separator) else {
+                return;
+            };
+            match map.get(head) {
+                Some(EventValue::Map(nested)) => collect(nested, tail, separator, result),
+                Some(EventValue::Sequence(seq)) => {
+                    for element in seq {
+                        if let EventValue::Map(nested) = element {
+                            collect(nested, tail, separator, result);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        let key = key.strip_prefix(self.separator).unwrap_or(key);
+        let mut result = vec![];
+        collect(&self.inner, key, self.separator, &mut result);
+        result
+    }
+
+    /// Get the value for a nested key of which a parent is a string containing a JSON
+    /// object, e.g. `Details.user` of the event `{"Details": "{\"user\": \"bob\"}"}`.
+    /// Strings that are not valid JSON objects are skipped.
+    #[cfg(feature = "serde_json")]
+    pub(crate) fn get_json(&self, key: &str) -> Option<EventValue> {
+        let key = key.strip_prefix(self.separator).unwrap_or(key);
+        for (i, _) in key.match_indices(self.separator) {
+            let Some(json) = self.get_str(&key[..i]) else {
+                continue;
+            };
+            let Some(mut decoded) = serde_json::from_str::<serde_json::Value>(json)
+                .ok()
+                .and_then(|v| Self::try_from(v).ok())
+            else {
+                continue;
+            };
+            decoded.separator = self.separator;
+
+            let tail = &key[i + self.separator.len_utf8()..];
+            let value = match decoded.get(tail) {
+                Some(_) => decoded.take(tail),
+                // the decoded object may contain JSON strings itself
+                None => decoded.get_json(tail),
+            };
+            if value.is_some() {
+                return value;
+            }
+        }
+        None
+    }
+
+    /// Like [`Event::get`] but moves the value out of the event
+    #[cfg(feature = "serde_json")]
+    fn take(&mut self, key: &str) -> Option<EventValue> {
+        if let Some(ev) = self.inner.remove(key) {
+            return Some(ev);
+        }
+        let mut current = &mut self.inner;
+        let mut nested_key = key;
+        while let Some((head, tail)) = nested_key.split_once(self.separator) {
+            let Some(EventValue::Map(map)) = current.get_mut(head) else {
+                return None;
+            };
+            if let Some(value) = map.remove(tail) {
+                return Some(value);
+            }
+            current = map;
+            nested_key = tail;
+        }
+        None
+    }
+
+    /// Get the value for a key if it is a string
+    pub fn get_str(&self, key: &str) -> Option<&str> {
+        match self.get(key) {
+            Some(EventValue::Value(FieldValue::String(s))) => Some(s.as_str()),
+            _ => None,
+        }
+    }
+
+    /// Get the value for a key if it is an integer that fits into an `i64`
+    pub fn get_i64(&self, key: &str) -> Option<i64> {
+        match self.get(key) {
+            Some(EventValue::Value(FieldValue::Int(i))) => Some(*i),
+            Some(EventValue::Value(FieldValue::Unsigned(u))) => i64::try_from(*u).ok(),
+            _ => None,
+        }
+    }
+
+    /// Get the value for a key if it is a number, integers are converted to `f64`
+    pub fn get_f64(&self, key: &str) -> Option<f64> {
+        match self.get(key) {
+            Some(EventValue::Value(FieldValue::Float(f))) => Some(*f),
+            Some(EventValue::Value(FieldValue::Int(i))) => Some(*i as f64),
+            Some(EventValue::Value(FieldValue::Unsigned(u))) => Some(*u as f64),
+            _ => None,
+        }
+    }
+
+    /// Get the value for a key if it is a boolean
+    pub fn get_bool(&self, key: &str) -> Option<bool> {
+        match self.get(key) {
+            Some(EventValue::Value(FieldValue::Boolean(b))) => Some(*b),
+            _ => None,
+        }
+    }
+
     pub fn values(&self) -> impl Iterator<Item = &EventValue> {
         self.inner.values()
     }
+
+    /// Visit the values of the event with their full keys. Nested maps are flattened by
+    /// joining the keys with the separator of the event, e.g. `User.Name`, and if `into_lists`
+    /// is set elements of lists by their index, e.g. `Args.0`. Empty maps have no values.
+    fn walk<'a, F: FnMut(String, &'a EventValue)>(&'a self, into_lists: bool, visit: &mut F) {
+        fn walk_value<'a, F: FnMut(String, &'a EventValue)>(
+            value: &'a EventValue,
+            key: String,
+            separator: char,
+            into_lists: bool,
+            visit: &mut F,
+        ) {
+            match value {
+                EventValue::Map(map) => {
+                    for (k, v) in map {
+                        let key = format!("{}{}{}", key, separator, k);
+                        walk_value(v, key, separator, into_lists, visit);
+                    }
+                }
+                EventValue::Sequence(seq) if into_lists => {
+                    for (i, v) in seq.iter().enumerate() {
+                        let key = format!("{}{}{}", key, separator, i);
+                        walk_value(v, key, separator, into_lists, visit);
+                    }
+                }
+                _ => visit(key, value),
+            }
+        }
+        for (key, value) in self.inner.iter() {
+            walk_value(value, key.clone(), self.separator, into_lists, visit);
+        }
+    }
+
+    /// All values of the event with their full keys, nested maps are flattened by joining
+    /// the keys with the separator of the event, e.g. `User.Name`. Sorted by key.
+    fn flattened(&self) -> Vec<(String, &EventValue)> {
+        let mut result = vec![];
+        self.walk(false, &mut |key, value| result.push((key, value)));
+        result.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+        result
+    }
+
+    /// All scalar values of the event with their full keys, e.g. for logging or for simple
+    /// matchers. Nested maps are flattened by joining the keys with the separator of the event
+    /// like they are accessed with [`Event::get`], elements of lists by their index, e.g.
+    /// `User.Name.First` and `Args.0`. Empty maps and lists have no entries.
+    ///
+    /// # Example
+    /// ```rust
+    /// # #[cfg(feature = "serde_json")]
+    /// # {
+    /// use sigma_rust::event_from_json;
+    /// let event = event_from_json(r#"{"Image": "cmd.exe", "Args": ["/c", "whoami"]}"#).unwrap();
+    /// let flat = event.flatten();
+    /// assert_eq!(flat.len(), 3);
+    /// assert_eq!(flat["Args.1"], "whoami".into());
+    /// # }
+    /// ```
+    pub fn flatten(&self) -> HashMap<String, FieldValue> {
+        let mut result = HashMap::new();
+        self.walk(true, &mut |key, value| {
+            if let EventValue::Value(v) = value {
+                result.insert(key, v.clone());
+            }
+        });
+        result
+    }
+
+    /// Iterate over the keys starting with the given prefix, e.g. `Data` for `Data1`, `Data2`.
+    /// Keys of nested maps are flattened like they are accessed with [`Event::get`], so
+    /// `RegistryValue.` yields e.g. `RegistryValue.Name`. The keys are sorted.
+    ///
+    /// # Example
+    /// ```rust
+    /// use sigma_rust::Event;
+    /// let event = Event::from([("Data2", "b"), ("Data1", "a"), ("Image", "c")]);
+    /// let keys: Vec<String> = event.keys_with_prefix("Data").collect();
+    /// assert_eq!(keys, vec!["Data1", "Data2"]);
+    /// ```
+    pub fn keys_with_prefix<'a>(&'a self, prefix: &'a str) -> impl Iterator<Item = String> + 'a {
+        self.values_with_prefix(prefix).map(|(key, _)| key)
+    }
+
+    /// Iterate over the keys starting with the given prefix together with their values,
+    /// see [`Event::keys_with_prefix`].
+    pub fn values_with_prefix<'a>(
+        &'a self,
+        prefix: &'a str,
+    ) -> impl Iterator<Item = (String, &'a EventValue)> + 'a {
+        self.flattened()
+            .into_iter()
+            .filter(move |(key, _)| key.starts_with(prefix))
+    }
+}
+
+/// A collection of named events, e.g. a process creation event and the event of its parent.
+///
+/// Fields of the events are accessed with the slot name as prefix, so that a rule can
+/// refer to and compare fields of different events, e.g. `parent.Image` or
+/// `Image|fieldref: parent.Image`. The separator of the events added is not preserved,
+/// nested fields are always accessed with `.`.
+#[derive(Debug, Default)]
+pub struct MultiEvent {
+    inner: Event,
+}
+
+impl From<HashMap<String, Event>> for MultiEvent {
+    fn from(events: HashMap<String, Event>) -> Self {
+        let mut result = Self::new();
+        for (slot, event) in events {
+            result.insert(slot, event);
+        }
+        result
+    }
+}
+
+impl MultiEvent {
+    /// Create a new multi event without any slots
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Insert an event into the given slot. An existing event in the slot will be replaced.
+    pub fn insert<S: Into<String>>(&mut self, slot: S, event: Event) {
+        self.inner.insert(slot.into(), EventValue::Map(event.inner));
+    }
+
+    /// Get the value for a key prefixed with the slot name, e.g. `parent.Image`
+    pub fn get(&self, key: &str) -> Option<&EventValue> {
+        self.inner.get(key)
+    }
+
+    /// The combined event in which every slot is a nested field
+    pub fn as_event(&self) -> &Event {
+        &self.inner
+    }
+}
+
+impl TryFrom<serde_yml::Value> for Event {
+    type Error = ParserError;
+
+    /// Create an event from a YAML mapping, e.g. for test fixtures written in YAML.
+    ///
+    /// # Example
+    /// ```rust
+    /// use sigma_rust::Event;
+    /// let yaml: serde_yml::Value = serde_yml::from_str(r#"
+    /// Image: C:\Windows\System32\cmd.exe
+    /// User:
+    ///     Name: Chuck
+    /// "#).unwrap();
+    /// let event = Event::try_from(yaml).unwrap();
+    /// assert_eq!(event.get_str("User.Name"), Some("Chuck"));
+    /// ```
+    fn try_from(data: serde_yml::Value) -> Result<Self, Self::Error> {
+        match EventValue::try_from(data)? {
+            EventValue::Map(inner) => Ok(Self {
+                inner,
+                separator: DEFAULT_SEPARATOR,
+            }),
+            other => Err(ParserError::InvalidYAML(format!("{:?}", other))),
+        }
+    }
 }
 
 #[cfg(feature = "serde_json")]
@@ -161,19 +645,139 @@ impl TryFrom<serde_json::Value> for Event {
     type Error = crate::error::JSONError;
 
     fn try_from(data: serde_json::Value) -> Result<Self, Self::Error> {
-        let mut result = Self::default();
         match data {
-            serde_json::Value::Object(data) => {
-                for (key, value) in data {
-                    result.insert(key, EventValue::try_from(value)?);
-                }
-            }
-            _ => return Err(Self::Error::InvalidEvent()),
+            serde_json::Value::Object(data) => Self::try_from(data),
+            _ => Err(Self::Error::InvalidEvent()),
+        }
+    }
+}
+
+#[cfg(feature = "serde_json")]
+impl TryFrom<serde_json::Map<String, serde_json::Value>> for Event {
+    type Error = crate::error::JSONError;
+
+    fn try_from(data: serde_json::Map<String, serde_json::Value>) -> Result<Self, Self::Error> {
+        let mut result = Self::default();
+        for (key, value) in data {
+            result.insert(key, EventValue::try_from(value)?);
         }
         Ok(result)
     }
 }
 
+#[cfg(feature = "serde_json")]
+impl TryFrom<HashMap<String, serde_json::Value>> for Event {
+    type Error = crate::error::JSONError;
+
+    fn try_from(data: HashMap<String, serde_json::Value>) -> Result<Self, Self::Error> {
+        let mut result = Self::default();
+        for (key, value) in data {
+            result.insert(key, EventValue::try_from(value)?);
+        }
+        Ok(result)
+    }
+}
+
+/// An event value deserialized without discarding duplicate keys of objects.
+/// The values of a key occurring more than once are collected in a sequence
+/// in the order they appear in the source.
+#[cfg(feature = "serde_json")]
+struct RawEventValue(EventValue);
+
+#[cfg(feature = "serde_json")]
+impl<'de> serde::Deserialize<'de> for RawEventValue {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(RawEventValueVisitor).map(Self)
+    }
+}
+
+#[cfg(feature = "serde_json")]
+struct RawEventValueVisitor;
+
+#[cfg(feature = "serde_json")]
+impl<'de> serde::de::Visitor<'de> for RawEventValueVisitor {
+    type Value = EventValue;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a JSON value")
+    }
+
+    fn visit_bool<E>(self, v: bool) -> Result<Self::Value, E> {
+        Ok(EventValue::from(v))
+    }
+
+    fn visit_i64<E>(self, v: i64) -> Result<Self::Value, E> {
+        Ok(EventValue::from(v))
+    }
+
+    fn visit_u64<E>(self, v: u64) -> Result<Self::Value, E> {
+        // like serde_json, only integers exceeding i64 are unsigned
+        Ok(match i64::try_from(v) {
+            Ok(i) => EventValue::from(i),
+            Err(_) => EventValue::Value(FieldValue::Unsigned(v)),
+        })
+    }
+
+    fn visit_f64<E>(self, v: f64) -> Result<Self::Value, E> {
+        Ok(EventValue::from(v))
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E> {
+        Ok(EventValue::from(v))
+    }
+
+    fn visit_string<E>(self, v: String) -> Result<Self::Value, E> {
+        Ok(EventValue::from(v))
+    }
+
+    fn visit_unit<E>(self) -> Result<Self::Value, E> {
+        Ok(EventValue::Value(FieldValue::Null))
+    }
+
+    fn visit_seq<A: serde::de::SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut result = vec![];
+        while let Some(RawEventValue(value)) = seq.next_element()? {
+            result.push(value);
+        }
+        Ok(EventValue::Sequence(result))
+    }
+
+    fn visit_map<A: serde::de::MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut entries: HashMap<String, Vec<EventValue>> = HashMap::new();
+        while let Some((key, RawEventValue(value))) = map.next_entry::<String, RawEventValue>()? {
+            entries.entry(key).or_default().push(value);
+        }
+        let result = entries
+            .into_iter()
+            .map(|(key, mut values)| {
+                let value = match values.len() {
+                    1 => values.remove(0),
+                    _ => EventValue::Sequence(values),
+                };
+                (key, value)
+            })
+            .collect();
+        Ok(EventValue::Map(result))
+    }
+}
+
+#[cfg(feature = "serde_json")]
+impl Event {
+    /// Parse an event from a JSON string, the values of duplicate keys are collected in a list
+    pub(crate) fn from_json_raw(json: &str) -> Result<Self, serde_json::Error> {
+        let RawEventValue(value) = serde_json::from_str(json)?;
+        match value {
+            EventValue::Map(inner) => Ok(Self {
+                inner,
+                separator: DEFAULT_SEPARATOR,
+            }),
+            _ => Err(<serde_json::Error as serde::de::Error>::custom(
+                crate::error::JSONError::InvalidEvent(),
+            )),
+        }
+    }
+}
+
 #[cfg(feature = "serde_json")]
 #[cfg(test)]
 mod tests {
@@ -205,4 +809,307 @@ mod tests {
             })
         );
     }
+
+    #[test]
+    fn test_load_from_json_map() {
+        let mut map = serde_json::Map::new();
+        map.insert("name".to_string(), json!("John Doe"));
+        map.insert("address".to_string(), json!({"city": "New York"}));
+        let event = Event::try_from(map).unwrap();
+        assert_eq!(event.inner["name"], EventValue::from("John Doe"));
+        assert_eq!(event.get_str("address.city"), Some("New York"));
+
+        let mut map = HashMap::new();
+        map.insert("age".to_string(), json!(43));
+        map.insert("tags".to_string(), json!(["a", "b"]));
+        let event = Event::try_from(map).unwrap();
+        assert_eq!(event.inner["age"], EventValue::from(43));
+        assert_eq!(
+            event.inner["tags"],
+            EventValue::Sequence(vec![EventValue::from("a"), EventValue::from("b")])
+        );
+    }
+
+    #[test]
+    fn test_typed_getters() {
+        let event: Event = json!({
+            "name": "John Doe",
+            "age": 43,
+            "big": u64::MAX,
+            "score": 1.5,
+            "admin": true,
+            "address": {
+                "city": "New York",
+                "zip": 10001
+            }
+        })
+        .try_into()
+        .unwrap();
+
+        assert_eq!(event.get_str("name"), Some("John Doe"));
+        assert_eq!(event.get_str("address.city"), Some("New York"));
+        assert_eq!(event.get_str("age"), None);
+        assert_eq!(event.get_str("missing"), None);
+
+        assert_eq!(event.get_i64("age"), Some(43));
+        assert_eq!(event.get_i64("address.zip"), Some(10001));
+        assert_eq!(event.get_i64("big"), None);
+        assert_eq!(event.get_i64("score"), None);
+        assert_eq!(event.get_i64("name"), None);
+
+        assert_eq!(event.get_f64("score"), Some(1.5));
+        assert_eq!(event.get_f64("age"), Some(43.0));
+        assert_eq!(event.get_f64("admin"), None);
+
+        assert_eq!(event.get_bool("admin"), Some(true));
+        assert_eq!(event.get_bool("name"), None);
+        assert_eq!(event.get_bool("address"), None);
+    }
+
+    #[test]
+    fn test_keys_with_prefix() {
+        let mut event: Event = json!({
+            "Data1": "first",
+            "Data2": "second",
+            "DataCount": 2,
+            "Image": "C:\\Windows\\regedit.exe",
+            "RegistryValue": {
+                "Name": "Run",
+                "Data": {
+                    "Type": "REG_SZ",
+                    "Value": "evil.exe"
+                }
+            }
+        })
+        .try_into()
+        .unwrap();
+
+        let keys: Vec<String> = event.keys_with_prefix("Data").collect();
+        assert_eq!(keys, vec!["Data1", "Data2", "DataCount"]);
+
+        let values: Vec<(String, &EventValue)> =
+            event.values_with_prefix("RegistryValue.").collect();
+        assert_eq!(
+            values,
+            vec![
+                (
+                    "RegistryValue.Data.Type".to_string(),
+                    &EventValue::from("REG_SZ")
+                ),
+                (
+                    "RegistryValue.Data.Value".to_string(),
+                    &EventValue::from("evil.exe")
+                ),
+                ("RegistryValue.Name".to_string(), &EventValue::from("Run")),
+            ]
+        );
+        assert_eq!(event.keys_with_prefix("").count(), 7);
+        assert_eq!(event.keys_with_prefix("data").count(), 0);
+
+        event.set_separator('/');
+        assert_eq!(event.keys_with_prefix("RegistryValue/Data/").count(), 2);
+    }
+
+    #[test]
+    fn test_load_from_yaml() {
+        let yaml: serde_yml::Value = serde_yml::from_str(
+            r#"
+            Image: C:\Windows\System32\cmd.exe
+            EventID: 4688
+            Elevated: true
+            Parent: ~
+            Ratio: 0.5
+            User:
+                Name: Chuck
+                Groups:
+                    - Administrators
+                    - Users
+                Logon:
+                    Id: 0x3e7
+            "#,
+        )
+        .unwrap();
+        let event = Event::try_from(yaml).unwrap();
+
+        assert_eq!(
+            event.inner["Image"],
+            EventValue::from("C:\\Windows\\System32\\cmd.exe")
+        );
+        assert_eq!(event.get_i64("EventID"), Some(4688));
+        assert_eq!(event.get_bool("Elevated"), Some(true));
+        assert_eq!(event.inner["Parent"], EventValue::from(None));
+        assert_eq!(event.get_f64("Ratio"), Some(0.5));
+        assert_eq!(event.get_str("User.Name"), Some("Chuck"));
+        assert_eq!(event.get_i64("User.Logon.Id"), Some(999));
+        assert_eq!(
+            event.get("User.Groups"),
+            Some(&EventValue::Sequence(vec![
+                EventValue::from("Administrators"),
+                EventValue::from("Users")
+            ]))
+        );
+
+        let yaml: serde_yml::Value = serde_yml::from_str("- a\n- b").unwrap();
+        assert!(matches!(
+            Event::try_from(yaml),
+            Err(ParserError::InvalidYAML(_))
+        ));
+        let yaml: serde_yml::Value = serde_yml::from_str("1: a").unwrap();
+        assert!(matches!(
+            Event::try_from(yaml),
+            Err(ParserError::InvalidFieldName(_))
+        ));
+    }
+
+    #[test]
+    fn test_deterministic_order() {
+        let keys: Vec<String> = (0..50).map(|i| format!("key{:02}", i)).collect();
+        let mut forward = Event::new();
+        for key in keys.iter() {
+            forward.insert(key.as_str(), key.as_str());
+        }
+        let mut backward = Event::new();
+        for key in keys.iter().rev() {
+            backward.insert(key.as_str(), key.as_str());
+        }
+
+        let sorted: Vec<&String> = forward.iter_sorted().map(|(k, _)| k).collect();
+        assert_eq!(sorted, keys.iter().collect::<Vec<_>>());
+        assert!(forward.iter_sorted().eq(backward.iter_sorted()));
+        assert_eq!(format!("{:?}", forward), format!("{:?}", backward));
+
+        let event: Event = json!({"b": {"y": 2, "x": [1, "a"]}, "a": null})
+            .try_into()
+            .unwrap();
+        assert_eq!(
+            format!("{:?}", event),
+            "Event { inner: {\"a\": Value(Null), \"b\": Map({\"x\": Sequence([Value(Int(1)), \
+             Value(String(\"a\"))]), \"y\": Value(Int(2))})}, separator: '.' }"
+        );
+    }
+
+    #[test]
+    fn test_from_json_raw_duplicate_keys() {
+        let event = Event::from_json_raw(
+            r#"{
+                "Image": "calc.exe",
+                "User": {"Name": "Chuck", "Name": "Norris"},
+                "Image": "cmd.exe",
+                "Args": ["/c", "whoami"],
+                "Image": 42,
+                "Big": 18446744073709551615,
+                "Nothing": null
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            event.get("Image"),
+            Some(&EventValue::Sequence(vec![
+                EventValue::from("calc.exe"),
+                EventValue::from("cmd.exe"),
+                EventValue::from(42),
+            ]))
+        );
+        assert_eq!(
+            event.get("User.Name"),
+            Some(&EventValue::Sequence(vec![
+                EventValue::from("Chuck"),
+                EventValue::from("Norris"),
+            ]))
+        );
+        // keys occurring once are parsed like with serde_json
+        let expected: Event = json!({
+            "Args": ["/c", "whoami"],
+            "Big": 18446744073709551615u64,
+            "Nothing": null
+        })
+        .try_into()
+        .unwrap();
+        for key in ["Args", "Big", "Nothing"] {
+            assert_eq!(event.get(key), expected.get(key), "{}", key);
+        }
+
+        // a duplicate list is not flattened
+        let event = Event::from_json_raw(r#"{"a": [1, 2], "a": 3}"#).unwrap();
+        assert_eq!(
+            event.get("a"),
+            Some(&EventValue::Sequence(vec![
+                EventValue::Sequence(vec![EventValue::from(1), EventValue::from(2)]),
+                EventValue::from(3),
+            ]))
+        );
+
+        assert!(Event::from_json_raw("[1, 2]").is_err());
+        assert!(Event::from_json_raw(r#"{"a": "#).is_err());
+    }
+
+    #[test]
+    fn test_remove_and_merge() {
+        let mut event: Event = json!({"Image": "cmd.exe", "User": {"Name": "Chuck"}})
+            .try_into()
+            .unwrap();
+        // nested fields are not removed with a dotted key
+        assert!(event.remove("User.Name").is_none());
+        assert!(event.get("User.Name").is_some());
+        assert_eq!(event.remove("Image"), Some(EventValue::from("cmd.exe")));
+        assert!(event.get("Image").is_none());
+
+        let mut enrichment: Event = json!({"User": {"Domain": "CORP"}, "Host": "srv01"})
+            .try_into()
+            .unwrap();
+        enrichment.set_separator('/');
+        event.merge(enrichment);
+        // the whole nested map is replaced
+        assert!(event.get("User.Name").is_none());
+        assert_eq!(event.get_str("User.Domain"), Some("CORP"));
+        assert_eq!(event.get_str("Host"), Some("srv01"));
+
+        event.merge(Event::from([("Host", 42)]));
+        assert_eq!(event.get("Host"), Some(&EventValue::from(42)));
+        event.merge(Event::new());
+        assert_eq!(event.iter().count(), 2);
+    }
+
+    #[test]
+    fn test_get_all() {
+        let event: Event = json!({
+            "Hashes": [{"type": "MD5", "value": "a"}, 1, {"type": "SHA1"}, {"value": "b"}],
+            "User": {"Groups": [{"Name": "Admins"}], "Name": "Chuck"},
+            "Image": "cmd.exe"
+        })
+        .try_into()
+        .unwrap();
+        assert_eq!(
+            event.get_all("Hashes.value"),
+            vec![&EventValue::from("a"), &EventValue::from("b")]
+        );
+        assert_eq!(
+            event.get_all(".User.Groups.Name"),
+            vec![&EventValue::from("Admins")]
+        );
+        assert_eq!(event.get_all("User.Name"), vec![&EventValue::from("Chuck")]);
+        assert_eq!(event.get_all("Image"), vec![&EventValue::from("cmd.exe")]);
+        assert!(event.get_all("Hashes.size").is_empty());
+        assert!(event.get_all("Image.Name").is_empty());
+    }
+
+    #[test]
+    fn test_keys_and_len() {
+        let event: Event = json!({"Image": "cmd.exe", "User": {"Name": "Chuck", "Id": 1}})
+            .try_into()
+            .unwrap();
+        let mut keys: Vec<&String> = event.keys().collect();
+        keys.sort();
+        assert_eq!(keys, vec!["Image", "User"]);
+        assert_eq!(event.len(), 2);
+        assert!(!event.is_empty());
+
+        let mut event = Event::new();
+        assert_eq!(event.len(), 0);
+        assert!(event.is_empty());
+        assert_eq!(event.keys().count(), 0);
+        event.insert("Image", "cmd.exe");
+        assert_eq!(event.len(), 1);
+        assert!(!event.is_empty());
+    }
 }
@@ -1,42 +1,196 @@
+mod cidr_set;
+mod decoder;
 mod modifier;
+mod regex_cache;
 mod transformation;
 mod value;
 
+pub(crate) use decoder::with_decoders;
+pub use decoder::{Decoder, Decoders};
 pub use modifier::*;
 pub use value::*;
 
 use crate::error::ParserError;
 use crate::error::ParserError::{IPParsing, InvalidYAML};
 use crate::event::{Event, EventValue};
-use crate::field::transformation::{encode_base64, encode_base64_offset, windash_variations};
+use crate::field::cidr_set::CidrSet;
+use crate::field::regex_cache::RegexCache;
+use crate::field::transformation::{
+    decode_base64_offset, encode_base64, encode_base64_offset, nfkc, url_decode, windash_variations,
+};
+use crate::field::value::canonical_regex;
 use crate::field::ValueTransformer::{Base64, Base64offset, Windash};
+use crate::lint::{simplify_regex, LintWarning};
+use crate::screen::Terms;
 use cidr::IpCidr;
-use fancy_regex::Regex; // supports lookarounds
+use fancy_regex::{Regex, RegexBuilder}; // supports lookarounds
 use serde_yml::Value;
-use std::cell::RefCell;
-use std::collections::HashMap;
+use std::borrow::Cow;
+use std::cmp::Ordering;
+use std::collections::HashSet;
+use std::net::IpAddr;
 use std::str::FromStr;
 
+/// Limits for compiling and running the regexes of a rule, including the regexes
+/// created for values with wildcards. Use them to protect against rules from
+/// untrusted sources with patterns prone to catastrophic backtracking.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct RegexLimits {
+    /// The approximate maximum size in bytes of a compiled regex.
+    /// Patterns exceeding it are rejected while parsing.
+    pub size_limit: Option<usize>,
+    /// The maximum number of backtracking steps for matching a regex.
+    /// A match exceeding it is treated as no match.
+    pub backtrack_limit: Option<usize>,
+}
+
+impl RegexLimits {
+    pub(crate) fn build(&self, pattern: &str) -> Result<Regex, fancy_regex::Error> {
+        let mut builder = RegexBuilder::new(pattern);
+        if let Some(limit) = self.size_limit {
+            builder.delegate_size_limit(limit);
+        }
+        if let Some(limit) = self.backtrack_limit {
+            builder.backtrack_limit(limit);
+        }
+        builder.build()
+    }
+}
+
+/// Approximates how expensive matching a regex may become. Every character of the
+/// pattern adds one, every level of group nesting adds 50 and every repeated group
+/// which itself contains a repetition, e.g. `(a+)+`, adds 1000 as such patterns are
+/// prone to catastrophic backtracking.
+pub(crate) fn regex_complexity(pattern: &str) -> usize {
+    // for each open group whether it contains a repetition
+    let mut groups: Vec<bool> = vec![];
+    let mut max_depth = 0;
+    let mut nested_repetitions = 0;
+    // whether the previous atom was a group containing a repetition
+    let mut closed_repeating_group = false;
+
+    let mut chars = pattern.chars().peekable();
+    while let Some(c) = chars.next() {
+        let mut repeating_group = false;
+        match c {
+            '\\' => {
+                chars.next();
+            }
+            '[' => {
+                // skip the character class, a leading ] is part of the class
+                if chars.peek() == Some(&']') {
+                    chars.next();
+                }
+                while let Some(c) = chars.next() {
+                    match c {
+                        '\\' => {
+                            chars.next();
+                        }
+                        ']' => break,
+                        _ => {}
+                    }
+                }
+            }
+            '(' => {
+                groups.push(false);
+                max_depth = max_depth.max(groups.len());
+                // skip group flags like (?i) or (?:
+                if chars.peek() == Some(&'?') {
+                    chars.next();
+                }
+            }
+            ')' => {
+                repeating_group = groups.pop().unwrap_or(false);
+                if repeating_group {
+                    if let Some(parent) = groups.last_mut() {
+                        *parent = true;
+                    }
+                }
+            }
+            '*' | '+' | '{' => {
+                if closed_repeating_group {
+                    nested_repetitions += 1;
+                }
+                if let Some(group) = groups.last_mut() {
+                    *group = true;
+                }
+            }
+            _ => {}
+        }
+        closed_repeating_group = repeating_group;
+    }
+
+    pattern.chars().count() + 50 * max_depth + 1000 * nested_repetitions
+}
+
+/// The maximum number of values generated by the `windash` and `base64offset` modifiers
+/// for a single field while parsing a rule
+pub(crate) const DEFAULT_MAX_VALUE_VARIANTS: usize = 10_000;
+
+/// Replace the path separator `\` with `/` in a value of a rule for the `path` modifier.
+/// A backslash escaping a wildcard (`\*` and `\?`) is kept, an escaped backslash
+/// followed by a wildcard (`\\*`) is a single separator.
+fn normalize_path_pattern(pattern: &str) -> String {
+    let chars: Vec<char> = pattern.chars().collect();
+    let mut result = String::with_capacity(pattern.len());
+    let mut i = 0;
+    while i < chars.len() {
+        let is_wildcard = |i: usize| matches!(chars.get(i), Some('*' | '?'));
+        match chars[i] {
+            '\\' if chars.get(i + 1) == Some(&'\\') && is_wildcard(i + 2) => {
+                result.push('/');
+                i += 1;
+            }
+            '\\' if is_wildcard(i + 1) => result.push('\\'),
+            '\\' => result.push('/'),
+            c => result.push(c),
+        }
+        i += 1;
+    }
+    result
+}
+
+/// The value prefixed with its type, so that e.g. the string `1` and the integer 1 differ
+fn canonical_value(value: &FieldValue) -> String {
+    match value {
+        FieldValue::String(s) => format!("s:{}", s),
+        FieldValue::Int(i) => format!("i:{}", i),
+        FieldValue::Float(f) => format!("f:{:x}", f.to_bits()),
+        FieldValue::Unsigned(u) => format!("u:{}", u),
+        FieldValue::Boolean(b) => format!("b:{}", b),
+        FieldValue::Null => "n:".to_string(),
+        FieldValue::Regex(r) => format!("r:{}", canonical_regex(r.as_str())),
+        FieldValue::Cidr(c) => format!("c:{}", c),
+        FieldValue::Timestamp(t) => format!("t:{}", t),
+    }
+}
+
+/// The 64 bit FNV-1a hash of the bytes
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, b| {
+        (hash ^ u64::from(*b)).wrapping_mul(0x100000001b3)
+    })
+}
+
 // https://sigmahq.io/docs/basics/modifiers.html
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Field {
     pub name: String,
     pub values: Vec<FieldValue>,
-    pub regexes: RefCell<HashMap<String, Regex>>, // cache any patterns with globs (*, ?) converted to regex
+    pub(crate) regexes: RegexCache, // cache any patterns with globs (*, ?) converted to regex
     pub(crate) modifier: Modifier,
-}
-
-/// Lowercase the given value if it is a string and the cased modifier is not provided
-macro_rules! conditional_lowercase {
-    ($value:expr, $cased:expr) => {
-        if $cased {
-            $value
-        } else if let FieldValue::String(s) = $value {
-            &FieldValue::String(s.to_lowercase())
-        } else {
-            $value
-        }
-    };
+    pub(crate) regex_limits: RegexLimits,
+    /// The networks of the `cidr` modifier for fast lookups, built in bootstrap
+    pub(crate) cidr_set: Option<CidrSet>,
+    /// The number of variants generated from each value of the rule by a value transformer,
+    /// empty if the values were not expanded
+    pub(crate) variant_groups: Vec<usize>,
+    /// Whether the values are ASCII prefixes or suffixes without wildcards, which are
+    /// compared ignoring ASCII case instead of lowercasing ASCII values of the event
+    pub(crate) ascii_affixes: bool,
+    /// Whether booleans are equal to the strings `true` and `false` and the numbers `1`
+    /// and `0`, see `Detection::set_coerce_booleans`
+    pub(crate) coerce_booleans: bool,
 }
 
 impl FromStr for Field {
@@ -47,8 +201,13 @@ impl FromStr for Field {
         let result = Self {
             name: s.split("|").next().unwrap_or("").to_string(),
             values: vec![],
-            regexes: RefCell::new(HashMap::new()),
+            regexes: RegexCache::default(),
             modifier: Modifier::from_str(s)?,
+            regex_limits: RegexLimits::default(),
+            cidr_set: None,
+            variant_groups: vec![],
+            ascii_affixes: false,
+            coerce_booleans: false,
         };
 
         Ok(result)
@@ -108,6 +267,15 @@ impl Field {
             }
         }
 
+        // numbers of the rule are matched as strings as well, e.g. `contains: 41`
+        if self.modifier.numstr {
+            for v in self.values.iter_mut() {
+                if v.is_number() {
+                    *v = FieldValue::String(v.value_to_string());
+                }
+            }
+        }
+
         match self.modifier.match_modifier {
             Some(MatchModifier::Contains)
             | Some(MatchModifier::StartsWith)
@@ -126,20 +294,63 @@ impl Field {
             }
             Some(MatchModifier::Cidr) => {
                 for i in 0..self.values.len() {
+                    if matches!(self.values[i], FieldValue::Cidr(_)) {
+                        continue;
+                    }
                     let val_str = self.values[i].value_to_string();
-                    match IpCidr::from_str(val_str.as_str()) {
+                    // a bare address is a network of that single host
+                    let cidr = match (val_str.contains('/'), val_str.contains(':')) {
+                        (true, _) => IpCidr::from_str(val_str.as_str()),
+                        (false, true) => IpCidr::from_str(&format!("{}/128", val_str)),
+                        (false, false) => IpCidr::from_str(&format!("{}/32", val_str)),
+                    };
+                    match cidr {
                         Ok(ip) => self.values[i] = FieldValue::Cidr(ip),
                         Err(err) => return Err(IPParsing(val_str, err.to_string())),
                     }
                 }
+                self.cidr_set = Some(
+                    self.values
+                        .iter()
+                        .filter_map(|v| match v {
+                            FieldValue::Cidr(cidr) => Some(cidr),
+                            _ => None,
+                        })
+                        .collect(),
+                );
+            }
+            Some(MatchModifier::Gt)
+            | Some(MatchModifier::Gte)
+            | Some(MatchModifier::Lt)
+            | Some(MatchModifier::Lte)
+                if self.modifier.date =>
+            {
+                for v in self.values.iter_mut() {
+                    match v.to_timestamp() {
+                        Some(t) => *v = FieldValue::Timestamp(t),
+                        None => return Err(ParserError::DateParsing(v.value_to_string())),
+                    }
+                }
             }
             Some(MatchModifier::Re) => {
                 for i in 0..self.values.len() {
-                    match Regex::new(self.values[i].value_to_string().as_str()) {
+                    match self.regex_limits.build(self.values[i].value_to_string().as_str()) {
                         Ok(re) => self.values[i] = FieldValue::Regex(re),
                         Err(err) => return Err(ParserError::RegexParsing(err)),
                     }
                 }
+                // the named capture groups are the referenced fields
+                if self.modifier.fieldref {
+                    for v in self.values.iter() {
+                        if let FieldValue::Regex(re) = v {
+                            if re.capture_names().flatten().next().is_none() {
+                                return Err(ParserError::RegexWithoutFieldReference(
+                                    re.as_str().to_string(),
+                                ));
+                            }
+                        }
+                    }
+                }
             }
             _ => {}
         }
@@ -153,33 +364,258 @@ impl Field {
                     .collect();
             }
             Some(Base64offset(utf16)) => {
-                self.values = self
-                    .values
-                    .iter()
-                    .flat_map(|val| encode_base64_offset(val, utf16))
-                    .map(FieldValue::String)
-                    .collect();
+                (self.values, self.variant_groups) =
+                    self.expand_values(|val| encode_base64_offset(val, utf16))?;
             }
             Some(Windash) => {
-                self.values = self
-                    .values
-                    .iter()
-                    .flat_map(windash_variations)
-                    .map(FieldValue::String)
-                    .collect();
+                (self.values, self.variant_groups) = self.expand_values(windash_variations)?;
             }
             None => {}
         }
 
+        // the values are normalized like the values of the event, NFKC before lowercasing
+        if self.modifier.normalize && !self.modifier.fieldref {
+            for v in self.values.iter_mut() {
+                if let FieldValue::String(s) = v {
+                    if let Cow::Owned(normalized) = nfkc(Cow::Borrowed(s)) {
+                        *s = normalized;
+                    }
+                }
+            }
+        }
+
+        if self.modifier.path && !self.modifier.fieldref {
+            for v in self.values.iter_mut() {
+                if let FieldValue::String(s) = v {
+                    *s = normalize_path_pattern(s);
+                }
+            }
+        }
+
+        // Lowercase the values once here instead of on every comparison.
+        // Values of field references are field names and must be kept as is.
+        if !self.modifier.cased && !self.modifier.fieldref {
+            for v in self.values.iter_mut() {
+                if let FieldValue::String(s) = v {
+                    *s = s.to_lowercase();
+                }
+            }
+        }
+
+        // Surface invalid wildcard patterns while parsing instead of silently not matching
+        if self.matches_wildcards() {
+            for v in self.values.iter() {
+                if let FieldValue::String(s) = v {
+                    if v.contains_unescaped_wildcards(s) {
+                        v.convert_to_regex(
+                            self.modifier.match_modifier.clone(),
+                            s,
+                            self.modifier.cased,
+                            &self.regex_limits,
+                        )
+                        .map_err(ParserError::RegexParsing)?;
+                    }
+                }
+            }
+        }
+
+        self.ascii_affixes = !self.modifier.cased
+            && !self.modifier.fieldref
+            && matches!(
+                self.modifier.match_modifier,
+                Some(MatchModifier::StartsWith) | Some(MatchModifier::EndsWith)
+            )
+            && self.values.iter().all(|v| match v {
+                FieldValue::String(s) => s.is_ascii() && !v.contains_unescaped_wildcards(s),
+                _ => false,
+            });
+
         Ok(())
     } 
 
+    /// Replace every value with its variants, e.g. the windash variations of a command line
+    /// flag or the three offset encodings of `base64offset`, and return the number of
+    /// variants of each value along with them.
+    /// Returns an error as soon as more than [`DEFAULT_MAX_VALUE_VARIANTS`] values
+    /// are generated to bound the memory used by adversarial rules.
+    fn expand_values<F>(&self, variants: F) -> Result<(Vec<FieldValue>, Vec<usize>), ParserError>
+    where
+        F: Fn(&FieldValue) -> Vec<String>,
+    {
+        let mut result = vec![];
+        let mut groups = Vec::with_capacity(self.values.len());
+        for val in self.values.iter() {
+            let expanded = variants(val);
+            groups.push(expanded.len());
+            result.extend(expanded.into_iter().map(FieldValue::String));
+            if result.len() > DEFAULT_MAX_VALUE_VARIANTS {
+                return Err(ParserError::TooManyValueVariants(
+                    self.name.clone(),
+                    DEFAULT_MAX_VALUE_VARIANTS,
+                ));
+            }
+        }
+        Ok((result, groups))
+    }
+
+    /// The values grouped by the value of the rule they were generated from by a value
+    /// transformer. The variants of a value are alternatives, with `all` every group must
+    /// match instead of every variant. Values which were not expanded form a group of their own.
+    fn value_groups(&self) -> impl Iterator<Item = &[FieldValue]> {
+        let mut sizes = self.variant_groups.iter();
+        let mut rest = self.values.as_slice();
+        std::iter::from_fn(move || {
+            if rest.is_empty() {
+                return None;
+            }
+            let size = sizes.next().copied().unwrap_or(1).min(rest.len());
+            let (group, tail) = rest.split_at(size);
+            rest = tail;
+            Some(group)
+        })
+    }
+
+    /// Returns an error if the values of a field with a value transformer like `windash`
+    /// or `base64offset` expand to more than `max_variants` values
+    pub(crate) fn validate_value_variants(&self, max_variants: usize) -> Result<(), ParserError> {
+        if self.modifier.value_transformer.is_some() && self.values.len() > max_variants {
+            return Err(ParserError::TooManyValueVariants(
+                self.name.clone(),
+                max_variants,
+            ));
+        }
+        Ok(())
+    }
+
+    /// A fingerprint of the field which is equal for fields with the same name, modifiers
+    /// and values, regardless of the order of the values unless the field is a `sequence`.
+    /// Values are compared after parsing, e.g. `a` and `A` are the same value unless the
+    /// field is `cased`, regexes are compared by their pattern.
+    ///
+    /// The fingerprint is computed with FNV-1a over a canonical form of the field, it does
+    /// not depend on the platform or the Rust version and can be persisted.
+    pub(crate) fn fingerprint(&self) -> u64 {
+        let mut values: Vec<String> = self.values.iter().map(canonical_value).collect();
+        // the order and repetition of the values of a sequence matter
+        if !self.modifier.sequence {
+            values.sort_unstable();
+            values.dedup();
+        }
+        let canonical = format!("{}{}\0{}", self.name, self.modifier, values.join("\0"));
+        fnv1a(canonical.as_bytes())
+    }
+
+    /// Set the limits for the regexes of this field. The `re` patterns are recompiled,
+    /// an error is returned if one of them exceeds the size limit.
+    pub(crate) fn set_regex_limits(&mut self, limits: RegexLimits) -> Result<(), ParserError> {
+        self.regex_limits = limits;
+        self.regexes.clear();
+        for v in self.values.iter_mut() {
+            if let FieldValue::Regex(re) = v {
+                *re = limits
+                    .build(re.as_str())
+                    .map_err(ParserError::RegexParsing)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns an error if the complexity of a `re` pattern exceeds the maximum
+    pub(crate) fn validate_regex_complexity(
+        &self,
+        max_complexity: usize,
+    ) -> Result<(), ParserError> {
+        for v in self.values.iter() {
+            if let FieldValue::Regex(re) = v {
+                let complexity = regex_complexity(re.as_str());
+                if complexity > max_complexity {
+                    return Err(ParserError::RegexTooComplex(
+                        re.as_str().to_string(),
+                        complexity,
+                        max_complexity,
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether wildcards in string values are converted to regexes
+    fn matches_wildcards(&self) -> bool {
+        !self.modifier.fieldref
+            && matches!(
+                self.modifier.match_modifier,
+                None | Some(MatchModifier::Contains)
+                    | Some(MatchModifier::StartsWith)
+                    | Some(MatchModifier::EndsWith)
+            )
+    }
+
+    /// The patterns of all regexes the field compiles, i.e. the values of `re` and the
+    /// regexes that values with wildcards are converted to
+    pub(crate) fn compiled_patterns(&self) -> Vec<String> {
+        let matches_wildcards = self.matches_wildcards();
+        self.values
+            .iter()
+            .filter_map(|v| match v {
+                FieldValue::Regex(re) => Some(re.as_str().to_string()),
+                FieldValue::String(s) if matches_wildcards && v.contains_unescaped_wildcards(s) => {
+                    Some(v.wildcard_regex_pattern(
+                        self.modifier.match_modifier.clone(),
+                        s,
+                        self.modifier.cased,
+                    ))
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
     #[inline(always)]
     pub(crate) fn compare(&self, target: &FieldValue, value: &FieldValue) -> bool {
         match self.modifier.match_modifier {
-            Some(MatchModifier::Contains) => target.contains(value, self.modifier.cased, &mut self.regexes.borrow_mut()),
-            Some(MatchModifier::StartsWith) => target.starts_with(value, self.modifier.cased, &mut self.regexes.borrow_mut()),
-            Some(MatchModifier::EndsWith) => target.ends_with(value, self.modifier.cased, &mut self.regexes.borrow_mut()),
+            Some(MatchModifier::Contains) => target.contains(
+                value,
+                self.modifier.cased,
+                &self.regex_limits,
+                &self.regexes,
+            ),
+            Some(MatchModifier::StartsWith) if self.ascii_affixes => {
+                target.starts_with_ignore_ascii_case(value)
+            }
+            Some(MatchModifier::EndsWith) if self.ascii_affixes => {
+                target.ends_with_ignore_ascii_case(value)
+            }
+            Some(MatchModifier::StartsWith) => target.starts_with(
+                value,
+                self.modifier.cased,
+                &self.regex_limits,
+                &self.regexes,
+            ),
+            Some(MatchModifier::EndsWith) => target.ends_with(
+                value,
+                self.modifier.cased,
+                &self.regex_limits,
+                &self.regexes,
+            ),
+            Some(MatchModifier::Gt) if self.modifier.date => {
+                matches!(target.date_cmp(value), Some(Ordering::Greater))
+            }
+            Some(MatchModifier::Gte) if self.modifier.date => {
+                matches!(
+                    target.date_cmp(value),
+                    Some(Ordering::Greater | Ordering::Equal)
+                )
+            }
+            Some(MatchModifier::Lt) if self.modifier.date => {
+                matches!(target.date_cmp(value), Some(Ordering::Less))
+            }
+            Some(MatchModifier::Lte) if self.modifier.date => {
+                matches!(
+                    target.date_cmp(value),
+                    Some(Ordering::Less | Ordering::Equal)
+                )
+            }
             Some(MatchModifier::Gt) => target > value,
             Some(MatchModifier::Gte) => target >= value,
             Some(MatchModifier::Lt) => target < value,
@@ -187,28 +623,106 @@ impl Field {
             Some(MatchModifier::Re) => value.is_regex_match(target.value_to_string().as_str()),
             Some(MatchModifier::Cidr) => value.cidr_contains(target),
             None => {
-                if self.modifier.fieldref == true { // this is a comparison to another field in the same log
-                    return value == target
-                }
-                return target.is_equal(value, self.modifier.cased, &mut self.regexes.borrow_mut())
+                target.is_equal(
+                    value,
+                    self.modifier.cased,
+                    &self.regex_limits,
+                    &self.regexes,
+                ) || (self.coerce_booleans && target.coerced_bool_eq(value))
             }
         }
     }
+
+    /// Compare the target with the value of the field referenced by `fieldref`.
+    /// The value of the other field is always matched literally, i.e. wildcards in it
+    /// have no special meaning, but the match modifier of this field is applied.
+    #[inline(always)]
+    fn compare_fieldref(&self, target: &FieldValue, value: &FieldValue) -> bool {
+        match (&self.modifier.match_modifier, target, value) {
+            (Some(MatchModifier::Contains), FieldValue::String(a), FieldValue::String(b)) => {
+                a.contains(b.as_str())
+            }
+            (Some(MatchModifier::StartsWith), FieldValue::String(a), FieldValue::String(b)) => {
+                a.starts_with(b.as_str())
+            }
+            (Some(MatchModifier::EndsWith), FieldValue::String(a), FieldValue::String(b)) => {
+                a.ends_with(b.as_str())
+            }
+            (
+                Some(MatchModifier::Contains)
+                | Some(MatchModifier::StartsWith)
+                | Some(MatchModifier::EndsWith),
+                _,
+                _,
+            ) => false,
+            (None, _, _) => target == value,
+            _ => self.compare(target, value),
+        }
+    }
     
+    /// Evaluate the field against the value of the event.
+    ///
+    /// If the event value is a list, the field matches if any element matches.
+    /// With the `all` modifier every field value must match at least one element, except for
+    /// `gt`, `gte`, `lt` and `lte` where every element must satisfy every value. Elements
+    /// that cannot be compared with a value, e.g. strings compared with a number, are ignored,
+    /// but at least one element must be comparable.
+    /// With the `sequence` modifier the field values must match consecutive elements
+    /// in the order of the values.
+    /// For plain equality with literal string values this is a set lookup in
+    /// O(values + elements), for all other modifiers (including values with wildcards)
+    /// every value is compared with every element in O(values * elements).
+    ///
+    /// If a parent of a nested field is a list of maps, e.g. `Hashes.value` of
+    /// `{"Hashes": [{"value": "..."}]}`, the field matches if it matches the value of any map.
     #[inline(always)]
     pub(crate) fn evaluate(&self, event: &Event) -> bool {
-        let Some(event_value) = event.get(&self.name) else {
-            return matches!(self.modifier.exists, Some(false));
+        let event_value = event.get(&self.name);
+        #[cfg(feature = "serde_json")]
+        if self.modifier.json && event_value.is_none() {
+            let decoded = event.get_json(&self.name);
+            return self.evaluate_value(decoded.as_ref(), event);
+        }
+        if event_value.is_none() {
+            let values = event.get_all(&self.name);
+            if !values.is_empty() {
+                return values.iter().any(|v| self.evaluate_value(Some(v), event));
+            }
+        }
+        self.evaluate_value(event_value, event)
+    }
+
+    #[inline(always)]
+    fn evaluate_value(&self, event_value: Option<&EventValue>, event: &Event) -> bool {
+        // a missing field has no value like null, e.g. `CommandLine: null` matches events
+        // without a command line
+        let Some(event_value) = event_value else {
+            return match self.modifier.exists {
+                Some(exists) => !exists,
+                None => self.matches_empty(),
+            };
         };
 
-        if matches!(self.modifier.exists, Some(true)) {
-            return true;
+        // The field is present, its value is irrelevant for the exists modifier.
+        // Especially a boolean false value must not be compared to the rule value.
+        if let Some(exists) = self.modifier.exists {
+            return exists;
         };
 
-        let EventValue::Value(target) = event_value else {
-            // We currently do not support matching against lists and hashmaps, see
-            // https://github.com/jopohl/sigma-rust/issues/9
-            return false;
+        // the keys of a map are matched like a list of strings, other values have no keys
+        let keys;
+        let event_value = if self.modifier.keys {
+            let EventValue::Map(map) = event_value else {
+                return false;
+            };
+            keys = EventValue::Sequence(
+                map.keys()
+                    .map(|key| EventValue::Value(FieldValue::String(key.clone())))
+                    .collect(),
+            );
+            &keys
+        } else {
+            event_value
         };
 
         if self.values.is_empty() {
@@ -219,20 +733,127 @@ impl Field {
             return true;
         }
 
-        let target = conditional_lowercase!(target, self.modifier.cased);
+        if self.modifier.sequence {
+            return match event_value {
+                EventValue::Sequence(seq) => self.evaluate_subsequence(seq),
+                // a single value is a list of one element
+                _ => self.evaluate_subsequence(std::slice::from_ref(event_value)),
+            };
+        }
 
-        for val in self.values.iter() {
-            let cmp = if self.modifier.fieldref {
-                if let Some(EventValue::Value(value)) = event.get(val.value_to_string().as_str()) {
-                    conditional_lowercase!(value, self.modifier.cased)
-                } else {
-                    continue;
-                }
-            } else {
-                conditional_lowercase!(val, self.modifier.cased)
+        match event_value {
+            EventValue::Value(target) => self.evaluate_target(target, event),
+            // an empty list has no value like null, so it only matches null
+            EventValue::Sequence(seq) if seq.is_empty() => self.matches_empty(),
+            EventValue::Sequence(seq) => self.evaluate_sequence(seq, event),
+            EventValue::Map(_) => {
+                // We currently do not support matching against hashmaps, see
+                // https://github.com/jopohl/sigma-rust/issues/9
+                false
+            }
+        }
+    }
+
+    /// Whether the field matches a value that is empty, i.e. an empty list or a missing field.
+    /// This is the case if its values are `null`, with `all` every value must be `null`.
+    #[inline(always)]
+    fn matches_empty(&self) -> bool {
+        let is_null = |v: &FieldValue| matches!(v, FieldValue::Null);
+        if self.modifier.match_all {
+            self.values.iter().all(is_null)
+        } else {
+            self.values.iter().any(is_null)
+        }
+    }
+
+    #[inline(always)]
+    fn evaluate_target(&self, target: &FieldValue, event: &Event) -> bool {
+        if self.decodes_event_value() {
+            return self
+                .decode(target)
+                .iter()
+                .any(|t| self.evaluate_normalized(&self.normalize(t), event));
+        }
+        self.evaluate_normalized(&self.normalize(target), event)
+    }
+
+    /// Whether the values of the event are decoded with `base64offsetdecode`, `urldecode`
+    /// or a custom decoder before they are matched
+    #[inline(always)]
+    fn decodes_event_value(&self) -> bool {
+        self.modifier.base64offset_decode
+            || self.modifier.url_decode
+            || self.modifier.custom_decoder.is_some()
+    }
+
+    /// The results of decoding a value of the event with `base64offsetdecode`, `urldecode`
+    /// or a custom decoder
+    fn decode(&self, target: &FieldValue) -> Vec<FieldValue> {
+        let FieldValue::String(s) = target else {
+            return vec![];
+        };
+        let decoded = if let Some(decoder) = &self.modifier.custom_decoder {
+            (decoder.decode)(s)
+        } else if self.modifier.url_decode {
+            url_decode(s)
+        } else {
+            decode_base64_offset(s)
+        };
+        decoded.into_iter().map(FieldValue::String).collect()
+    }
+
+    /// Apply the `numstr`, `trim`, `normalize`, `path` and `cased` modifiers to a value of the event.
+    /// ASCII values are not lowercased for prefixes and suffixes compared ignoring ASCII case,
+    /// see [`Field::ascii_affixes`]. Other values are, because lowercasing may turn non-ASCII
+    /// characters into ASCII ones, e.g. the Kelvin sign `K` into `k`.
+    /// `numstr` converts numbers to strings, so that they are also matched by their digits
+    /// with plain values, e.g. the wildcard `41*`, not only with `contains`, `startswith`
+    /// and `endswith`.
+    /// `trim` removes leading and trailing whitespace as defined by Unicode,
+    /// which includes spaces, tabs and line breaks. `normalize` applies the Unicode
+    /// normalization form NFKC before the value is lowercased. `path` replaces every `\` with `/`.
+    #[inline(always)]
+    fn normalize<'a>(&self, target: &'a FieldValue) -> Cow<'a, FieldValue> {
+        // dates of the event are parsed once for the comparison with all values
+        if self.modifier.date {
+            return match target.to_timestamp() {
+                Some(t) => Cow::Owned(FieldValue::Timestamp(t)),
+                None => Cow::Borrowed(target),
             };
+        }
+        // digits are not affected by the other modifiers
+        if self.modifier.numstr && target.is_number() {
+            return Cow::Owned(FieldValue::String(target.value_to_string()));
+        }
+        let FieldValue::String(s) = target else {
+            return Cow::Borrowed(target);
+        };
+        let mut value = Cow::Borrowed(if self.modifier.trim { s.trim() } else { s });
+        if self.modifier.normalize {
+            value = nfkc(value);
+        }
+        if self.modifier.path && value.contains('\\') {
+            value = Cow::Owned(value.replace('\\', "/"));
+        }
+        let keeps_case = self.modifier.cased || (self.ascii_affixes && value.is_ascii());
+        if !keeps_case {
+            Cow::Owned(FieldValue::String(value.to_lowercase()))
+        } else if value.len() != s.len() || matches!(value, Cow::Owned(_)) {
+            Cow::Owned(FieldValue::String(value.into_owned()))
+        } else {
+            Cow::Borrowed(target)
+        }
+    }
 
-            let fired = self.compare(target, cmp);
+    #[inline(always)]
+    fn evaluate_normalized(&self, target: &FieldValue, event: &Event) -> bool {
+        // a single lookup instead of checking every network of the cidr values
+        if let (Some(cidr_set), false) = (&self.cidr_set, self.modifier.match_all) {
+            return IpAddr::from_str(target.value_to_string().as_str())
+                .is_ok_and(|ip| cidr_set.contains(&ip));
+        }
+        for group in self.value_groups() {
+            let fired = self.compare_group(target, group, event);
             if fired && !self.modifier.match_all {
                 return true;
             } else if !fired && self.modifier.match_all {
@@ -244,6 +865,277 @@ impl Field {
         // 2. match_all = true: all conditions fired => return true
         self.modifier.match_all
     }
+
+    /// Compare the normalized target against the variants of a value, see [`Field::value_groups`]
+    #[inline(always)]
+    fn compare_group(&self, target: &FieldValue, group: &[FieldValue], event: &Event) -> bool {
+        group
+            .iter()
+            .any(|val| self.compare_value(target, val, event))
+    }
+
+    /// Compare the normalized target against a single value of the field.
+    /// A value referencing a field which is not in the event does not match, also with `all`.
+    /// A referenced list matches if any of its elements does, nested lists and maps of the
+    /// list are not compared. A referenced map never matches, it has no value to compare with.
+    #[inline(always)]
+    fn compare_value(&self, target: &FieldValue, val: &FieldValue, event: &Event) -> bool {
+        if !self.modifier.fieldref {
+            // the values are lowercased in bootstrap
+            return self.compare(target, val);
+        }
+        if self.modifier.match_modifier == Some(MatchModifier::Re) {
+            return self.compare_captures(target, val, event);
+        }
+        match event.get(val.value_to_string().as_str()) {
+            Some(EventValue::Value(value)) => self.compare_fieldref(target, &self.normalize(value)),
+            Some(EventValue::Sequence(seq)) => seq.iter().any(|v| match v {
+                EventValue::Value(value) => self.compare_fieldref(target, &self.normalize(value)),
+                _ => false,
+            }),
+            Some(EventValue::Map(_)) | None => false,
+        }
+    }
+
+    /// Match the `re` pattern of `re|fieldref` against the target and compare the text of each
+    /// named capture group with the field of the same name, e.g. `(?P<Host>[^/]+)` must equal
+    /// the value of `Host`. A referenced field which is not in the event or not a single
+    /// value does not match.
+    fn compare_captures(&self, target: &FieldValue, val: &FieldValue, event: &Event) -> bool {
+        let FieldValue::Regex(re) = val else {
+            return false;
+        };
+        let target = target.value_to_string();
+        // errors of the regex engine, e.g. exceeding the backtrack limit, are no match
+        let Ok(Some(captures)) = re.captures(&target) else {
+            return false;
+        };
+        for name in re.capture_names().flatten() {
+            let Some(EventValue::Value(value)) = event.get(name) else {
+                return false;
+            };
+            let Some(captured) = captures.name(name) else {
+                return false;
+            };
+            if captured.as_str() != self.normalize(value).value_to_string() {
+                return false;
+            }
+        }
+        true
+    }
+
+    fn evaluate_sequence(&self, seq: &[EventValue], event: &Event) -> bool {
+        // Nested lists and maps within the list are not matched
+        let values = seq.iter().filter_map(|v| match v {
+            EventValue::Value(target) => Some(target),
+            _ => None,
+        });
+        // every decoded element is matched like an element of the list
+        let decoded: Vec<FieldValue> = if self.decodes_event_value() {
+            values.clone().flat_map(|t| self.decode(t)).collect()
+        } else {
+            vec![]
+        };
+        let targets: Vec<Cow<FieldValue>> = if self.decodes_event_value() {
+            decoded.iter().map(|t| self.normalize(t)).collect()
+        } else {
+            values.map(|t| self.normalize(t)).collect()
+        };
+
+        if self.is_literal_equality() {
+            let elements: HashSet<&str> = targets
+                .iter()
+                .filter_map(|t| match t.as_ref() {
+                    FieldValue::String(s) => Some(s.as_str()),
+                    _ => None,
+                })
+                .collect();
+            let contained = |v: &FieldValue| match v {
+                FieldValue::String(s) => elements.contains(s.as_str()),
+                _ => false,
+            };
+            return if self.modifier.match_all {
+                self.value_groups().all(|group| group.iter().any(contained))
+            } else {
+                self.values.iter().any(contained)
+            };
+        }
+
+        if !self.modifier.match_all {
+            return targets.iter().any(|t| self.evaluate_normalized(t, event));
+        }
+
+        if self.is_comparison() {
+            return self.values.iter().all(|val| {
+                let referenced = self.referenced_value(val, event);
+                if self.modifier.fieldref && referenced.is_none() {
+                    // values referencing a missing field do not match like for single values
+                    return false;
+                }
+                let other = referenced.unwrap_or(Cow::Borrowed(val));
+                let mut results = targets
+                    .iter()
+                    .filter_map(|t| self.compare_ordered(t, &other))
+                    .peekable();
+                results.peek().is_some() && results.all(|b| b)
+            });
+        }
+
+        self.value_groups()
+            .all(|group| targets.iter().any(|t| self.compare_group(t, group, event)))
+    }
+
+    /// Returns true if the field compares values with `gt`, `gte`, `lt` or `lte`
+    #[inline(always)]
+    fn is_comparison(&self) -> bool {
+        matches!(
+            self.modifier.match_modifier,
+            Some(MatchModifier::Gt | MatchModifier::Gte | MatchModifier::Lt | MatchModifier::Lte)
+        )
+    }
+
+    /// The normalized value of the field referenced by `fieldref`
+    fn referenced_value<'a>(
+        &self,
+        val: &FieldValue,
+        event: &'a Event,
+    ) -> Option<Cow<'a, FieldValue>> {
+        if !self.modifier.fieldref {
+            return None;
+        }
+        match event.get(val.value_to_string().as_str()) {
+            Some(EventValue::Value(value)) => Some(self.normalize(value)),
+            _ => None,
+        }
+    }
+
+    /// Compare the target with the value using the comparison modifier of the field.
+    /// Returns `None` if the two values cannot be ordered, e.g. they are of different types.
+    fn compare_ordered(&self, target: &FieldValue, value: &FieldValue) -> Option<bool> {
+        let ordering = if self.modifier.date {
+            target.date_cmp(value)
+        } else {
+            target.partial_cmp(value)
+        }?;
+        Some(match self.modifier.match_modifier {
+            Some(MatchModifier::Gt) => ordering.is_gt(),
+            Some(MatchModifier::Gte) => ordering.is_ge(),
+            Some(MatchModifier::Lt) => ordering.is_lt(),
+            Some(MatchModifier::Lte) => ordering.is_le(),
+            _ => false,
+        })
+    }
+
+    /// Returns true if the values of the field match a contiguous run of elements of the list,
+    /// i.e. the first value matches an element, the second value the next element and so on
+    fn evaluate_subsequence(&self, seq: &[EventValue]) -> bool {
+        seq.windows(self.values.len()).any(|window| {
+            window
+                .iter()
+                .zip(self.values.iter())
+                .all(|(element, value)| match element {
+                    EventValue::Value(target) => self.compare(&self.normalize(target), value),
+                    _ => false,
+                })
+        })
+    }
+
+    /// Returns true if the field is a plain equality check against literal strings.
+    /// Booleans coerced from strings are not found by a set lookup of the strings.
+    #[inline(always)]
+    fn is_literal_equality(&self) -> bool {
+        self.modifier.match_modifier.is_none()
+            && !self.modifier.fieldref
+            && !self.coerce_booleans
+            && self
+                .values
+                .iter()
+                .all(|v| matches!(v, FieldValue::String(s) if !v.contains_unescaped_wildcards(s)))
+    }
+
+    /// Reports `re` patterns of this field which can be replaced by a cheaper modifier.
+    /// Regexes are case-sensitive, so the suggested modifiers always include `cased`.
+    pub(crate) fn lint(&self, selection: &str) -> Vec<LintWarning> {
+        if self.modifier.match_modifier != Some(MatchModifier::Re) || self.modifier.fieldref {
+            return vec![];
+        }
+        self.values
+            .iter()
+            .filter_map(|v| match v {
+                FieldValue::Regex(re) => {
+                    let (match_modifier, value) = simplify_regex(re.as_str())?;
+                    let mut modifiers = vec![];
+                    if let Some(m) = match_modifier {
+                        modifiers.push(m.to_string());
+                    }
+                    if self.modifier.match_all {
+                        modifiers.push("all".to_string());
+                    }
+                    modifiers.push("cased".to_string());
+                    Some(LintWarning::SimplifiableRegex {
+                        selection: selection.to_string(),
+                        field: self.name.clone(),
+                        pattern: re.as_str().to_string(),
+                        modifier: modifiers.join("|"),
+                        value,
+                    })
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Returns the literal strings of which at least one must be contained in the event value
+    /// for this field to match, or `None` if no such strings can be derived from the field.
+    pub(crate) fn literal_terms(&self) -> Option<Terms> {
+        // decoded or normalized values do not occur literally in the event
+        if self.modifier.exists.is_some()
+            || self.modifier.fieldref
+            || self.modifier.path
+            || self.modifier.normalize
+            || self.modifier.json
+            || self.modifier.keys
+            || self.decodes_event_value()
+        {
+            return None;
+        }
+        match self.modifier.match_modifier {
+            None
+            | Some(MatchModifier::Contains)
+            | Some(MatchModifier::StartsWith)
+            | Some(MatchModifier::EndsWith) => {}
+            _ => return None,
+        }
+
+        let mut terms = Vec::with_capacity(self.values.len());
+        for value in self.values.iter() {
+            match value {
+                // a coerced boolean of the event is matched by its value, not by the string
+                FieldValue::String(_)
+                    if self.coerce_booleans && value.as_coerced_bool().is_some() =>
+                {
+                    return None
+                }
+                FieldValue::String(s) if !value.contains_unescaped_wildcards(s) => {
+                    terms.push(s.to_string())
+                }
+                _ => return None,
+            }
+        }
+
+        if self.modifier.cased {
+            Some(Terms {
+                cased: terms,
+                ..Default::default()
+            })
+        } else {
+            // the values are already lowercased in bootstrap
+            Some(Terms {
+                uncased: terms,
+                ..Default::default()
+            })
+        }
+    }
 }
 
 #[cfg(test)]
@@ -316,25 +1208,323 @@ mod tests {
     }
 
     #[test]
-    fn test_evaluate_cased() {
-        let mut field = Field::new(
-            "test|cased",
-            vec![
-                FieldValue::from("zsh"),
-                FieldValue::from("BASH"),
-                FieldValue::from("pwsh"),
-            ],
-        )
-        .unwrap();
-        // field.modifier.cased = true;
-        println!("{:?}", field.modifier.cased);
-        let event_no_match = Event::from([("test", "bash")]);
-        assert!(!field.evaluate(&event_no_match));
-        let matching_event = Event::from([("test", "BASH")]);
-        assert!(field.evaluate(&matching_event));
-        field.modifier.cased = false;
-        let matching_event = Event::from([("test", "BASH")]);
-        assert!(field.evaluate(&matching_event));
+    fn test_evaluate_cased() {
+        let mut field = Field::new(
+            "test|cased",
+            vec![
+                FieldValue::from("zsh"),
+                FieldValue::from("BASH"),
+                FieldValue::from("pwsh"),
+            ],
+        )
+        .unwrap();
+        // field.modifier.cased = true;
+        println!("{:?}", field.modifier.cased);
+        let event_no_match = Event::from([("test", "bash")]);
+        assert!(!field.evaluate(&event_no_match));
+        let matching_event = Event::from([("test", "BASH")]);
+        assert!(field.evaluate(&matching_event));
+        field = Field::new("test", vec![FieldValue::from("BASH")]).unwrap();
+        let matching_event = Event::from([("test", "BASH")]);
+        assert!(field.evaluate(&matching_event));
+    }
+
+    #[test]
+    fn test_evaluate_fieldref_with_modifiers() {
+        let mut event = Event::from([
+            ("CommandLine", "C:\\Windows\\cmd.exe /c whoami"),
+            ("Image", "C:\\Windows\\CMD.exe"),
+            ("Name", "cmd.exe"),
+            ("Argument", "WHOAMI"),
+            ("Pattern", "*"),
+        ]);
+        event.insert("Low", 10);
+        event.insert("High", 20);
+        let cases = [
+            ("CommandLine|fieldref", "Image", false),
+            ("Image|fieldref", "Image", true),
+            ("CommandLine|fieldref|startswith", "Image", true),
+            ("CommandLine|fieldref|startswith", "Name", false),
+            ("Image|fieldref|endswith", "Name", true),
+            ("CommandLine|fieldref|endswith", "Name", false),
+            ("CommandLine|fieldref|contains", "Argument", true),
+            ("CommandLine|fieldref|contains", "Name", true),
+            ("Image|fieldref|contains", "Argument", false),
+            ("CommandLine|fieldref|contains|cased", "Argument", false),
+            // values of other fields are no wildcard patterns
+            ("CommandLine|fieldref|contains", "Pattern", false),
+            ("CommandLine|fieldref", "Pattern", false),
+            ("High|fieldref|gt", "Low", true),
+            ("Low|fieldref|gt", "High", false),
+            ("Low|fieldref|lte", "High", true),
+        ];
+        for (name, reference, expected) in cases {
+            let field = Field::new(name, vec![FieldValue::from(reference)]).unwrap();
+            assert_eq!(field.evaluate(&event), expected, "{} {}", name, reference);
+        }
+    }
+
+    #[test]
+    fn test_evaluate_regex_captures_fieldref() {
+        let field = Field::new(
+            "Url|re|fieldref",
+            vec![FieldValue::from("^https?://(?P<Host>[^/:]+)")],
+        )
+        .unwrap();
+        let event = |url: &str, host: &str| Event::from([("Url", url), ("Host", host)]);
+        assert!(field.evaluate(&event("https://example.com/index.html", "example.com")));
+        assert!(field.evaluate(&event("http://Example.COM:8080/", "example.com")));
+        assert!(!field.evaluate(&event("https://evil.com/example.com", "example.com")));
+        assert!(!field.evaluate(&event("ftp://example.com/", "example.com")));
+        // the referenced field is missing
+        assert!(!field.evaluate(&Event::from([("Url", "https://example.com/")])));
+
+        let field = Field::new(
+            "Url|re|fieldref|cased",
+            vec![FieldValue::from(
+                "^https?://(?P<Host>[^/:]+):(?P<Port>\\d+)",
+            )],
+        )
+        .unwrap();
+        let mut event = event("https://example.com:8443/", "example.com");
+        event.insert("Port", 8443);
+        assert!(field.evaluate(&event));
+        event.insert("Port", 443);
+        assert!(!field.evaluate(&event));
+        event.insert("Host", "EXAMPLE.COM");
+        event.insert("Port", 8443);
+        assert!(!field.evaluate(&event));
+
+        let err = Field::new(
+            "Url|re|fieldref",
+            vec![FieldValue::from("^https?://([^/]+)")],
+        )
+        .unwrap_err();
+        assert!(matches!(err, ParserError::RegexWithoutFieldReference(_)));
+    }
+
+    #[test]
+    fn test_fingerprint() {
+        let field = |name: &str, values: &[&str]| {
+            Field::new(name, values.iter().map(|v| FieldValue::from(*v)).collect()).unwrap()
+        };
+        let a = field("Image|endswith", &["\\cmd.exe", "\\powershell.exe"]);
+        assert_eq!(a.fingerprint(), a.fingerprint());
+        assert_eq!(a.fingerprint(), a.clone().fingerprint());
+        // order, duplicates and case of values do not matter
+        for b in [
+            field("Image|endswith", &["\\powershell.exe", "\\cmd.exe"]),
+            field(
+                "Image|endswith",
+                &["\\CMD.exe", "\\powershell.exe", "\\cmd.exe"],
+            ),
+        ] {
+            assert_eq!(a.fingerprint(), b.fingerprint());
+        }
+        for b in [
+            field("Image|endswith|cased", &["\\cmd.exe", "\\powershell.exe"]),
+            field("Image|contains", &["\\cmd.exe", "\\powershell.exe"]),
+            field("ParentImage|endswith", &["\\cmd.exe", "\\powershell.exe"]),
+            field("Image|endswith", &["\\cmd.exe"]),
+        ] {
+            assert_ne!(a.fingerprint(), b.fingerprint());
+        }
+
+        assert_eq!(
+            field("CommandLine|re", &["a+b", "^c"]).fingerprint(),
+            field("CommandLine|re", &["^c", "a+b"]).fingerprint()
+        );
+        // but they do for sequences
+        let sequence = field("CommandLine|sequence", &["cmd", "/c"]);
+        assert_eq!(
+            sequence.fingerprint(),
+            field("CommandLine|sequence", &["CMD", "/c"]).fingerprint()
+        );
+        for b in [
+            field("CommandLine|sequence", &["/c", "cmd"]),
+            field("CommandLine|sequence", &["cmd", "/c", "/c"]),
+        ] {
+            assert_ne!(sequence.fingerprint(), b.fingerprint());
+        }
+        let typed = |value: FieldValue| Field::new("EventID", vec![value]).unwrap().fingerprint();
+        assert_ne!(typed(FieldValue::Int(1)), typed(FieldValue::from("1")));
+        assert_ne!(typed(FieldValue::Int(1)), typed(FieldValue::Float(1.0)));
+
+        // the fingerprint is stable, it must not change between releases
+        assert_eq!(fnv1a(b""), 0xcbf29ce484222325);
+        assert_eq!(fnv1a(b"a"), 0xaf63dc4c8601ec8c);
+    }
+
+    #[test]
+    fn test_evaluate_trim() {
+        let field = Field::new("test|trim|cased", vec![FieldValue::from("cmd.exe")]).unwrap();
+        assert!(field.evaluate(&Event::from([("test", " cmd.exe\n")])));
+        assert!(!field.evaluate(&Event::from([("test", " CMD.exe\n")])));
+
+        let field = Field::new("test|trim|startswith", vec![FieldValue::from("cmd")]).unwrap();
+        assert!(field.evaluate(&Event::from([("test", "  CMD.exe")])));
+
+        let elements = vec![EventValue::from(" a "), EventValue::from("b\t")];
+        let event = Event::from([("test", EventValue::Sequence(elements))]);
+        let field = Field::new(
+            "test|trim|all",
+            vec![FieldValue::from("a"), FieldValue::from("b")],
+        )
+        .unwrap();
+        assert!(field.evaluate(&event));
+        let field = Field::new(
+            "test|all",
+            vec![FieldValue::from("a"), FieldValue::from("b")],
+        )
+        .unwrap();
+        assert!(!field.evaluate(&event));
+    }
+
+    #[test]
+    fn test_regex_runtime_error_is_no_match() {
+        // exceeds the backtrack limit of fancy_regex
+        let field = Field::new("test|re", vec![FieldValue::from(r"^(a*)*\1b$")]).unwrap();
+        let event = Event::from([("test", "a".repeat(50))]);
+        assert!(!field.evaluate(&event));
+
+        let field = Field::new("test|re", vec![FieldValue::from(r"^(a*)\1$")]).unwrap();
+        assert!(field.evaluate(&event));
+    }
+
+    #[test]
+    fn test_regex_limits() {
+        let limits = RegexLimits {
+            size_limit: Some(10_000),
+            backtrack_limit: Some(1_000),
+        };
+        let mut field = Field::new("test|re", vec![FieldValue::from(r"\w{50}")]).unwrap();
+        assert!(matches!(
+            field.set_regex_limits(limits),
+            Err(ParserError::RegexParsing(_))
+        ));
+
+        // a pathological pattern gives up after the backtrack limit
+        let mut field = Field::new("test|re", vec![FieldValue::from(r"^(a|aa)+\1$")]).unwrap();
+        let event = Event::from([("test", format!("{}b", "a".repeat(25)))]);
+        assert!(!field.evaluate(&event));
+        field.set_regex_limits(limits).unwrap();
+        assert!(!field.evaluate(&event));
+        let event = Event::from([("test", "aa")]);
+        assert!(field.evaluate(&event));
+
+        let mut field = Field::new("test", vec![FieldValue::from("a*b*c")]).unwrap();
+        field.set_regex_limits(limits).unwrap();
+        assert!(field.evaluate(&Event::from([("test", "aXbYc")])));
+    }
+
+    #[test]
+    fn test_regex_complexity() {
+        assert_eq!(regex_complexity("abc"), 3);
+        assert_eq!(regex_complexity("a+b*"), 4);
+        assert_eq!(regex_complexity("(ab)+"), 55);
+        assert_eq!(regex_complexity("((a))"), 105);
+        assert_eq!(regex_complexity("(a+)+"), 1055);
+        assert_eq!(regex_complexity("(?:a|b*)*c"), 1060);
+        assert_eq!(regex_complexity("((a+)b)+"), 1108);
+        assert_eq!(regex_complexity(r"(\(+)+"), 1056);
+        assert_eq!(regex_complexity("([(+])+"), 57);
+        assert_eq!(regex_complexity("(a+)?"), 55);
+
+        let field = Field::new("test|re", vec![FieldValue::from("^(a+)+$")]).unwrap();
+        assert!(matches!(
+            field.validate_regex_complexity(500),
+            Err(ParserError::RegexTooComplex(_, 1057, 500))
+        ));
+        let field = Field::new("test|re", vec![FieldValue::from(r"^\d+-\w+$")]).unwrap();
+        assert!(field.validate_regex_complexity(500).is_ok());
+    }
+
+    #[test]
+    fn test_values_lowercased_at_bootstrap() {
+        let field = Field::new(
+            "test|contains",
+            vec![FieldValue::from("PowerShell"), FieldValue::from("CMD.exe")],
+        )
+        .unwrap();
+        assert_eq!(
+            field.values,
+            vec![FieldValue::from("powershell"), FieldValue::from("cmd.exe")]
+        );
+        let field = Field::new("test|cased", vec![FieldValue::from("PowerShell")]).unwrap();
+        assert_eq!(field.values, vec![FieldValue::from("PowerShell")]);
+        let field = Field::new("test|fieldref", vec![FieldValue::from("OtherField")]).unwrap();
+        assert_eq!(field.values, vec![FieldValue::from("OtherField")]);
+
+        // the results are the same as when lowercasing on every comparison
+        let values = ["*Shell*", "CMD.EXE", "wHoAmI", "Net?exe"];
+        let targets = [
+            "powershell",
+            "POWERSHELL.exe",
+            "cmd.exe",
+            "WHOAMI",
+            "net.exe",
+            "calc",
+        ];
+        for modifier in ["", "|contains", "|startswith", "|endswith"] {
+            let field = Field::new(
+                format!("test{}", modifier),
+                values.iter().map(|v| FieldValue::from(*v)).collect(),
+            )
+            .unwrap();
+            for target in targets {
+                let event = Event::from([("test", target)]);
+                let expected = values.iter().any(|v| {
+                    let value = FieldValue::from(v.to_lowercase());
+                    let target = FieldValue::from(target.to_lowercase());
+                    field.compare(&target, &value)
+                });
+                assert_eq!(field.evaluate(&event), expected, "{} {}", modifier, target);
+            }
+        }
+    }
+
+    #[test]
+    fn test_ascii_affixes() {
+        let values = ["Power", "CMD.EXE", "\\*", "x"];
+        let targets = [
+            "powershell.exe",
+            "POWERSHELL.EXE",
+            "c:\\windows\\cmd.exe",
+            "\\*",
+            "Ärger.cmd.exe",
+            // lowercased to "kelvin"
+            "\u{212A}elvin.x",
+            "x\u{212A}",
+            "",
+        ];
+        for modifier in ["|startswith", "|endswith"] {
+            let field = Field::new(
+                format!("test{}", modifier),
+                values.iter().map(|v| FieldValue::from(*v)).collect(),
+            )
+            .unwrap();
+            assert!(field.ascii_affixes);
+            for target in targets {
+                let event = Event::from([("test", target)]);
+                let lowercased = target.to_lowercase();
+                let expected = values.iter().any(|v| match modifier {
+                    "|startswith" => lowercased.starts_with(&v.to_lowercase()),
+                    _ => lowercased.ends_with(&v.to_lowercase()),
+                });
+                assert_eq!(field.evaluate(&event), expected, "{} {}", modifier, target);
+            }
+        }
+
+        // values with wildcards or non-ASCII characters and cased values are not affected
+        for (name, value) in [
+            ("test|startswith", "a*b"),
+            ("test|endswith", "Ä"),
+            ("test|startswith|cased", "a"),
+            ("test|contains", "a"),
+        ] {
+            let field = Field::new(name, vec![FieldValue::from(value)]).unwrap();
+            assert!(!field.ascii_affixes, "{}", name);
+        }
     }
 
     #[test]
@@ -352,6 +1542,26 @@ mod tests {
         assert!(field.evaluate(&matching_event));
     } 
 
+    #[test]
+    fn test_evaluate_wildcards_match_whole_value() {
+        let field = Field::new("test", vec![FieldValue::from("a*b")]).unwrap();
+        for (value, expected) in [
+            ("ab", true),
+            ("aXXb", true),
+            ("zzabzz", false),
+            ("xaXXbx", false),
+            ("ab\nzz", false),
+        ] {
+            let event = Event::from([("test", value)]);
+            assert_eq!(field.evaluate(&event), expected, "{}", value);
+        }
+
+        let field = Field::new("test", vec![FieldValue::from("a?c")]).unwrap();
+        assert!(field.evaluate(&Event::from([("test", "ABC")])));
+        assert!(!field.evaluate(&Event::from([("test", "xabc")])));
+        assert!(!field.evaluate(&Event::from([("test", "abcx")])));
+    }
+
     #[test]
     fn test_evaluate_not_exists() {
         let field = Field::new(
@@ -367,6 +1577,28 @@ mod tests {
         assert!(field.evaluate(&matching_event));
     } 
     
+    #[test]
+    fn test_evaluate_exists_checks_presence_not_value() {
+        let exists = Field::new("test|exists", vec![FieldValue::from(true)]).unwrap();
+        let not_exists = Field::new("test|exists", vec![FieldValue::from(false)]).unwrap();
+
+        for value in [
+            FieldValue::from(false),
+            FieldValue::from(true),
+            FieldValue::Null,
+        ] {
+            let mut event = Event::new();
+            event.insert("test", value);
+            assert!(exists.evaluate(&event));
+            assert!(!not_exists.evaluate(&event));
+        }
+
+        let mut event = Event::new();
+        event.insert("other", false);
+        assert!(!exists.evaluate(&event));
+        assert!(not_exists.evaluate(&event));
+    }
+
     #[test]
     fn test_evaluate_wildcards() {
         let mut field = Field::new(
@@ -384,6 +1616,74 @@ mod tests {
         assert!(field.evaluate(&matching_event));
     } 
 
+    #[test]
+    fn test_contains_all_mixed_wildcards() {
+        let values = || {
+            vec![
+                FieldValue::from("Invoke-"),
+                FieldValue::from("Web*Request"),
+                FieldValue::from("-Uri ?ttp"),
+            ]
+        };
+        let cased = Field::new("test|contains|all|cased", values()).unwrap();
+        let uncased = Field::new("test|contains|all", values()).unwrap();
+        for (value, expected_cased, expected_uncased) in [
+            ("Invoke-WebRequest -Uri http://a", true, true),
+            ("Invoke-WebClientRequest -Uri https://a", true, true),
+            ("invoke-webrequest -uri http://a", false, true),
+            ("INVOKE-WEBREQUEST -URI HTTP://A", false, true),
+            // only the plain value differs in case
+            ("invoke-WebRequest -Uri http://a", false, true),
+            // only a wildcard value differs in case
+            ("Invoke-webrequest -Uri http://a", false, true),
+            ("Invoke-WebRequest http://a", false, false),
+        ] {
+            let event = Event::from([("test", value)]);
+            // evaluate repeatedly, the cached regexes must give the same results
+            for _ in 0..2 {
+                assert_eq!(cased.evaluate(&event), expected_cased, "cased: {}", value);
+                assert_eq!(uncased.evaluate(&event), expected_uncased, "{}", value);
+            }
+        }
+    }
+
+    #[test]
+    fn test_regex_cache_cased() {
+        let mut field = Field::new("test|contains", vec![FieldValue::from("web*request")]).unwrap();
+        field.modifier.cased = true;
+        let event = Event::from([("test", "Invoke-WebRequest")]);
+        assert!(!field.evaluate(&event));
+        assert!(field.evaluate(&Event::from([("test", "webrequest")])));
+
+        // the regex compiled case-sensitively must not be reused
+        field.modifier.cased = false;
+        assert!(field.evaluate(&event));
+        field.modifier.cased = true;
+        assert!(!field.evaluate(&event));
+    }
+
+    #[test]
+    fn test_regex_cache_pattern_type() {
+        let values = || vec![FieldValue::from("c?d")];
+        let starts_with = Field::new("test|startswith", values()).unwrap();
+        let ends_with = Field::new("test|endswith", values()).unwrap();
+        let event = Event::from([("test", "cmd.exe")]);
+        assert!(starts_with.evaluate(&event));
+        assert!(!ends_with.evaluate(&event));
+        let event = Event::from([("test", "/bin/cmd")]);
+        assert!(!starts_with.evaluate(&event));
+        assert!(ends_with.evaluate(&event));
+
+        // the anchored regex of a pattern type must not be reused for another one
+        let mut field = Field::new("test|startswith", values()).unwrap();
+        assert!(field.evaluate(&Event::from([("test", "cmd.exe")])));
+        field.modifier.match_modifier = Some(MatchModifier::EndsWith);
+        assert!(!field.evaluate(&Event::from([("test", "cmd.exe")])));
+        assert!(field.evaluate(&Event::from([("test", "/bin/cmd")])));
+        field.modifier.match_modifier = Some(MatchModifier::Contains);
+        assert!(field.evaluate(&Event::from([("test", "a cmd b")])));
+    }
+
     #[test]
     fn test_evaluate_startswith() {
         let mut field = Field::new(
@@ -494,6 +1794,76 @@ mod tests {
         assert!(!field.evaluate(&event));
     }
 
+    #[test]
+    fn test_evaluate_comparison_over_list() {
+        let ports = |ports: Vec<EventValue>| Event::from([("Ports", EventValue::Sequence(ports))]);
+        let mut field = Field::new("Ports|lt", vec![FieldValue::Int(1024)]).unwrap();
+        let mixed = ports(vec![EventValue::from(80), EventValue::from(2048)]);
+        let low = ports(vec![EventValue::from(22), EventValue::from(80)]);
+        let high = ports(vec![EventValue::from(2048), EventValue::from(8080)]);
+        assert!(field.evaluate(&mixed));
+        assert!(field.evaluate(&low));
+        assert!(!field.evaluate(&high));
+
+        // with all every element must be lower
+        field.modifier.match_all = true;
+        assert!(!field.evaluate(&mixed));
+        assert!(field.evaluate(&low));
+        assert!(!field.evaluate(&high));
+
+        let field = Field::new(
+            "Ports|gte|all",
+            vec![FieldValue::Int(20), FieldValue::Int(22)],
+        )
+        .unwrap();
+        assert!(field.evaluate(&low));
+        assert!(!field.evaluate(&ports(vec![EventValue::from(21), EventValue::from(80)])));
+
+        // elements of other types are ignored, but one element must be comparable
+        let field = Field::new("Ports|lt|all", vec![FieldValue::Int(1024)]).unwrap();
+        let with_others = ports(vec![
+            EventValue::from(80),
+            EventValue::from("2048"),
+            EventValue::from(FieldValue::Null),
+            EventValue::Sequence(vec![EventValue::from(4096)]),
+        ]);
+        assert!(field.evaluate(&with_others));
+        assert!(!field.evaluate(&ports(vec![EventValue::from("80")])));
+        assert!(!field.evaluate(&ports(vec![])));
+        let field = Field::new("Ports|lt", vec![FieldValue::Int(1024)]).unwrap();
+        assert!(field.evaluate(&with_others));
+        assert!(!field.evaluate(&ports(vec![EventValue::from("80")])));
+    }
+
+    #[test]
+    fn test_evaluate_date() {
+        let mut field = Field::new(
+            "Timestamp|gte|date",
+            vec![FieldValue::from("2024-01-01T00:00:00Z")],
+        )
+        .unwrap();
+        // the values are parsed once while bootstrapping
+        assert_eq!(field.values, vec![FieldValue::Timestamp(1_704_067_200_000)]);
+        let event = Event::from([("Timestamp", "2024-03-05T12:00:00+01:00")]);
+        assert!(field.evaluate(&event));
+        let event = Event::from([("Timestamp", "2024-01-01T00:00:00Z")]);
+        assert!(field.evaluate(&event));
+        let event = Event::from([("Timestamp", "2023-12-31T23:59:59Z")]);
+        assert!(!field.evaluate(&event));
+        // invalid event values never match
+        let event = Event::from([("Timestamp", "not a date")]);
+        assert!(!field.evaluate(&event));
+        let event = Event::from([("Timestamp", 1704067200)]);
+        assert!(!field.evaluate(&event));
+
+        field.modifier.match_modifier = Some(MatchModifier::Lt);
+        let event = Event::from([("Timestamp", "2023-12-31")]);
+        assert!(field.evaluate(&event));
+
+        let err = Field::new("Timestamp|lt|date", vec![FieldValue::from("tomorrow")]).unwrap_err();
+        assert!(matches!(err, ParserError::DateParsing(ref a) if a == "tomorrow"));
+    }
+
     #[test]
     fn test_evaluate_regex() {
         let mut field = Field::new(
@@ -521,8 +1891,13 @@ mod tests {
         let mut field = Field {
             name: "test".to_string(),
             values: vec![],
-            regexes: RefCell::new(HashMap::new()),
+            regexes: RegexCache::default(),
             modifier: Modifier::default(),
+            regex_limits: RegexLimits::default(),
+            cidr_set: None,
+            variant_groups: vec![],
+            ascii_affixes: false,
+            coerce_booleans: false,
         };
 
         assert!(field.compare(&FieldValue::from("zsh"), &FieldValue::from("zsh")));
@@ -558,6 +1933,80 @@ mod tests {
         assert!(!field.evaluate(&event));
     }
 
+    #[test]
+    fn test_cidr_single_address() {
+        let field = Field::new(
+            "test|cidr",
+            vec![FieldValue::from("10.0.0.1"), FieldValue::from("fe80::1")],
+        )
+        .unwrap();
+        assert!(matches!(field.values[0], FieldValue::Cidr(c) if c.to_string() == "10.0.0.1"));
+        assert!(matches!(field.values[1], FieldValue::Cidr(c) if c.network_length() == 128));
+
+        for (ip, expected) in [
+            ("10.0.0.1", true),
+            ("10.0.0.2", false),
+            ("fe80::1", true),
+            ("fe80::2", false),
+        ] {
+            let event = Event::from([("test", ip)]);
+            assert_eq!(field.evaluate(&event), expected, "{}", ip);
+        }
+
+        let err = Field::new("test|cidr", vec![FieldValue::from("10.0.0.256")]).unwrap_err();
+        assert!(matches!(err, IPParsing(ref v, _) if v == "10.0.0.256"));
+    }
+
+    #[test]
+    fn test_compiled_patterns() {
+        let field = Field::new(
+            "test|cased",
+            vec![
+                FieldValue::from("a*b?"),
+                FieldValue::from("no wildcards"),
+                FieldValue::from("escaped \\*"),
+            ],
+        )
+        .unwrap();
+        assert_eq!(field.compiled_patterns(), vec!["^a.*b.$"]);
+
+        let field = Field::new("test|startswith", vec![FieldValue::from("/TMP/*.sh")]).unwrap();
+        assert_eq!(field.compiled_patterns(), vec!["(?i)^/tmp/.*\\.sh"]);
+
+        let field = Field::new("test|re", vec![FieldValue::from("^a+b$")]).unwrap();
+        assert_eq!(field.compiled_patterns(), vec!["^a+b$"]);
+
+        let field = Field::new("test|fieldref", vec![FieldValue::from("other*")]).unwrap();
+        assert!(field.compiled_patterns().is_empty());
+    }
+
+    #[test]
+    fn test_cidr_from_ip_types() {
+        let network = IpCidr::from_str("10.0.0.0/24").unwrap();
+        let host = IpAddr::from_str("fe80::1").unwrap();
+        let field = Field::new("test|cidr", vec![network.into(), host.into()]).unwrap();
+        assert!(matches!(field.values[0], FieldValue::Cidr(c) if c == network));
+        assert!(matches!(field.values[1], FieldValue::Cidr(c) if c.network_length() == 128));
+
+        for (ip, expected) in [
+            ("10.0.0.42", true),
+            ("10.0.1.1", false),
+            ("fe80::1", true),
+            ("fe80::2", false),
+        ] {
+            let event = Event::from([("test", ip)]);
+            assert_eq!(field.evaluate(&event), expected, "{}", ip);
+        }
+
+        // addresses of events are matched as well, also without cidr
+        let event = Event::from([("test", IpAddr::from_str("10.0.0.7").unwrap())]);
+        assert!(field.evaluate(&event));
+        let field = Field::new("test", vec![FieldValue::from("10.0.0.7")]).unwrap();
+        assert!(field.evaluate(&event));
+        let field = Field::new("test|startswith", vec![FieldValue::from("10.")]).unwrap();
+        assert!(field.evaluate(&event));
+    }
+
     #[test]
     fn test_base64_utf16le() {
         let patterns = ["Add-MpPreference ", "Set-MpPreference "];
@@ -642,10 +2091,412 @@ mod tests {
         assert!(field.evaluate(&event));
     }
 
+    #[cfg(feature = "unicode-normalization")]
+    #[test]
+    fn test_evaluate_normalize() {
+        let field = Field::new(
+            "CommandLine|normalize|contains",
+            vec![FieldValue::from("café"), FieldValue::from("POWERSHELL")],
+        )
+        .unwrap();
+        for (value, expected) in [
+            // e followed by a combining acute accent
+            ("type cafe\u{301}.txt", true),
+            ("type café.txt", true),
+            // fullwidth letters
+            ("ｐｏｗｅｒｓｈｅｌｌ -nop", true),
+            ("ＰＯＷＥＲＳＨＥＬＬ -nop", true),
+            ("type cafe.txt", false),
+        ] {
+            let event = Event::from([("CommandLine", value)]);
+            assert_eq!(field.evaluate(&event), expected, "{}", value);
+        }
+
+        // the combining variant in the rule matches the composed form in the event
+        let field = Field::new(
+            "Name|normalize|cased",
+            vec![FieldValue::from("Cafe\u{301}")],
+        )
+        .unwrap();
+        assert!(field.evaluate(&Event::from([("Name", "Café")])));
+        assert!(!field.evaluate(&Event::from([("Name", "café")])));
+        assert!(field.literal_terms().is_none());
+
+        let field = Field::new("Name", vec![FieldValue::from("café")]).unwrap();
+        assert!(!field.evaluate(&Event::from([("Name", "cafe\u{301}")])));
+    }
+
+    #[test]
+    fn test_windash_with_all() {
+        let field = Field::new(
+            "test|windash|contains|all",
+            vec![FieldValue::from("-c"), FieldValue::from("-enc")],
+        )
+        .unwrap();
+        // every flag must be present, each in any of its variants
+        for (value, expected) in [
+            ("powershell.exe -c -enc", true),
+            ("powershell.exe /c /enc", true),
+            ("powershell.exe /c \u{2013}enc", true),
+            ("powershell.exe -c", false),
+            ("powershell.exe /enc", false),
+        ] {
+            let event = Event::from([("test", value)]);
+            assert_eq!(field.evaluate(&event), expected, "{}", value);
+        }
+
+        // the same holds for the elements of a list
+        let args = |args: &[&str]| {
+            let elements = args.iter().map(|a| EventValue::from(*a)).collect();
+            Event::from([("test", EventValue::Sequence(elements))])
+        };
+        assert!(field.evaluate(&args(&["powershell.exe", "/c", "-enc"])));
+        assert!(!field.evaluate(&args(&["powershell.exe", "/c"])));
+
+        let field = Field::new(
+            "test|windash|all",
+            vec![FieldValue::from("-c"), FieldValue::from("-enc")],
+        )
+        .unwrap();
+        assert!(field.evaluate(&args(&["/c", "\u{2014}enc"])));
+        assert!(!field.evaluate(&args(&["/c", "/c"])));
+    }
+
+    #[test]
+    fn test_base64offset_with_all() {
+        let field = Field::new(
+            "test|base64offset|contains|all",
+            vec![FieldValue::from("whoami"), FieldValue::from("net user")],
+        )
+        .unwrap();
+        // each value is expanded to its three offset encodings, but only one of them
+        // needs to be present for every value
+        assert_eq!(field.values.len(), 6);
+        assert_eq!(field.value_groups().count(), 2);
+        for (value, expected) in [
+            // cmd /c whoami && net user admin
+            ("Y21kIC9jIHdob2FtaSAmJiBuZXQgdXNlciBhZG1pbg==", true),
+            // cmd /c whoami
+            ("Y21kIC9jIHdob2FtaQ==", false),
+            // x net user admin
+            ("eCBuZXQgdXNlciBhZG1pbg==", false),
+        ] {
+            let event = Event::from([("test", value)]);
+            assert_eq!(field.evaluate(&event), expected, "{}", value);
+        }
+    }
+
+    #[test]
+    fn test_evaluate_null() {
+        let field = Field::new("test", vec![FieldValue::Null]).unwrap();
+        let event = |value: EventValue| Event::from([("test", value)]);
+        let null = || EventValue::from(FieldValue::Null);
+        let list = |elements: Vec<EventValue>| event(EventValue::Sequence(elements));
+        for (event, expected) in [
+            (event(null()), true),
+            (list(vec![]), true),
+            (list(vec![EventValue::from("a"), null()]), true),
+            (list(vec![EventValue::from("a")]), false),
+            (event(EventValue::Map(Default::default())), false),
+            (event(EventValue::from("")), false),
+            (Event::from([("other", "a")]), true),
+        ] {
+            assert_eq!(field.evaluate(&event), expected, "{:?}", event);
+        }
+
+        // other values never match an empty list
+        let field = Field::new("test|contains", vec![FieldValue::from("a")]).unwrap();
+        assert!(!field.evaluate(&list(vec![])));
+        let values = vec![FieldValue::from("a"), FieldValue::Null];
+        let field = Field::new("test", values.clone()).unwrap();
+        assert!(field.evaluate(&list(vec![])));
+        let field = Field::new("test|all", values).unwrap();
+        assert!(!field.evaluate(&list(vec![])));
+        assert!(!field.evaluate(&Event::from([("other", "a")])));
+        let field = Field::new("test", vec![FieldValue::from("a")]).unwrap();
+        assert!(!field.evaluate(&Event::from([("other", "a")])));
+    }
+
+    #[test]
+    fn test_normalize_path_pattern() {
+        let cases = [
+            ("\\rundll32.exe", "/rundll32.exe"),
+            ("C:\\System32\\cmd.exe", "C:/System32/cmd.exe"),
+            ("C:\\Windows\\\\*", "C:/Windows/*"),
+            ("C:/Temp/\\*.exe", "C:/Temp/\\*.exe"),
+            ("a\\?b", "a\\?b"),
+        ];
+        for (pattern, expected) in cases {
+            assert_eq!(normalize_path_pattern(pattern), expected, "{}", pattern);
+        }
+    }
+
+    #[test]
+    fn test_evaluate_path() {
+        let field = Field::new(
+            "Image|endswith|path",
+            vec![FieldValue::from("\\Rundll32.exe")],
+        )
+        .unwrap();
+        for image in [
+            "C:\\Windows\\System32\\rundll32.exe",
+            "C:/Windows/System32/rundll32.exe",
+            "/mnt/c/windows/system32/RUNDLL32.EXE",
+        ] {
+            assert!(
+                field.evaluate(&Event::from([("Image", image)])),
+                "{}",
+                image
+            );
+        }
+        assert!(!field.evaluate(&Event::from([("Image", "C:\\notrundll32.exe")])));
+
+        let field = Field::new(
+            "Image|startswith|path|cased",
+            vec![FieldValue::from("C:/Windows/*/")],
+        )
+        .unwrap();
+        assert!(field.evaluate(&Event::from([("Image", "C:\\Windows\\Temp\\a.exe")])));
+        assert!(!field.evaluate(&Event::from([("Image", "c:\\windows\\temp\\a.exe")])));
+
+        let field = Field::new("Image|path", vec![FieldValue::from("C:\\Temp\\a.exe")]).unwrap();
+        assert!(field.evaluate(&Event::from([("Image", "c:/temp/a.exe")])));
+        assert!(field.literal_terms().is_none());
+    }
+
+    #[test]
+    fn test_string_modifiers_on_numbers() {
+        let field = Field::new("Port|startswith", vec![FieldValue::from("80")]).unwrap();
+        assert!(field.evaluate(&Event::from([("Port", 8080)])));
+        assert!(field.evaluate(&Event::from([("Port", FieldValue::Unsigned(80))])));
+        assert!(!field.evaluate(&Event::from([("Port", 443)])));
+        assert!(field.evaluate(&Event::from([("Port", "8080")])));
+
+        let field = Field::new("Ratio|endswith", vec![FieldValue::from(".5")]).unwrap();
+        assert!(field.evaluate(&Event::from([("Ratio", 10.5)])));
+        // numbers are only matched with contains if they are converted by numstr
+        let field = Field::new("Ratio|contains", vec![FieldValue::from("0.")]).unwrap();
+        assert!(!field.evaluate(&Event::from([("Ratio", 10.0)])));
+        let field = Field::new("Ratio|numstr|contains", vec![FieldValue::from("0.")]).unwrap();
+        assert!(field.evaluate(&Event::from([("Ratio", 10.0)])));
+        let field = Field::new("Id|numstr|contains", vec![FieldValue::from("4*8")]).unwrap();
+        assert!(field.evaluate(&Event::from([("Id", 4688)])));
+        let field = Field::new("Elevated|contains", vec![FieldValue::from("ru")]).unwrap();
+        assert!(field.evaluate(&Event::from([("Elevated", true)])));
+
+        let field = Field::new("Elevated|startswith", vec![FieldValue::from("TR")]).unwrap();
+        assert!(field.evaluate(&Event::from([("Elevated", true)])));
+        let field = Field::new("Elevated|startswith|cased", vec![FieldValue::from("TR")]).unwrap();
+        assert!(!field.evaluate(&Event::from([("Elevated", true)])));
+
+        // null values are not stringified
+        let field = Field::new("Parent|contains", vec![FieldValue::from("null")]).unwrap();
+        assert!(!field.evaluate(&Event::from([("Parent", None)])));
+    }
+
+    #[test]
+    fn test_value_variants_limit() {
+        // every flag adds 4 variants with the other dash characters
+        let flags: Vec<String> = (0..250).map(|i| format!("-f{}", i)).collect();
+        let field = Field::new(
+            "test|windash|contains",
+            vec![FieldValue::from(flags.join(" "))],
+        )
+        .unwrap();
+        assert_eq!(field.values.len(), 1001);
+        assert!(field.validate_value_variants(1001).is_ok());
+        assert!(matches!(
+            field.validate_value_variants(1000),
+            Err(ParserError::TooManyValueVariants(ref name, 1000)) if name == "test"
+        ));
+
+        // the limit applies to all values of the field
+        let values = vec![FieldValue::from("cmd -a -b -c -d -e"); 477];
+        let field = Field::new("test|windash|contains", values[1..].to_vec()).unwrap();
+        assert_eq!(field.values.len(), 9996);
+        let err = Field::new("test|windash|contains", values).unwrap_err();
+        assert!(matches!(
+            err,
+            ParserError::TooManyValueVariants(_, DEFAULT_MAX_VALUE_VARIANTS)
+        ));
+        let values = vec![FieldValue::from("whoami"); 3334];
+        assert!(Field::new("test|base64offset|contains", values[1..].to_vec()).is_ok());
+        assert!(Field::new("test|base64offset|contains", values).is_err());
+
+        // plain values are not limited
+        let field = Field::new("test", vec![FieldValue::from("-f"); 20_000]).unwrap();
+        assert!(field.validate_value_variants(1).is_ok());
+    }
+
+    #[test]
+    fn test_evaluate_sequence() {
+        let event = Event::from([(
+            "test",
+            EventValue::Sequence(vec![
+                EventValue::from("cmd.exe"),
+                EventValue::from("/c"),
+                EventValue::from(42),
+                EventValue::from("WHOAMI"),
+            ]),
+        )]);
+
+        let field = Field::new("test", vec![FieldValue::from("whoami")]).unwrap();
+        assert!(field.evaluate(&event));
+        let field = Field::new("test|cased", vec![FieldValue::from("whoami")]).unwrap();
+        assert!(!field.evaluate(&event));
+        let field = Field::new("test", vec![FieldValue::Int(42)]).unwrap();
+        assert!(field.evaluate(&event));
+
+        let field = Field::new(
+            "test|all",
+            vec![FieldValue::from("/c"), FieldValue::from("whoami")],
+        )
+        .unwrap();
+        assert!(field.evaluate(&event));
+        let field = Field::new(
+            "test|all",
+            vec![FieldValue::from("/c"), FieldValue::from("/k")],
+        )
+        .unwrap();
+        assert!(!field.evaluate(&event));
+
+        let field = Field::new(
+            "test|contains|all",
+            vec![FieldValue::from("cmd"), FieldValue::from("who")],
+        )
+        .unwrap();
+        assert!(field.evaluate(&event));
+        let field = Field::new(
+            "test|endswith|all",
+            vec![FieldValue::from(".exe"), FieldValue::from("ps1")],
+        )
+        .unwrap();
+        assert!(!field.evaluate(&event));
+        let field = Field::new("test", vec![FieldValue::from("who*")]).unwrap();
+        assert!(field.evaluate(&event));
+    }
+
+    #[test]
+    fn test_evaluate_subsequence() {
+        let args = |args: &[&str]| {
+            let elements = args.iter().map(|a| EventValue::from(*a)).collect();
+            Event::from([("Args", EventValue::Sequence(elements))])
+        };
+        let field = Field::new(
+            "Args|sequence",
+            vec![FieldValue::from("cmd"), FieldValue::from("/c")],
+        )
+        .unwrap();
+        assert!(field.evaluate(&args(&["cmd", "/c", "whoami"])));
+        assert!(field.evaluate(&args(&["start", "CMD", "/C", "whoami"])));
+        // both values are present but not adjacent or not in order
+        assert!(!field.evaluate(&args(&["cmd", "/q", "/c", "whoami"])));
+        assert!(!field.evaluate(&args(&["/c", "cmd"])));
+        assert!(!field.evaluate(&args(&["cmd"])));
+        assert!(!field.evaluate(&Event::from([("Args", "cmd /c")])));
+
+        // the same elements match without the sequence modifier
+        let field = Field::new(
+            "Args|all",
+            vec![FieldValue::from("cmd"), FieldValue::from("/c")],
+        )
+        .unwrap();
+        assert!(field.evaluate(&args(&["cmd", "/q", "/c", "whoami"])));
+        assert!(field.evaluate(&args(&["/c", "cmd"])));
+
+        let field = Field::new(
+            "Args|endswith|sequence",
+            vec![
+                FieldValue::from("\\powershell.exe"),
+                FieldValue::from("-enc"),
+            ],
+        )
+        .unwrap();
+        assert!(field.evaluate(&args(&["C:\\powershell.exe", "-enc", "ZQBjAGgAbwA="])));
+        assert!(!field.evaluate(&args(&["C:\\powershell.exe", "-nop", "-enc"])));
+
+        let field = Field::new("Args|sequence", vec![FieldValue::from("cmd")]).unwrap();
+        assert!(field.evaluate(&Event::from([("Args", "cmd")])));
+    }
+
+    #[test]
+    fn test_evaluate_large_sequence_with_all() {
+        let elements = (0..1000)
+            .map(|i| EventValue::from(format!("Value{}", i)))
+            .collect();
+        let event = Event::from([("test", EventValue::Sequence(elements))]);
+
+        let values = (0..1000)
+            .rev()
+            .map(|i| FieldValue::from(format!("value{}", i)))
+            .collect();
+        let mut field = Field::new("test|all", values).unwrap();
+        assert!(field.evaluate(&event));
+
+        field.values.push(FieldValue::from("value1000"));
+        assert!(!field.evaluate(&event));
+        field.modifier.match_all = false;
+        assert!(field.evaluate(&event));
+    }
+
+    #[test]
+    fn test_literal_terms() {
+        let field = Field::new("test|contains", vec![FieldValue::from("AbC")]).unwrap();
+        let terms = field.literal_terms().unwrap();
+        assert!(terms.cased.is_empty());
+        assert_eq!(terms.uncased, vec!["abc"]);
+
+        let field = Field::new("test|cased", vec![FieldValue::from("AbC")]).unwrap();
+        let terms = field.literal_terms().unwrap();
+        assert_eq!(terms.cased, vec!["AbC"]);
+        assert!(terms.uncased.is_empty());
+
+        let field = Field::new("test", vec![FieldValue::from("a*c")]).unwrap();
+        assert!(field.literal_terms().is_none());
+        let field = Field::new("test|re", vec![FieldValue::from("abc")]).unwrap();
+        assert!(field.literal_terms().is_none());
+        let field = Field::new("test", vec![FieldValue::from("a"), FieldValue::Int(4)]).unwrap();
+        assert!(field.literal_terms().is_none());
+    }
+
     #[test]
     fn test_invalid_contains() {
         let values: Vec<FieldValue> = vec![FieldValue::from("ok"), FieldValue::Int(5)];
         let err = Field::new("test|contains", values).unwrap_err();
         assert!(matches!(err, ParserError::InvalidValueForStringModifier(_)));
     }
+
+    #[test]
+    fn test_numstr() {
+        let event = Event::from([("EventID", 4104)]);
+
+        // wildcards only match numbers as strings with numstr
+        let field = Field::new("EventID", vec![FieldValue::from("41*")]).unwrap();
+        assert!(!field.evaluate(&event));
+        let field = Field::new("EventID|numstr", vec![FieldValue::from("41*")]).unwrap();
+        assert!(field.evaluate(&event));
+        let field = Field::new("EventID|numstr", vec![FieldValue::from("42*")]).unwrap();
+        assert!(!field.evaluate(&event));
+
+        // substrings of numbers only match with numstr
+        let field = Field::new("EventID|contains", vec![FieldValue::from("41")]).unwrap();
+        assert!(!field.evaluate(&event));
+        let field = Field::new("EventID|numstr|contains", vec![FieldValue::from("41")]).unwrap();
+        assert!(field.evaluate(&event));
+
+        // numbers of the rule are only accepted for string modifiers with numstr
+        let err = Field::new("EventID|contains", vec![FieldValue::Int(41)]).unwrap_err();
+        assert!(matches!(err, ParserError::InvalidValueForStringModifier(_)));
+        let field = Field::new("EventID|numstr|contains", vec![FieldValue::Int(41)]).unwrap();
+        assert!(field.evaluate(&event));
+        let field = Field::new("EventID|numstr|endswith", vec![FieldValue::Int(41)]).unwrap();
+        assert!(!field.evaluate(&event));
+        let field = Field::new("EventID|numstr", vec![FieldValue::Int(4104)]).unwrap();
+        assert!(field.evaluate(&event));
+
+        // strings are not affected
+        let event = Event::from([("EventID", "4104")]);
+        let field = Field::new("EventID|numstr|startswith", vec![FieldValue::Int(41)]).unwrap();
+        assert!(field.evaluate(&event));
+    }
 }
@@ -0,0 +1,187 @@
+use cidr::IpCidr;
+use std::net::IpAddr;
+
+#[derive(Debug, Clone, Default)]
+struct Node {
+    /// Indices of the child nodes for the next bit being 0 or 1
+    children: [Option<usize>; 2],
+    /// Whether a network ends at this node
+    terminal: bool,
+}
+
+/// A binary trie over the bits of network addresses, the path from the root to a
+/// terminal node is the network prefix.
+#[derive(Debug, Clone)]
+struct Trie {
+    nodes: Vec<Node>,
+    width: u32,
+}
+
+impl Trie {
+    fn new(width: u32) -> Self {
+        Self {
+            nodes: vec![Node::default()],
+            width,
+        }
+    }
+
+    #[inline(always)]
+    fn bit(&self, address: u128, i: u32) -> usize {
+        ((address >> (self.width - 1 - i)) & 1) as usize
+    }
+
+    fn insert(&mut self, address: u128, prefix_length: u8) {
+        let mut current = 0;
+        for i in 0..prefix_length as u32 {
+            if self.nodes[current].terminal {
+                // a shorter network already contains this one
+                return;
+            }
+            let bit = self.bit(address, i);
+            current = match self.nodes[current].children[bit] {
+                Some(child) => child,
+                None => {
+                    self.nodes.push(Node::default());
+                    let child = self.nodes.len() - 1;
+                    self.nodes[current].children[bit] = Some(child);
+                    child
+                }
+            };
+        }
+        self.nodes[current].terminal = true;
+    }
+
+    /// Walks at most `width` nodes, independent of the number of networks
+    fn contains(&self, address: u128) -> bool {
+        let mut current = 0;
+        for i in 0..self.width {
+            if self.nodes[current].terminal {
+                return true;
+            }
+            match self.nodes[current].children[self.bit(address, i)] {
+                Some(child) => current = child,
+                None => return false,
+            }
+        }
+        self.nodes[current].terminal
+    }
+}
+
+/// A set of IPv4 and IPv6 networks optimized for checking whether an address is contained
+/// in any of the networks. A lookup takes at most 32 (IPv4) or 128 (IPv6) steps regardless
+/// of the number of networks, instead of checking the networks one by one.
+#[derive(Debug, Clone)]
+pub(crate) struct CidrSet {
+    v4: Trie,
+    v6: Trie,
+}
+
+impl Default for CidrSet {
+    fn default() -> Self {
+        Self {
+            v4: Trie::new(32),
+            v6: Trie::new(128),
+        }
+    }
+}
+
+impl<'a> FromIterator<&'a IpCidr> for CidrSet {
+    fn from_iter<T: IntoIterator<Item = &'a IpCidr>>(iter: T) -> Self {
+        let mut result = Self::default();
+        for cidr in iter {
+            result.insert(cidr);
+        }
+        result
+    }
+}
+
+impl CidrSet {
+    pub(crate) fn insert(&mut self, cidr: &IpCidr) {
+        match cidr.first_address() {
+            IpAddr::V4(a) => self.v4.insert(u32::from(a) as u128, cidr.network_length()),
+            IpAddr::V6(a) => self.v6.insert(u128::from(a), cidr.network_length()),
+        }
+    }
+
+    pub(crate) fn contains(&self, address: &IpAddr) -> bool {
+        match address {
+            IpAddr::V4(a) => self.v4.contains(u32::from(*a) as u128),
+            IpAddr::V6(a) => self.v6.contains(u128::from(*a)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::Ipv4Addr;
+    use std::str::FromStr;
+
+    fn set(cidrs: &[&str]) -> CidrSet {
+        let cidrs: Vec<IpCidr> = cidrs.iter().map(|c| IpCidr::from_str(c).unwrap()).collect();
+        cidrs.iter().collect()
+    }
+
+    fn contains(set: &CidrSet, ip: &str) -> bool {
+        set.contains(&IpAddr::from_str(ip).unwrap())
+    }
+
+    #[test]
+    fn test_contains() {
+        let set = set(&[
+            "10.0.0.0/8",
+            "10.1.0.0/16",
+            "192.168.1.0/24",
+            "172.16.5.4/32",
+            "2001:db8::/32",
+            "::1/128",
+        ]);
+        for ip in [
+            "10.0.0.1",
+            "10.255.255.255",
+            "192.168.1.77",
+            "172.16.5.4",
+            "2001:db8::1",
+            "2001:db8:ffff::",
+            "::1",
+        ] {
+            assert!(contains(&set, ip), "{}", ip);
+        }
+        for ip in [
+            "11.0.0.1",
+            "192.168.2.1",
+            "172.16.5.5",
+            "2001:db9::1",
+            "::2",
+            "::ffff:10.0.0.1",
+        ] {
+            assert!(!contains(&set, ip), "{}", ip);
+        }
+    }
+
+    #[test]
+    fn test_contains_all_addresses() {
+        let set = set(&["0.0.0.0/0"]);
+        assert!(contains(&set, "1.2.3.4"));
+        assert!(!contains(&set, "::1"));
+        assert!(!contains(&CidrSet::default(), "1.2.3.4"));
+    }
+
+    #[test]
+    fn test_same_result_as_linear_scan() {
+        let cidrs: Vec<IpCidr> = (0..2000u32)
+            .map(|i| {
+                let length = 8 + (i % 25);
+                // an address with the host bits cleared
+                let address = i.wrapping_mul(2_654_435_761) & (u32::MAX << (32 - length));
+                IpCidr::from_str(&format!("{}/{}", Ipv4Addr::from(address), length)).unwrap()
+            })
+            .collect();
+        let set: CidrSet = cidrs.iter().collect();
+        for i in 0..5000u32 {
+            let ip = IpAddr::from(Ipv4Addr::from(i.wrapping_mul(40_503) << 7));
+            let expected = cidrs.iter().any(|c| c.contains(&ip));
+            assert_eq!(set.contains(&ip), expected, "{}", ip);
+        }
+    }
+}
@@ -0,0 +1,96 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+
+/// A custom decoder of event values, returns the decoded variants of a value.
+/// An empty result means the value cannot be decoded and does not match.
+pub type Decoder = Arc<dyn Fn(&str) -> Vec<String> + Send + Sync>;
+
+/// Custom decoders by the name of the modifier they are used with, e.g. `gzipbase64`
+/// for `Payload|gzipbase64|contains`. The names are matched ignoring case.
+pub type Decoders = HashMap<String, Decoder>;
+
+thread_local! {
+    /// The decoders available to modifiers parsed on this thread, see [`with_decoders`]
+    static DECODERS: RefCell<Decoders> = RefCell::new(HashMap::new());
+}
+
+/// A custom decoder resolved while parsing a modifier, compared by its name
+#[derive(Clone)]
+pub(crate) struct CustomDecoder {
+    pub(crate) name: String,
+    pub(crate) decode: Decoder,
+}
+
+impl fmt::Debug for CustomDecoder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("CustomDecoder").field(&self.name).finish()
+    }
+}
+
+impl PartialEq for CustomDecoder {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name
+    }
+}
+
+impl Eq for CustomDecoder {}
+
+impl Hash for CustomDecoder {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.name.hash(state);
+    }
+}
+
+/// Restores the decoders of the thread when dropped, also if parsing panics
+struct Restore(Option<Decoders>);
+
+impl Drop for Restore {
+    fn drop(&mut self) {
+        if let Some(previous) = self.0.take() {
+            DECODERS.with(|d| *d.borrow_mut() = previous);
+        }
+    }
+}
+
+/// Run `f` with the decoders available to the modifiers it parses. Modifiers are parsed
+/// while deserializing a rule, so the decoders cannot be passed to them directly.
+pub(crate) fn with_decoders<T>(decoders: &Decoders, f: impl FnOnce() -> T) -> T {
+    let lowercased = decoders
+        .iter()
+        .map(|(name, decoder)| (name.to_lowercase(), decoder.clone()))
+        .collect();
+    let _restore = Restore(Some(DECODERS.with(|d| d.replace(lowercased))));
+    f()
+}
+
+/// The decoder registered for the lowercase modifier name, if any
+pub(crate) fn lookup(name: &str) -> Option<CustomDecoder> {
+    DECODERS.with(|d| {
+        d.borrow().get(name).map(|decode| CustomDecoder {
+            name: name.to_string(),
+            decode: decode.clone(),
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_with_decoders() {
+        let reverse: Decoder = Arc::new(|s| vec![s.chars().rev().collect()]);
+        let decoders = Decoders::from([("Reverse".to_string(), reverse)]);
+        assert!(lookup("reverse").is_none());
+        let decoded = with_decoders(&decoders, || {
+            let decoder = lookup("reverse").unwrap();
+            assert_eq!(decoder.name, "reverse");
+            (decoder.decode)("abc")
+        });
+        assert_eq!(decoded, vec!["cba"]);
+        assert!(lookup("reverse").is_none());
+    }
+}
@@ -1,9 +1,11 @@
 use crate::error::ParserError;
+use crate::field::decoder::{self, CustomDecoder};
 use crate::field::ValueTransformer::{Base64, Base64offset};
+use std::fmt;
 use std::str::FromStr;
 use strum::{Display, EnumString};
 
-#[derive(Debug, PartialEq, Display, EnumString)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Display, EnumString)]
 #[strum(serialize_all = "lowercase")]
 pub enum MatchModifier {
     Contains,
@@ -17,7 +19,7 @@ pub enum MatchModifier {
     Cidr,
 }
 
-#[derive(Debug, PartialEq, Display, Clone)]
+#[derive(Debug, PartialEq, Eq, Hash, Display, Clone)]
 pub enum Utf16Modifier {
     Utf16le,
     Utf16be,
@@ -25,7 +27,7 @@ pub enum Utf16Modifier {
     Wide,
 }
 
-#[derive(Debug, PartialEq, Display, EnumString)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Display, EnumString)]
 #[strum(serialize_all = "lowercase")]
 pub enum ValueTransformer {
     Base64(Option<Utf16Modifier>),
@@ -34,11 +36,23 @@ pub enum ValueTransformer {
     // Cased,
 }
 
-#[derive(Debug, Default, PartialEq)]
+#[derive(Debug, Default, PartialEq, Eq, Hash, Clone)]
 pub struct Modifier {
     pub(crate) match_all: bool,
+    pub(crate) sequence: bool,
     pub(crate) fieldref: bool,
     pub(crate) cased: bool,
+    pub(crate) date: bool,
+    pub(crate) trim: bool,
+    pub(crate) path: bool,
+    pub(crate) normalize: bool,
+    pub(crate) json: bool,
+    pub(crate) keys: bool,
+    pub(crate) numstr: bool,
+    pub(crate) base64offset_decode: bool,
+    pub(crate) url_decode: bool,
+    /// A decoder registered with [`crate::rule_from_yaml_with_decoders`]
+    pub(crate) custom_decoder: Option<CustomDecoder>,
     pub(crate) exists: Option<bool>,
     pub(crate) match_modifier: Option<MatchModifier>,
     pub(crate) value_transformer: Option<ValueTransformer>,
@@ -58,6 +72,68 @@ impl FromStr for Utf16Modifier {
     }
 }
 
+impl fmt::Display for Modifier {
+    /// Formats the modifiers as they are appended to a field name, e.g. `|base64|contains|all`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.value_transformer {
+            Some(Base64(utf16) | Base64offset(utf16)) => {
+                let name = match self.value_transformer {
+                    Some(Base64(_)) => "base64",
+                    _ => "base64offset",
+                };
+                write!(f, "|{}", name)?;
+                if let Some(utf16) = utf16 {
+                    write!(f, "|{}", utf16.to_string().to_lowercase())?;
+                }
+            }
+            Some(ValueTransformer::Windash) => write!(f, "|windash")?,
+            None => {}
+        }
+        if self.json {
+            write!(f, "|json")?;
+        }
+        if self.keys {
+            write!(f, "|keys")?;
+        }
+        if self.base64offset_decode {
+            write!(f, "|base64offsetdecode")?;
+        }
+        if self.url_decode {
+            write!(f, "|urldecode")?;
+        }
+        if let Some(decoder) = &self.custom_decoder {
+            write!(f, "|{}", decoder.name)?;
+        }
+        if self.trim {
+            write!(f, "|trim")?;
+        }
+        if self.normalize {
+            write!(f, "|normalize")?;
+        }
+        if self.path {
+            write!(f, "|path")?;
+        }
+        if self.numstr {
+            write!(f, "|numstr")?;
+        }
+        if let Some(m) = &self.match_modifier {
+            write!(f, "|{}", m)?;
+        }
+        let flags = [
+            (self.date, "date"),
+            (self.match_all, "all"),
+            (self.sequence, "sequence"),
+            (self.cased, "cased"),
+            (self.fieldref, "fieldref"),
+            (self.exists.is_some(), "exists"),
+        ];
+        for (_, name) in flags.iter().filter(|(set, _)| *set) {
+            write!(f, "|{}", name)?;
+        }
+        Ok(())
+    }
+}
+
 impl FromStr for Modifier {
     type Err = ParserError;
 
@@ -70,6 +146,10 @@ impl FromStr for Modifier {
                 result.match_all = true;
                 continue;
             }
+            if s == "sequence" {
+                result.sequence = true;
+                continue;
+            }
             if s == "fieldref" {
                 result.fieldref = true;
                 continue;
@@ -78,6 +158,44 @@ impl FromStr for Modifier {
                 result.cased = true;
                 continue;
             }
+            if s == "date" {
+                result.date = true;
+                continue;
+            }
+            if s == "trim" {
+                result.trim = true;
+                continue;
+            }
+            if s == "path" {
+                result.path = true;
+                continue;
+            }
+            #[cfg(feature = "unicode-normalization")]
+            if s == "normalize" {
+                result.normalize = true;
+                continue;
+            }
+            #[cfg(feature = "serde_json")]
+            if s == "json" {
+                result.json = true;
+                continue;
+            }
+            if s == "numstr" {
+                result.numstr = true;
+                continue;
+            }
+            if s == "keys" {
+                result.keys = true;
+                continue;
+            }
+            if s == "base64offsetdecode" {
+                result.base64offset_decode = true;
+                continue;
+            }
+            if s == "urldecode" {
+                result.url_decode = true;
+                continue;
+            }
             if s == "exists" {
                 // The real value of the exists modifier will be set during field parsing
                 // because it is the field value and here we only parse the field name.
@@ -124,6 +242,15 @@ impl FromStr for Modifier {
                 continue;
             }
 
+            // built-in modifiers take precedence over custom decoders of the same name
+            if let Some(custom_decoder) = decoder::lookup(&s) {
+                if let Some(d) = result.custom_decoder {
+                    return Err(Self::Err::ConflictingModifiers(custom_decoder.name, d.name));
+                }
+                result.custom_decoder = Some(custom_decoder);
+                continue;
+            }
+
             return Err(ParserError::UnknownModifier(s));
         }
 
@@ -142,6 +269,82 @@ impl FromStr for Modifier {
             }
         }
 
+        if result.base64offset_decode {
+            if let Some(v) = &result.value_transformer {
+                return Err(Self::Err::ConflictingModifiers(
+                    "base64offsetdecode".to_string(),
+                    v.to_string(),
+                ));
+            }
+            if result.url_decode {
+                return Err(Self::Err::ConflictingModifiers(
+                    "base64offsetdecode".to_string(),
+                    "urldecode".to_string(),
+                ));
+            }
+        }
+
+        if let Some(d) = &result.custom_decoder {
+            let conflicting = [
+                (result.base64offset_decode, "base64offsetdecode".to_string()),
+                (result.url_decode, "urldecode".to_string()),
+            ]
+            .into_iter()
+            .find_map(|(set, name)| set.then_some(name))
+            .or_else(|| result.value_transformer.as_ref().map(|v| v.to_string()));
+            if let Some(m) = conflicting {
+                return Err(Self::Err::ConflictingModifiers(d.name.clone(), m));
+            }
+        }
+
+        // path separators and Unicode are only normalized and numbers only converted to strings
+        // for string comparisons of plain values
+        for (set, name) in [
+            (result.path, "path"),
+            (result.normalize, "normalize"),
+            (result.numstr, "numstr"),
+        ] {
+            if !set {
+                continue;
+            }
+            if let Some(m) = result.match_modifier.as_ref().filter(|m| {
+                !matches!(
+                    m,
+                    MatchModifier::Contains | MatchModifier::StartsWith | MatchModifier::EndsWith
+                )
+            }) {
+                return Err(Self::Err::ConflictingModifiers(
+                    name.to_string(),
+                    m.to_string(),
+                ));
+            }
+            if let Some(v @ (Base64(_) | Base64offset(_))) = &result.value_transformer {
+                return Err(Self::Err::ConflictingModifiers(
+                    name.to_string(),
+                    v.to_string(),
+                ));
+            }
+        }
+
+        // the values are matched in order against consecutive elements of the event list,
+        // this is undefined if a value or an element is expanded to several variants
+        if result.sequence {
+            let conflicting = [
+                (result.match_all, "all".to_string()),
+                (result.fieldref, "fieldref".to_string()),
+                (result.base64offset_decode, "base64offsetdecode".to_string()),
+                (result.url_decode, "urldecode".to_string()),
+                (result.keys, "keys".to_string()),
+            ]
+            .into_iter()
+            .find_map(|(set, name)| set.then_some(name))
+            .or_else(|| result.custom_decoder.as_ref().map(|d| d.name.clone()))
+            .or_else(|| result.value_transformer.as_ref().map(|v| v.to_string()));
+            if let Some(m) = conflicting {
+                return Err(Self::Err::ConflictingModifiers("sequence".to_string(), m));
+            }
+        }
+
         if let (Some(MatchModifier::Re) | Some(MatchModifier::Cidr), Some(_)) =
             (&result.match_modifier, &result.value_transformer)
         {
@@ -150,6 +353,17 @@ impl FromStr for Modifier {
             ));
         }
         
+        if result.date
+            && !matches!(
+                result.match_modifier,
+                Some(
+                    MatchModifier::Gt | MatchModifier::Gte | MatchModifier::Lt | MatchModifier::Lte
+                )
+            )
+        {
+            return Err(Self::Err::DateWithoutComparison());
+        }
+
         if result.exists.is_some() {
             let tmp = Self {
                 exists: Some(bool::default()),
@@ -180,6 +394,168 @@ mod test {
         assert!(matches!(err, ParserError::ExistsNotStandalone()));
     }
 
+    #[test]
+    fn test_trim_modifier() {
+        let modifier = Modifier::from_str("fieldname|trim|endswith").unwrap();
+        assert!(modifier.trim);
+        assert_eq!(modifier.match_modifier, Some(MatchModifier::EndsWith));
+        assert!(!Modifier::from_str("fieldname|endswith").unwrap().trim);
+    }
+
+    #[test]
+    fn test_base64offset_decode_modifier() {
+        let modifier = Modifier::from_str("fieldname|base64offsetdecode|contains").unwrap();
+        assert!(modifier.base64offset_decode);
+        let err = Modifier::from_str("fieldname|base64offsetdecode|base64|contains").unwrap_err();
+        assert!(matches!(err, ParserError::ConflictingModifiers(_, _)));
+    }
+
+    #[test]
+    fn test_url_decode_modifier() {
+        let modifier = Modifier::from_str("fieldname|urldecode|contains").unwrap();
+        assert!(modifier.url_decode);
+        assert!(!modifier.base64offset_decode);
+        let err = Modifier::from_str("fieldname|base64offsetdecode|urldecode").unwrap_err();
+        assert!(matches!(err, ParserError::ConflictingModifiers(_, _)));
+    }
+
+    #[test]
+    fn test_path_modifier() {
+        let modifier = Modifier::from_str("Image|endswith|path").unwrap();
+        assert!(modifier.path);
+        assert!(Modifier::from_str("Image|path").unwrap().path);
+        assert!(Modifier::from_str("Image|windash|contains|path").is_ok());
+        for s in [
+            "Image|path|re",
+            "Image|path|cidr",
+            "Image|path|gt",
+            "Image|base64|path",
+        ] {
+            let err = Modifier::from_str(s).unwrap_err();
+            assert!(
+                matches!(err, ParserError::ConflictingModifiers(ref a, _) if a == "path"),
+                "{}",
+                s
+            );
+        }
+    }
+
+    #[test]
+    fn test_sequence_modifier() {
+        let modifier = Modifier::from_str("CommandLine|sequence").unwrap();
+        assert!(modifier.sequence);
+        assert!(!modifier.match_all);
+        assert!(Modifier::from_str("CommandLine|contains|sequence|cased").is_ok());
+        for s in [
+            "CommandLine|sequence|all",
+            "CommandLine|sequence|fieldref",
+            "CommandLine|windash|sequence",
+            "CommandLine|urldecode|sequence",
+        ] {
+            let err = Modifier::from_str(s).unwrap_err();
+            assert!(
+                matches!(err, ParserError::ConflictingModifiers(ref a, _) if a == "sequence"),
+                "{}",
+                s
+            );
+        }
+    }
+
+    #[cfg(feature = "unicode-normalization")]
+    #[test]
+    fn test_normalize_modifier() {
+        let modifier = Modifier::from_str("CommandLine|normalize|contains").unwrap();
+        assert!(modifier.normalize);
+        assert_eq!(modifier.to_string(), "|normalize|contains");
+        for s in ["CommandLine|normalize|re", "CommandLine|base64|normalize"] {
+            let err = Modifier::from_str(s).unwrap_err();
+            assert!(
+                matches!(err, ParserError::ConflictingModifiers(ref a, _) if a == "normalize"),
+                "{}",
+                s
+            );
+        }
+    }
+
+    #[cfg(not(feature = "unicode-normalization"))]
+    #[test]
+    fn test_normalize_modifier_requires_feature() {
+        let err = Modifier::from_str("CommandLine|normalize|contains").unwrap_err();
+        assert!(matches!(err, ParserError::UnknownModifier(_)));
+    }
+
+    #[cfg(feature = "serde_json")]
+    #[test]
+    fn test_json_modifier() {
+        let modifier = Modifier::from_str("Details.user|json|contains|all").unwrap();
+        assert!(modifier.json);
+        assert_eq!(modifier.to_string(), "|json|contains|all");
+    }
+
+    #[test]
+    fn test_numstr_modifier() {
+        let modifier = Modifier::from_str("EventID|numstr|contains").unwrap();
+        assert!(modifier.numstr);
+        assert_eq!(modifier.to_string(), "|numstr|contains");
+
+        let err = Modifier::from_str("EventID|numstr|gt").unwrap_err();
+        assert!(
+            matches!(err, ParserError::ConflictingModifiers(ref a, ref b) if a == "numstr" && b == "gt")
+        );
+    }
+
+    #[test]
+    fn test_keys_modifier() {
+        let modifier = Modifier::from_str("RegistryValues|keys|contains|all").unwrap();
+        assert!(modifier.keys);
+        assert_eq!(modifier.to_string(), "|keys|contains|all");
+
+        let err = Modifier::from_str("RegistryValues|keys|sequence").unwrap_err();
+        assert!(
+            matches!(err, ParserError::ConflictingModifiers(ref a, ref b) if a == "sequence" && b == "keys")
+        );
+    }
+
+    #[cfg(not(feature = "serde_json"))]
+    #[test]
+    fn test_json_modifier_requires_feature() {
+        let err = Modifier::from_str("Details.user|json").unwrap_err();
+        assert!(matches!(err, ParserError::UnknownModifier(_)));
+    }
+
+    #[test]
+    fn test_display() {
+        for s in [
+            "",
+            "|contains",
+            "|base64offset|utf16le|endswith",
+            "|windash|contains|all|cased",
+            "|trim|startswith",
+            "|path|endswith",
+            "|urldecode|contains|all",
+            "|startswith|sequence|cased",
+            "|gte|date",
+            "|exists",
+        ] {
+            assert_eq!(
+                Modifier::from_str(&format!("a{}", s)).unwrap().to_string(),
+                s
+            );
+        }
+    }
+
+    #[test]
+    fn test_date_modifier() {
+        let modifier = Modifier::from_str("fieldname|gte|date").unwrap();
+        assert!(modifier.date);
+        assert_eq!(modifier.match_modifier, Some(MatchModifier::Gte));
+
+        let err = Modifier::from_str("fieldname|date").unwrap_err();
+        assert!(matches!(err, ParserError::DateWithoutComparison()));
+        let err = Modifier::from_str("fieldname|contains|date").unwrap_err();
+        assert!(matches!(err, ParserError::DateWithoutComparison()));
+    }
+
     #[test]
     fn test_unknown_modifier() {
         let err = Modifier::from_str("test|staartswith").unwrap_err();
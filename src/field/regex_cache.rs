@@ -0,0 +1,142 @@
+use crate::field::MatchModifier;
+use fancy_regex::Regex;
+use std::collections::HashMap;
+use std::sync::{PoisonError, RwLock};
+
+/// A cache of the regexes that wildcard patterns (`*`, `?`) of a field are converted to.
+/// The regexes are cached by pattern, case sensitivity and pattern type, because the same
+/// pattern results in a different regex when it is matched case-insensitively or anchored
+/// differently, e.g. `^a.*` for `startswith` and `a.*$` for `endswith`.
+///
+/// The cache is shared by all threads evaluating the same rule. Lookups only take a
+/// read lock, the write lock is taken briefly to insert a newly compiled regex, so
+/// concurrent evaluations of a rule do not block each other once the cache is warm.
+/// A poisoned lock is recovered from, the cache only ever contains complete entries.
+#[derive(Debug, Default)]
+pub(crate) struct RegexCache(RwLock<HashMap<RegexKind, HashMap<String, Regex>>>);
+
+/// How a pattern is converted to a regex: the pattern type and whether it is case-sensitive
+type RegexKind = (Option<MatchModifier>, bool);
+
+impl Clone for RegexCache {
+    fn clone(&self) -> Self {
+        let regexes = self.0.read().unwrap_or_else(PoisonError::into_inner);
+        Self(RwLock::new(regexes.clone()))
+    }
+}
+
+impl RegexCache {
+    /// Apply `f` to the cached regex of the pattern, returns `None` if it is not cached
+    #[inline(always)]
+    pub(crate) fn with_regex<T>(
+        &self,
+        pattern: &str,
+        kind: &RegexKind,
+        f: impl FnOnce(&Regex) -> T,
+    ) -> Option<T> {
+        let regexes = self.0.read().unwrap_or_else(PoisonError::into_inner);
+        regexes.get(kind)?.get(pattern).map(f)
+    }
+
+    pub(crate) fn insert(&self, pattern: String, kind: RegexKind, regex: Regex) {
+        self.0
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .entry(kind)
+            .or_default()
+            .insert(pattern, regex);
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.0
+            .get_mut()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::thread;
+
+    #[test]
+    fn test_shared_between_threads() {
+        let cache = Arc::new(RegexCache::default());
+        let handles: Vec<_> = (0..4)
+            .map(|i| {
+                let cache = cache.clone();
+                thread::spawn(move || {
+                    let pattern = format!("^a{}", i);
+                    cache.insert(pattern.clone(), (None, true), Regex::new(&pattern).unwrap());
+                    cache.with_regex(&pattern, &(None, true), |r| {
+                        r.is_match(&format!("a{}", i)).unwrap()
+                    })
+                })
+            })
+            .collect();
+        for handle in handles {
+            assert_eq!(handle.join().unwrap(), Some(true));
+        }
+        assert_eq!(
+            cache
+                .with_regex("^a1", &(None, true), |r| r.as_str().to_string())
+                .as_deref(),
+            Some("^a1")
+        );
+        assert!(cache.with_regex("^b", &(None, true), |_| ()).is_none());
+
+        let mut cloned = (*cache).clone();
+        cloned.clear();
+        assert!(cloned.with_regex("^a1", &(None, true), |_| ()).is_none());
+        assert!(cache.with_regex("^a1", &(None, true), |_| ()).is_some());
+    }
+
+    #[test]
+    fn test_cased_key() {
+        let cache = RegexCache::default();
+        cache.insert("a*".to_string(), (None, true), Regex::new("^a.*$").unwrap());
+        assert!(cache.with_regex("a*", &(None, false), |_| ()).is_none());
+        cache.insert(
+            "a*".to_string(),
+            (None, false),
+            Regex::new("(?i)^a.*$").unwrap(),
+        );
+        assert_eq!(
+            cache.with_regex("a*", &(None, true), |r| r.is_match("A").unwrap()),
+            Some(false)
+        );
+        assert_eq!(
+            cache.with_regex("a*", &(None, false), |r| r.is_match("A").unwrap()),
+            Some(true)
+        );
+    }
+
+    #[test]
+    fn test_pattern_type_key() {
+        let cache = RegexCache::default();
+        let starts_with = (Some(MatchModifier::StartsWith), true);
+        let ends_with = (Some(MatchModifier::EndsWith), true);
+        cache.insert(
+            "a*".to_string(),
+            starts_with.clone(),
+            Regex::new("^a.*").unwrap(),
+        );
+        assert!(cache.with_regex("a*", &ends_with, |_| ()).is_none());
+        assert!(cache.with_regex("a*", &(None, true), |_| ()).is_none());
+        cache.insert(
+            "a*".to_string(),
+            ends_with.clone(),
+            Regex::new("a.*$").unwrap(),
+        );
+        assert_eq!(
+            cache.with_regex("a*", &starts_with, |r| r.as_str().to_string()),
+            Some("^a.*".to_string())
+        );
+        assert_eq!(
+            cache.with_regex("a*", &ends_with, |r| r.as_str().to_string()),
+            Some("a.*$".to_string())
+        );
+    }
+}
@@ -1,83 +1,170 @@
 use crate::field::{FieldValue, Utf16Modifier};
-use base64::engine::general_purpose::STANDARD_NO_PAD;
+use base64::alphabet::STANDARD;
+use base64::engine::general_purpose::{GeneralPurpose, GeneralPurposeConfig, STANDARD_NO_PAD};
+use base64::engine::DecodePaddingMode;
 use base64::Engine;
+use std::borrow::Cow;
 use std::collections::HashMap;
 
-pub fn encode_base64(input: &FieldValue, utf16modifier: &Option<Utf16Modifier>) -> String {
-    let mut encoded = match utf16modifier {
-        Some(Utf16Modifier::Utf16le | Utf16Modifier::Wide) => STANDARD_NO_PAD.encode(
-            input
-                .value_to_string()
-                .encode_utf16()
-                .flat_map(|x| x.to_le_bytes())
-                .collect::<Vec<u8>>(),
-        ),
-        Some(Utf16Modifier::Utf16be) => STANDARD_NO_PAD.encode(
-            input
-                .value_to_string()
-                .encode_utf16()
-                .flat_map(|x| x.to_be_bytes())
-                .collect::<Vec<u8>>(),
-        ),
+/// The bytes of the value in the encoding of the UTF-16 modifier, UTF-8 without modifier.
+/// `utf16` is UTF-16LE preceded by a byte order mark, `wide` is an alias for `utf16le`.
+fn encode_bytes(input: &FieldValue, utf16modifier: &Option<Utf16Modifier>) -> Vec<u8> {
+    let value = input.value_to_string();
+    match utf16modifier {
+        Some(Utf16Modifier::Utf16le | Utf16Modifier::Wide) => {
+            value.encode_utf16().flat_map(|x| x.to_le_bytes()).collect()
+        }
+        Some(Utf16Modifier::Utf16be) => {
+            value.encode_utf16().flat_map(|x| x.to_be_bytes()).collect()
+        }
         Some(Utf16Modifier::Utf16) => {
             let mut bytes = vec![0xFF, 0xFE];
-            bytes.extend(
-                input
-                    .value_to_string()
-                    .encode_utf16()
-                    .flat_map(|x| x.to_le_bytes()),
-            );
-            STANDARD_NO_PAD.encode(bytes)
+            bytes.extend(value.encode_utf16().flat_map(|x| x.to_le_bytes()));
+            bytes
         }
-        None => STANDARD_NO_PAD.encode(input.value_to_string()),
-    };
+        None => value.into_bytes(),
+    }
+}
+
+/// Encode the bytes without padding and remove the last character if it depends on the
+/// bytes following the value
+fn encode_base64_bytes(bytes: &[u8]) -> String {
+    let mut encoded = STANDARD_NO_PAD.encode(bytes);
     if encoded.len() % 4 == 2 || encoded.len() % 4 == 3 {
         encoded.pop();
-        encoded
-    } else {
-        encoded
     }
+    encoded
 }
 
+pub fn encode_base64(input: &FieldValue, utf16modifier: &Option<Utf16Modifier>) -> String {
+    encode_base64_bytes(&encode_bytes(input, utf16modifier))
+}
+
+/// The base64 encodings of the value at the three possible offsets within a base64 encoded
+/// stream. The value is shifted by zero, one and two bytes and the characters which depend
+/// on the preceding bytes are removed. The shift is applied to the encoded bytes, so that
+/// it is the same for every UTF-16 modifier and the byte order mark of `utf16` is part of
+/// the value.
 pub fn encode_base64_offset(
     input: &FieldValue,
     utf16modifier: &Option<Utf16Modifier>,
 ) -> Vec<String> {
+    let bytes = encode_bytes(input, utf16modifier);
     let mut encoded = vec![];
-
-    let char_width = match utf16modifier {
-        Some(_) => 2,
-        None => 1,
-    };
-
-    let output_0 = encode_base64(input, utf16modifier);
-    if !output_0.is_empty() {
-        encoded.push(output_0);
+    // the number of characters encoding the shift and the first bytes of the value
+    for (shift, prefix_len) in [(0, 0), (1, 2), (2, 3)] {
+        let mut shifted = vec![0; shift];
+        shifted.extend_from_slice(&bytes);
+        let output = encode_base64_bytes(&shifted);
+        if output.len() > prefix_len {
+            encoded.push(output[prefix_len..].to_string());
+        }
     }
+    encoded
+}
 
-    let mut input_str_1 = input.value_to_string();
-    for _ in 0..char_width {
-        input_str_1.insert(0, '\0');
-    }
-    let mut output_1 = encode_base64(&FieldValue::String(input_str_1), utf16modifier);
-    if output_1.len() > char_width * (1 + char_width) {
-        // Remove first characters
-        output_1.drain(0..char_width * (1 + char_width));
-        encoded.push(output_1)
+/// Accepts base64 with and without padding, and incomplete final groups
+const LENIENT_BASE64: GeneralPurpose = GeneralPurpose::new(
+    &STANDARD,
+    GeneralPurposeConfig::new()
+        .with_decode_padding_mode(DecodePaddingMode::Indifferent)
+        .with_decode_allow_trailing_bits(true),
+);
+
+/// Decode a base64 string that may have been cut out of a larger encoded stream, so that it
+/// does not necessarily start at the boundary of a 4 character group. Decoding is attempted
+/// after skipping 0 to 3 leading characters, the results of all alignments that decode are
+/// returned both as UTF-8 and UTF-16LE text (for e.g. encoded PowerShell commands).
+pub fn decode_base64_offset(input: &str) -> Vec<String> {
+    let mut decoded = vec![];
+    let input = input.trim().trim_end_matches('=');
+    for offset in 0..4 {
+        let Some(mut encoded) = input.get(offset..) else {
+            break;
+        };
+        if encoded.len() % 4 == 1 {
+            // a single trailing character does not encode a full byte
+            encoded = &encoded[..encoded.len() - 1];
+        }
+        let Ok(bytes) = LENIENT_BASE64.decode(encoded) else {
+            continue;
+        };
+        if bytes.is_empty() {
+            continue;
+        }
+        decoded.push(String::from_utf8_lossy(&bytes).into_owned());
+        for start in 0..2 {
+            let utf16: Vec<u16> = bytes[start..]
+                .chunks_exact(2)
+                .map(|c| u16::from_le_bytes([c[0], c[1]]))
+                .collect();
+            if !utf16.is_empty() {
+                decoded.push(String::from_utf16_lossy(&utf16));
+            }
+        }
     }
+    decoded
+}
 
-    let mut input_str_2 = input.value_to_string();
-    for _ in 0..2 * char_width {
-        input_str_2.insert(0, '\0');
+/// The maximum number of times a value is percent-decoded by [`url_decode`]
+const MAX_URL_DECODE_ROUNDS: usize = 3;
+
+fn percent_decode(input: &str) -> String {
+    let bytes = input.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|h| std::str::from_utf8(h).ok())
+            .and_then(|h| u8::from_str_radix(h, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (b, _) => {
+                decoded.push(b);
+                i += 1;
+            }
+        }
     }
-    let mut output_2 = encode_base64(&FieldValue::String(input_str_2), utf16modifier);
-    if output_2.len() > 2 * (char_width * (1 + char_width)) - 1 {
-        // Remove first characters
-        output_2.drain(0..2 * (char_width * (1 + char_width)) - 1);
-        encoded.push(output_2)
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// The NFKC normalization of the value, compatibility characters like the fullwidth `ｃｍｄ`
+/// become `cmd` and combining characters are composed, e.g. `e` followed by U+0301 becomes `é`.
+#[cfg(feature = "unicode-normalization")]
+pub fn nfkc(input: Cow<str>) -> Cow<str> {
+    use unicode_normalization::{is_nfkc_quick, IsNormalized, UnicodeNormalization};
+    match is_nfkc_quick(input.chars()) {
+        IsNormalized::Yes => input,
+        _ => Cow::Owned(input.nfkc().collect()),
     }
+}
 
-    encoded
+/// Without the `unicode-normalization` feature the `normalize` modifier cannot be used
+#[cfg(not(feature = "unicode-normalization"))]
+pub fn nfkc(input: Cow<str>) -> Cow<str> {
+    input
+}
+
+/// Percent-decode a value of the event, e.g. `%2e%2e%2f` becomes `../`. Multiple encoded values
+/// are decoded repeatedly until the value does not change anymore, up to 3 times. Every decoding
+/// step is returned, so that both single and double encoded payloads are found.
+/// Invalid escape sequences like `%zz` are kept as they are, invalid UTF-8 is replaced
+/// with the replacement character. A `+` is not decoded to a space.
+pub fn url_decode(input: &str) -> Vec<String> {
+    let mut decoded = vec![percent_decode(input)];
+    while decoded.len() < MAX_URL_DECODE_ROUNDS {
+        let last = decoded.last().unwrap();
+        let next = percent_decode(last);
+        if &next == last {
+            break;
+        }
+        decoded.push(next);
+    }
+    decoded
 }
 
 pub fn windash_variations(input: &FieldValue) -> Vec<String> {
@@ -131,6 +218,39 @@ mod tests {
         assert_eq!(encode_base64(&input, &None), "");
     }
 
+    #[test]
+    fn test_base64_offset_decoding() {
+        let encoded = STANDARD_NO_PAD.encode("garbage;whoami /priv");
+        let decoded = decode_base64_offset(&encoded);
+        assert!(decoded.contains(&"garbage;whoami /priv".to_string()));
+
+        // cut out of a larger stream, the alignment is found by skipping characters
+        let decoded = decode_base64_offset(&encoded[2..]);
+        assert!(decoded.iter().any(|d| d.ends_with(";whoami /priv")));
+
+        let utf16: Vec<u8> = "IEX whoami"
+            .encode_utf16()
+            .flat_map(|c| c.to_le_bytes())
+            .collect();
+        let decoded = decode_base64_offset(&STANDARD_NO_PAD.encode(utf16));
+        assert!(decoded.contains(&"IEX whoami".to_string()));
+
+        assert!(decode_base64_offset("not base64!").is_empty());
+        assert!(decode_base64_offset("").is_empty());
+    }
+
+    #[test]
+    fn test_url_decoding() {
+        assert_eq!(url_decode("/a/%2e%2e%2Fetc"), vec!["/a/../etc"]);
+        assert_eq!(
+            url_decode("/a/%252e%252e%252fetc"),
+            vec!["/a/%2e%2e%2fetc", "/a/../etc"]
+        );
+        assert_eq!(url_decode("%25252541"), vec!["%252541", "%2541", "%41"]);
+        assert_eq!(url_decode("a+b%zz%4%"), vec!["a+b%zz%4%"]);
+        assert_eq!(url_decode("%C3%A4%ff"), vec!["ä\u{fffd}"]);
+    }
+
     #[test]
     fn test_base64_encoding_utf16_le() {
         let input = FieldValue::from("ping");
@@ -249,6 +369,34 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_base64_offset_wide() {
+        let input = FieldValue::from("::FromBase64String");
+        let encoded = encode_base64_offset(&input, &Some(Utf16Modifier::Wide));
+        assert_eq!(
+            encoded,
+            encode_base64_offset(&input, &Some(Utf16Modifier::Utf16le))
+        );
+        assert_eq!(
+            encoded,
+            [
+                "OgA6AEYAcgBvAG0AQgBhAHMAZQA2ADQAUwB0AHIAaQBuAGcA",
+                "oAOgBGAHIAbwBtAEIAYQBzAGUANgA0AFMAdAByAGkAbgBnA",
+                "6ADoARgByAG8AbQBCAGEAcwBlADYANABTAHQAcgBpAG4AZw",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_base64_offset_utf16() {
+        // the byte order mark is shifted together with the value
+        let encoded = encode_base64_offset(&FieldValue::from("cmd"), &Some(Utf16Modifier::Utf16));
+        assert_eq!(encoded, ["//5jAG0AZA", "/+YwBtAGQA", "//mMAbQBkA"]);
+
+        let encoded = encode_base64_offset(&FieldValue::from("cmd"), &Some(Utf16Modifier::Utf16be));
+        assert_eq!(encoded, ["AGMAbQBk", "BjAG0AZ", "AYwBtAG"]);
+    }
+
     #[test]
     fn test_base64_offset_utf16le_preference() {
         // values taken from
@@ -1,13 +1,15 @@
-use crate::field::{ParserError, MatchModifier};
+use crate::field::regex_cache::RegexCache;
+use crate::field::{MatchModifier, ParserError, RegexLimits};
+use chrono::{DateTime, NaiveDate, NaiveDateTime, SecondsFormat, Utc};
 use cidr::IpCidr;
 // use regex::Regex;
 use fancy_regex::{Regex, escape};
+use std::borrow::Cow;
 use std::cmp::Ordering;
 use std::net::IpAddr;
 use std::str::FromStr;
-use std::collections::HashMap;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum FieldValue {
     String(String),
     Int(i64),
@@ -17,6 +19,16 @@ pub enum FieldValue {
     Null,
     Regex(Regex),
     Cidr(IpCidr),
+    /// A point in time as milliseconds since the Unix epoch, the values of the `date`
+    /// modifier are converted to it once instead of parsing them for every comparison
+    Timestamp(i64),
+}
+
+impl From<DateTime<Utc>> for FieldValue {
+    #[inline(always)]
+    fn from(dt: DateTime<Utc>) -> Self {
+        Self::Timestamp(dt.timestamp_millis())
+    }
 }
 
 impl From<i32> for FieldValue {
@@ -92,6 +104,22 @@ impl From<&str> for FieldValue {
     }
 }
 
+impl From<IpCidr> for FieldValue {
+    #[inline(always)]
+    fn from(cidr: IpCidr) -> Self {
+        Self::Cidr(cidr)
+    }
+}
+
+/// An address is stored as its string, so that it matches like an address written in
+/// the event or rule, e.g. with `startswith`, and as the network of that host with `cidr`
+impl From<IpAddr> for FieldValue {
+    #[inline(always)]
+    fn from(ip: IpAddr) -> Self {
+        Self::String(ip.to_string())
+    }
+}
+
 #[cfg(feature = "serde_json")]
 impl TryFrom<serde_json::Value> for FieldValue {
     type Error = crate::error::JSONError;
@@ -123,13 +151,18 @@ impl TryFrom<serde_yml::Value> for FieldValue {
     fn try_from(value: serde_yml::Value) -> Result<Self, Self::Error> {
         match value {
             serde_yml::Value::Bool(b) => Ok(Self::Boolean(b)),
+            // Integers in decimal, hex (0xFF), octal (0o17) and binary (0b101) notation are
+            // Int, or Unsigned if they exceed i64. Exponents (1e6), .inf and decimals are Float.
             serde_yml::Value::Number(n) => {
-                if n.is_i64() {
-                    Ok(Self::Int(n.as_i64().unwrap()))
-                } else if n.is_f64() {
-                    Ok(Self::Float(n.as_f64().unwrap()))
+                if let Some(i) = n.as_i64() {
+                    Ok(Self::Int(i))
+                } else if let Some(f) = n.as_f64().filter(|f| n.is_f64() && !f.is_nan()) {
+                    Ok(Self::Float(f))
+                } else if let Some(u) = n.as_u64() {
+                    Ok(Self::Unsigned(u))
                 } else {
-                    Ok(Self::Unsigned(n.as_u64().unwrap()))
+                    // NaN never matches any value
+                    Err(ParserError::InvalidNumber(n.to_string()))
                 }
             }
             serde_yml::Value::String(s) => Ok(Self::from(s.to_string())),
@@ -148,7 +181,10 @@ impl PartialEq for FieldValue {
             (Self::Unsigned(a), Self::Unsigned(b)) => a.eq(b),
             (Self::Float(a), Self::Float(b)) => a.eq(b),
             (Self::Boolean(a), Self::Boolean(b)) => a.eq(b),
-            (Self::Regex(a), Self::Regex(b)) => a.as_str().eq(b.as_str()),
+            (Self::Regex(a), Self::Regex(b)) => {
+                canonical_regex(a.as_str()) == canonical_regex(b.as_str())
+            }
+            (Self::Timestamp(a), Self::Timestamp(b)) => a.eq(b),
             (Self::Null, Self::Null) => true,
             _ => false,
         }
@@ -164,6 +200,7 @@ impl PartialOrd for FieldValue {
             (Self::Unsigned(a), Self::Unsigned(b)) => a.partial_cmp(b),
             (Self::Float(a), Self::Float(b)) => a.partial_cmp(b),
             (Self::Boolean(a), Self::Boolean(b)) => a.partial_cmp(b),
+            (Self::Timestamp(a), Self::Timestamp(b)) => a.partial_cmp(b),
             (Self::Null, Self::Null) => Some(Ordering::Equal),
             _ => None,
         }
@@ -176,17 +213,41 @@ impl FieldValue {
         match self {
             Self::String(s) => s.to_string(),
             Self::Int(i) => i.to_string(),
+            // keep the fractional part of whole numbers so that 1.0 does not become "1"
+            Self::Float(f) if f.is_finite() && f.fract() == 0.0 => format!("{:.1}", f),
             Self::Float(f) => f.to_string(),
             Self::Unsigned(u) => u.to_string(),
             Self::Boolean(b) => b.to_string(),
             Self::Regex(r) => r.to_string(),
             Self::Cidr(c) => c.to_string(),
+            Self::Timestamp(t) => DateTime::from_timestamp_millis(*t)
+                .map(|dt| dt.to_rfc3339_opts(SecondsFormat::Millis, true))
+                .unwrap_or_else(|| t.to_string()),
             Self::Null => "null".to_string(),
         }
     }
 
+    /// Convert a wildcard pattern to a regex. The regex is anchored according to the
+    /// pattern type, `None` is plain equality which must match the whole value.
     #[inline(always)]
-    pub fn convert_to_regex(&self, pattern_type: MatchModifier, pattern: &str, cased: bool) -> Regex {
+    pub fn convert_to_regex(
+        &self,
+        pattern_type: Option<MatchModifier>,
+        pattern: &str,
+        cased: bool,
+        limits: &RegexLimits,
+    ) -> Result<Regex, fancy_regex::Error> {
+        limits.build(&self.wildcard_regex_pattern(pattern_type, pattern, cased))
+    }
+
+    /// The pattern of the regex a wildcard pattern is converted to by
+    /// [`FieldValue::convert_to_regex`], without compiling it
+    pub(crate) fn wildcard_regex_pattern(
+        &self,
+        pattern_type: Option<MatchModifier>,
+        pattern: &str,
+        cased: bool,
+    ) -> String {
         let mut regex_pattern = String::new();
         let mut chars = pattern.chars().peekable();
         
@@ -224,14 +285,13 @@ impl FieldValue {
         }
 
         let full_pattern = match pattern_type {
-            MatchModifier::Contains => regex_pattern,
-            MatchModifier::StartsWith => format!("^{}", regex_pattern),
-            MatchModifier::EndsWith => format!("{}$", regex_pattern),
+            Some(MatchModifier::Contains) => regex_pattern,
+            Some(MatchModifier::StartsWith) => format!("^{}", regex_pattern),
+            Some(MatchModifier::EndsWith) => format!("{}$", regex_pattern),
             _ => format!("^{}$", regex_pattern),
         };
         
-        let regex = self.case_compare(&full_pattern, cased);
-        Regex::new(&regex).unwrap()
+        self.case_compare(&full_pattern, cased)
     }
 
     #[inline(always)]
@@ -262,86 +322,213 @@ impl FieldValue {
         false
     }
 
+    /// Match `a` against the wildcard pattern `b` using the cached regex of the pattern.
+    /// Returns `None` if `b` contains no wildcards. A pattern that cannot be compiled
+    /// and a regex engine error during matching (e.g. the backtrack limit was exceeded)
+    /// are treated as no match.
+    #[inline(always)]
+    fn wildcard_match(
+        &self,
+        pattern_type: Option<MatchModifier>,
+        a: &str,
+        b: &str,
+        cased: bool,
+        limits: &RegexLimits,
+        regexes: &RegexCache,
+    ) -> Option<bool> {
+        let kind = (pattern_type, cased);
+        if let Some(result) = regexes.with_regex(b, &kind, |r| r.is_match(a).unwrap_or(false)) {
+            return Some(result);
+        }
+        if !self.contains_unescaped_wildcards(b) {
+            return None;
+        }
+        let Ok(r) = self.convert_to_regex(kind.0.clone(), b, cased, limits) else {
+            return Some(false);
+        };
+        let result = r.is_match(a).unwrap_or(false);
+        regexes.insert(b.to_string(), kind, r);
+        Some(result)
+    }
+
+    /// Whether the string starts with the lowercase ASCII string `other` ignoring ASCII case,
+    /// without lowercasing the string. Wildcards in `other` are not supported.
     #[inline(always)]
-    pub(crate) fn contains(&self, other: &Self, cased: bool, regexes: &mut HashMap<String, Regex>) -> bool {
+    pub(crate) fn starts_with_ignore_ascii_case(&self, other: &Self) -> bool {
         match (self, other) {
             (Self::String(a), Self::String(b)) => {
-                if let Some(r) = regexes.get(b) {
-                    return r.is_match(a).unwrap();
-                }
-                if self.contains_unescaped_wildcards(b) {
-                    let r = self.convert_to_regex(MatchModifier::Contains, &b, cased);
-                    regexes.insert(b.to_string(), r.clone());
-                    r.is_match(a).unwrap()
-                } else {
-                    return a.contains(b)
-                }
+                let (a, b) = (a.as_bytes(), b.as_bytes());
+                a.len() >= b.len() && a[..b.len()].eq_ignore_ascii_case(b)
+            }
+            (_, Self::String(_)) if self.is_scalar() => {
+                Self::String(self.value_to_string()).starts_with_ignore_ascii_case(other)
             }
             _ => false,
         }
     }
 
+    /// Whether the string ends with the lowercase ASCII string `other` ignoring ASCII case,
+    /// see [`FieldValue::starts_with_ignore_ascii_case`]
     #[inline(always)]
-    pub(crate) fn starts_with(&self, other: &Self, cased: bool, regexes: &mut HashMap<String, Regex>) -> bool {
+    pub(crate) fn ends_with_ignore_ascii_case(&self, other: &Self) -> bool {
         match (self, other) {
             (Self::String(a), Self::String(b)) => {
-                if let Some(r) = regexes.get(b) {
-                    return r.is_match(a).unwrap();
-                }
-                if self.contains_unescaped_wildcards(b) {
-                    let r = self.convert_to_regex(MatchModifier::StartsWith, &b, cased);
-                    regexes.insert(b.to_string(), r.clone());
-                    r.is_match(a).unwrap()
-                } else {
-                    return a.starts_with(b)
-                }
+                let (a, b) = (a.as_bytes(), b.as_bytes());
+                a.len() >= b.len() && a[a.len() - b.len()..].eq_ignore_ascii_case(b)
+            }
+            (_, Self::String(_)) if self.is_scalar() => {
+                Self::String(self.value_to_string()).ends_with_ignore_ascii_case(other)
             }
             _ => false,
         }
     }
-    
+
+    /// Whether a boolean equals the other value written as the string `true` or `false`
+    /// ignoring case or as the number `1` or `0`, in either direction. Returns false if
+    /// neither value is a boolean.
     #[inline(always)]
-    pub(crate) fn ends_with(&self, other: &Self, cased: bool, regexes: &mut HashMap<String, Regex>) -> bool {
+    pub(crate) fn coerced_bool_eq(&self, other: &Self) -> bool {
         match (self, other) {
-            (Self::String(a), Self::String(b)) => {
-                if let Some(r) = regexes.get(b) {
-                    return r.is_match(a).unwrap();
-                }
-                if self.contains_unescaped_wildcards(b) {
-                    let r = self.convert_to_regex(MatchModifier::EndsWith, &b, cased);
-                    regexes.insert(b.to_string(), r.clone());
-                    r.is_match(a).unwrap()
-                } else {
-                    return a.ends_with(b)
-                }
+            (Self::Boolean(b), v) | (v, Self::Boolean(b)) => v.as_coerced_bool() == Some(*b),
+            _ => false,
+        }
+    }
+
+    #[inline(always)]
+    pub(crate) fn as_coerced_bool(&self) -> Option<bool> {
+        match self {
+            Self::Boolean(b) => Some(*b),
+            Self::String(s) if s.eq_ignore_ascii_case("true") => Some(true),
+            Self::String(s) if s.eq_ignore_ascii_case("false") => Some(false),
+            Self::Int(1) | Self::Unsigned(1) => Some(true),
+            Self::Int(0) | Self::Unsigned(0) => Some(false),
+            Self::Float(f) if *f == 1.0 => Some(true),
+            Self::Float(f) if *f == 0.0 => Some(false),
+            _ => None,
+        }
+    }
+
+    #[inline(always)]
+    pub(crate) fn is_number(&self) -> bool {
+        matches!(self, Self::Int(_) | Self::Unsigned(_) | Self::Float(_))
+    }
+
+    /// Numbers and booleans of the event are matched by their string representation
+    /// with `startswith` and `endswith`, e.g. the port `8080` starts with `80`.
+    /// With `contains` only booleans are, see [`FieldValue::contains`].
+    #[inline(always)]
+    fn is_scalar(&self) -> bool {
+        matches!(
+            self,
+            Self::Int(_) | Self::Unsigned(_) | Self::Float(_) | Self::Boolean(_)
+        )
+    }
+
+    /// Whether the value contains the string `other`. Numbers never contain a string, e.g.
+    /// `EventID|contains: '41'` does not match `4104` unless the `numstr` modifier
+    /// converts the number to a string first, because a substring of digits is ambiguous.
+    #[inline(always)]
+    pub(crate) fn contains(
+        &self,
+        other: &Self,
+        cased: bool,
+        limits: &RegexLimits,
+        regexes: &RegexCache,
+    ) -> bool {
+        match (self, other) {
+            (Self::String(a), Self::String(b)) => self
+                .wildcard_match(Some(MatchModifier::Contains), a, b, cased, limits, regexes)
+                .unwrap_or_else(|| a.contains(b.as_str())),
+            (Self::Boolean(_), Self::String(_)) => {
+                Self::String(self.value_to_string()).contains(other, cased, limits, regexes)
             }
             _ => false,
         }
     }
 
     #[inline(always)]
-    pub(crate) fn is_equal(&self, other: &Self, cased: bool, regexes: &mut HashMap<String, Regex>) -> bool {
+    pub(crate) fn starts_with(
+        &self,
+        other: &Self,
+        cased: bool,
+        limits: &RegexLimits,
+        regexes: &RegexCache,
+    ) -> bool {
         match (self, other) {
-            (Self::String(a), Self::String(b)) => {
-                if let Some(r) = regexes.get(b) {
-                    return r.is_match(a).unwrap();
-                }
-                if self.contains_unescaped_wildcards(b) {
-                    let r = self.convert_to_regex(MatchModifier::Contains, &b, cased);
-                    regexes.insert(b.to_string(), r.clone());
-                    r.is_match(a).unwrap()
-                } else {
-                    return a == b;
-                }
+            (Self::String(a), Self::String(b)) => self
+                .wildcard_match(
+                    Some(MatchModifier::StartsWith),
+                    a,
+                    b,
+                    cased,
+                    limits,
+                    regexes,
+                )
+                .unwrap_or_else(|| a.starts_with(b.as_str())),
+            (_, Self::String(_)) if self.is_scalar() => {
+                Self::String(self.value_to_string()).starts_with(other, cased, limits, regexes)
             }
+            _ => false,
+        }
+    }
+    
+    #[inline(always)]
+    pub(crate) fn ends_with(
+        &self,
+        other: &Self,
+        cased: bool,
+        limits: &RegexLimits,
+        regexes: &RegexCache,
+    ) -> bool {
+        match (self, other) {
+            (Self::String(a), Self::String(b)) => self
+                .wildcard_match(Some(MatchModifier::EndsWith), a, b, cased, limits, regexes)
+                .unwrap_or_else(|| a.ends_with(b.as_str())),
+            (_, Self::String(_)) if self.is_scalar() => {
+                Self::String(self.value_to_string()).ends_with(other, cased, limits, regexes)
+            }
+            _ => false,
+        }
+    }
+
+    #[inline(always)]
+    pub(crate) fn is_equal(
+        &self,
+        other: &Self,
+        cased: bool,
+        limits: &RegexLimits,
+        regexes: &RegexCache,
+    ) -> bool {
+        match (self, other) {
+            (Self::String(a), Self::String(b)) => self
+                .wildcard_match(None, a, b, cased, limits, regexes)
+                .unwrap_or_else(|| a == b),
             _ => self == other,
         }
     }
 
+    /// Compare two values chronologically, values other than timestamps are parsed
+    /// as ISO 8601 dates or datetimes. Returns `None` if one of the values is not a valid date.
+    #[inline(always)]
+    pub(crate) fn date_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.to_timestamp()?.cmp(&other.to_timestamp()?))
+    }
+
+    /// The value as milliseconds since the Unix epoch, parsing it as an ISO 8601 date
+    /// or datetime if it is not a timestamp already
+    #[inline(always)]
+    pub(crate) fn to_timestamp(&self) -> Option<i64> {
+        match self {
+            Self::Timestamp(t) => Some(*t),
+            _ => parse_datetime(&self.value_to_string()).map(|dt| dt.timestamp_millis()),
+        }
+    }
+
     #[inline(always)]
     pub(crate) fn is_regex_match(&self, target: &str) -> bool {
         match self {
-            Self::Regex(r) => r.is_match(target).unwrap(),
+            // errors of the regex engine, e.g. exceeding the backtrack limit, are no match
+            Self::Regex(r) => r.is_match(target).unwrap_or(false),
             _ => false,
         }
     }
@@ -360,6 +547,48 @@ impl FieldValue {
     }
 }
 
+/// Normalize the flag groups at the start of a regex, e.g. `(?mi)` and `(?i)(?m)` both
+/// become `(?im)`, so patterns differing only in the notation of their flags are equal.
+/// Groups that unset flags (`(?-i)`) or scope them (`(?i:...)`) are kept as they are.
+pub(crate) fn canonical_regex(pattern: &str) -> Cow<'_, str> {
+    let mut flags: Vec<char> = vec![];
+    let mut rest = pattern;
+    while let Some((group, tail)) = rest
+        .strip_prefix("(?")
+        .and_then(|r| r.split_once(')'))
+        .filter(|(group, _)| !group.is_empty() && group.chars().all(|c| c.is_ascii_alphabetic()))
+    {
+        flags.extend(group.chars());
+        rest = tail;
+    }
+    if flags.is_empty() {
+        return Cow::Borrowed(pattern);
+    }
+    flags.sort_unstable();
+    flags.dedup();
+    let flags: String = flags.into_iter().collect();
+    Cow::Owned(format!("(?{}){}", flags, rest))
+}
+
+/// Parse an RFC 3339 / ISO 8601 datetime, e.g. `2024-01-01T10:00:00Z`.
+/// Datetimes without an offset and plain dates (`2024-01-01`) are interpreted as UTC.
+pub(crate) fn parse_datetime(s: &str) -> Option<DateTime<Utc>> {
+    let s = s.trim();
+    if let Ok(dt) = DateTime::parse_from_rfc3339(s) {
+        return Some(dt.with_timezone(&Utc));
+    }
+    let s = s.to_uppercase();
+    for format in ["%Y-%m-%dT%H:%M:%S%.f", "%Y-%m-%d %H:%M:%S%.f"] {
+        if let Ok(dt) = NaiveDateTime::parse_from_str(&s, format) {
+            return Some(dt.and_utc());
+        }
+    }
+    NaiveDate::parse_from_str(&s, "%Y-%m-%d")
+        .ok()
+        .and_then(|d| d.and_hms_opt(0, 0, 0))
+        .map(|dt| dt.and_utc())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -396,4 +625,104 @@ mod tests {
         let field_value = FieldValue::try_from(v["EventID"].clone()).unwrap();
         assert_eq!(field_value, FieldValue::Unsigned(18446744073709551615));
     }
+
+    #[test]
+    fn test_date_cmp() {
+        let a = FieldValue::from("2024-01-01T00:00:00Z");
+        let b = FieldValue::from("2024-01-01T01:00:00+02:00");
+        assert_eq!(a.date_cmp(&b), Some(Ordering::Greater));
+        assert_eq!(b.date_cmp(&a), Some(Ordering::Less));
+        let c = FieldValue::from("2024-01-01");
+        assert_eq!(a.date_cmp(&c), Some(Ordering::Equal));
+        let d = FieldValue::from("2024-01-01 00:00:00.000");
+        assert_eq!(a.date_cmp(&d), Some(Ordering::Equal));
+        let e = FieldValue::from("2024-01-01t00:00:00z");
+        assert_eq!(a.date_cmp(&e), Some(Ordering::Equal));
+        assert_eq!(a.date_cmp(&FieldValue::from("yesterday")), None);
+        assert_eq!(a.date_cmp(&FieldValue::Int(5)), None);
+    }
+
+    #[test]
+    fn test_timestamp() {
+        let a = FieldValue::Timestamp(1_704_067_200_000);
+        let b = FieldValue::from(parse_datetime("2024-01-01T01:00:00+02:00").unwrap());
+        assert_eq!(b, FieldValue::Timestamp(1_704_063_600_000));
+        assert!(a > b);
+        assert!(b < a);
+        assert_eq!(a.partial_cmp(&a.clone()), Some(Ordering::Equal));
+        // timestamps are not ordered with other types, even if they are numbers or dates
+        assert_eq!(a.partial_cmp(&FieldValue::Int(1_704_067_200_000)), None);
+        assert_ne!(a, FieldValue::from("2024-01-01T00:00:00Z"));
+
+        // dates are compared chronologically with timestamps
+        assert_eq!(
+            a.date_cmp(&FieldValue::from("2024-01-01")),
+            Some(Ordering::Equal)
+        );
+        assert_eq!(
+            FieldValue::from("2023-12-31T23:59:59.999Z").date_cmp(&a),
+            Some(Ordering::Less)
+        );
+        assert_eq!(a.date_cmp(&FieldValue::from("yesterday")), None);
+
+        assert_eq!(a.value_to_string(), "2024-01-01T00:00:00.000Z");
+        assert_eq!(a.to_timestamp(), Some(1_704_067_200_000));
+        assert_eq!(
+            FieldValue::from("2024-01-01T00:00:00.5Z").to_timestamp(),
+            Some(1_704_067_200_500)
+        );
+    }
+
+    #[test]
+    fn test_regex_flag_order() {
+        let regex = |p: &str| FieldValue::Regex(Regex::new(p).unwrap());
+        assert_eq!(regex("(?im)^foo"), regex("(?mi)^foo"));
+        assert_eq!(regex("(?i)(?m)^foo"), regex("(?mi)^foo"));
+        assert_eq!(regex("(?ii)foo"), regex("(?i)foo"));
+        assert_ne!(regex("(?i)foo"), regex("foo"));
+        assert_ne!(regex("(?i)foo"), regex("(?m)foo"));
+        assert_ne!(regex("(?i-m)foo"), regex("(?-mi)foo"));
+
+        assert_eq!(canonical_regex("(?smi)a(?m)b"), "(?ims)a(?m)b");
+        assert_eq!(canonical_regex("(?i:a)b"), "(?i:a)b");
+        assert_eq!(canonical_regex("()a"), "()a");
+        assert!(matches!(canonical_regex("abc"), Cow::Borrowed("abc")));
+    }
+
+    #[test]
+    fn test_float_to_string() {
+        assert_eq!(FieldValue::Float(1.0).value_to_string(), "1.0");
+        assert_eq!(FieldValue::Float(-3.0).value_to_string(), "-3.0");
+        assert_eq!(FieldValue::Float(0.5).value_to_string(), "0.5");
+        assert_eq!(FieldValue::Float(42.21).value_to_string(), "42.21");
+        assert_eq!(FieldValue::Float(f64::NAN).value_to_string(), "NaN");
+        assert_eq!(FieldValue::Float(f64::INFINITY).value_to_string(), "inf");
+        assert_eq!(FieldValue::Int(1).value_to_string(), "1");
+    }
+
+    #[test]
+    fn test_from_yaml_numbers() {
+        let parse = |s: &str| {
+            let value: serde_yml::Value = serde_yml::from_str(s).unwrap();
+            FieldValue::try_from(value)
+        };
+        let cases = [
+            ("-1", FieldValue::Int(-1)),
+            ("+5", FieldValue::Int(5)),
+            ("-9223372036854775808", FieldValue::Int(i64::MIN)),
+            ("18446744073709551615", FieldValue::Unsigned(u64::MAX)),
+            ("0xFF", FieldValue::Int(255)),
+            ("0o17", FieldValue::Int(15)),
+            ("0b101", FieldValue::Int(5)),
+            ("1e6", FieldValue::Float(1e6)),
+            ("-2.5e-3", FieldValue::Float(-0.0025)),
+            ("1.0", FieldValue::Float(1.0)),
+            (".inf", FieldValue::Float(f64::INFINITY)),
+            ("1_000", FieldValue::from("1_000")),
+        ];
+        for (s, expected) in cases {
+            assert_eq!(parse(s).unwrap(), expected, "{}", s);
+        }
+        assert!(matches!(parse(".nan"), Err(ParserError::InvalidNumber(_))));
+    }
 }
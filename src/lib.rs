@@ -1,19 +1,210 @@
 #![forbid(unsafe_code)]
 //! `sigma-rust` is a library for parsing and checking Sigma rules against log events.
 
+mod collection;
 mod detection;
 mod error;
 mod event;
 mod field;
+mod lint;
+#[cfg(feature = "loader")]
+mod loader;
 mod rule;
+mod ruleset;
+mod screen;
 mod selection;
+mod timeframe;
 
-pub use event::Event;
-pub use rule::Rule;
+pub use collection::parse_rule_collection;
+pub use detection::{CompiledCondition, DetectionMetrics};
+pub use error::{ParserError, SelectionError};
+pub use event::{Event, MultiEvent};
+pub use field::{Decoder, Decoders, RegexLimits};
+pub use lint::LintWarning;
+#[cfg(feature = "loader")]
+pub use loader::{load_rules_from_dir, LoadedRule};
+pub use rule::{Level, Logsource, LogsourceMapping, LogsourceQuery, MatchContext, Rule};
+pub use ruleset::RuleSet;
+pub use timeframe::Timeframe;
 
-/// Parse a rule from a YAML string
+/// Parse a rule from a YAML string. A leading byte order mark is ignored.
 pub fn rule_from_yaml(yaml: &str) -> Result<Rule, serde_yml::Error> {
-    serde_yml::from_str(yaml)
+    serde_yml::from_str(collection::strip_bom(yaml))
+}
+
+/// Parse all rules from a YAML string containing multiple documents separated by `---`.
+/// Empty documents are skipped.
+///
+/// Rule collections are not resolved, a document with an `action` key
+/// (e.g. `action: global`) results in an error. Use [`parse_rule_collection`] for such files.
+///
+/// # Example
+/// ```rust
+/// use sigma_rust::rules_from_yaml;
+/// let rules = rules_from_yaml(r#"
+/// title: First rule
+/// logsource:
+///     category: test
+/// detection:
+///     selection:
+///         EventID: 4104
+///     condition: selection
+/// ---
+/// title: Second rule
+/// logsource:
+///     category: test
+/// detection:
+///     selection:
+///         EventID: 4103
+///     condition: selection
+/// "#).unwrap();
+/// assert_eq!(rules.len(), 2);
+/// ```
+pub fn rules_from_yaml(yaml: &str) -> Result<Vec<Rule>, serde_yml::Error> {
+    let mut rules = vec![];
+    for value in collection::yaml_documents(yaml)? {
+        if let Some(action) = value.get("action") {
+            let action = action.as_str().unwrap_or_default().to_string();
+            return Err(serde::de::Error::custom(
+                ParserError::UnsupportedCollectionAction(action),
+            ));
+        }
+        rules.push(serde_yml::from_value(value)?);
+    }
+    Ok(rules)
+}
+
+/// Parse a rule from a YAML string and reject top-level keys that are not part of the
+/// Sigma specification, e.g. a misspelled `detection` key.
+///
+/// Custom keys are allowed if they are prefixed with `x-`.
+pub fn rule_from_yaml_strict(yaml: &str) -> Result<Rule, serde_yml::Error> {
+    let rule = rule_from_yaml(yaml)?;
+    rule.check_unknown_keys()
+        .map_err(<serde_yml::Error as serde::de::Error>::custom)?;
+    Ok(rule)
+}
+
+/// Parse a rule from a YAML string, allowing patterns of `1 of` and `all of` in the condition
+/// that match no selection. [`rule_from_yaml`] rejects such conditions because the pattern
+/// is most likely a typo: `1 of` a pattern matching nothing is always false and `all of`
+/// such a pattern is always true. With this function, they are reported by [`Rule::lint`],
+/// together with the selections that are unused as a result.
+///
+/// # Example
+/// ```rust
+/// use sigma_rust::{rule_from_yaml, rule_from_yaml_lenient, LintWarning};
+/// let yaml = r#"
+/// title: Some test title
+/// logsource:
+///     category: test
+/// detection:
+///     selection:
+///         EventID: 4104
+///     condition: 1 of selction*
+/// "#;
+/// assert!(rule_from_yaml(yaml).is_err());
+/// let rule = rule_from_yaml_lenient(yaml).unwrap();
+/// assert_eq!(
+///     rule.lint(),
+///     vec![
+///         LintWarning::UnusedSelection { selection: "selection".to_string() },
+///         LintWarning::UnmatchedQuantifier { pattern: "selction*".to_string() },
+///     ]
+/// );
+/// ```
+pub fn rule_from_yaml_lenient(yaml: &str) -> Result<Rule, serde_yml::Error> {
+    with_lenient_conditions(|| rule_from_yaml(yaml))
+}
+
+/// Run `f` allowing patterns of `1 of` and `all of` that match no selection in the conditions
+/// of all rules it parses, like [`rule_from_yaml_lenient`]. This works with every way to load
+/// rules, e.g. [`rules_from_yaml`], [`parse_rule_collection`], [`rule_from_yaml_strict`] or
+/// [`rule_from_yaml_with_decoders`]. Only rules parsed on the calling thread are affected.
+///
+/// # Example
+/// ```rust
+/// use sigma_rust::{parse_rule_collection, with_lenient_conditions};
+/// let yaml = r#"
+/// title: Some test title
+/// logsource:
+///     category: test
+/// detection:
+///     selection:
+///         EventID: 4104
+///     condition: selection or 1 of filter_*
+/// "#;
+/// assert!(parse_rule_collection(yaml).is_err());
+/// let rules = with_lenient_conditions(|| parse_rule_collection(yaml)).unwrap();
+/// assert_eq!(rules[0].lint().len(), 1);
+/// ```
+pub fn with_lenient_conditions<T>(f: impl FnOnce() -> T) -> T {
+    detection::with_lenient_conditions(f)
+}
+
+/// Parse a rule from a YAML string and apply the given limits to its regexes,
+/// see [`RegexLimits`]. Returns an error if a regex exceeds the size limit.
+///
+/// # Example
+/// ```rust
+/// use sigma_rust::{rule_from_yaml, rule_from_yaml_with_limits, RegexLimits};
+/// let yaml = r#"
+/// title: Some test title
+/// logsource:
+///     category: test
+/// detection:
+///     selection:
+///         CommandLine|re: '\w{50}'
+///     condition: selection
+/// "#;
+/// let limits = RegexLimits {
+///     size_limit: Some(10_000),
+///     backtrack_limit: Some(10_000),
+/// };
+/// assert!(rule_from_yaml(yaml).is_ok());
+/// assert!(rule_from_yaml_with_limits(yaml, limits).is_err());
+/// ```
+pub fn rule_from_yaml_with_limits(
+    yaml: &str,
+    limits: RegexLimits,
+) -> Result<Rule, serde_yml::Error> {
+    let mut rule = rule_from_yaml(yaml)?;
+    rule.detection
+        .set_regex_limits(limits)
+        .map_err(<serde_yml::Error as serde::de::Error>::custom)?;
+    Ok(rule)
+}
+
+/// Parse a rule from a YAML string with custom decoders for event values. A modifier which
+/// is not built in is looked up by name in the decoders, e.g. `Payload|gzipbase64|contains`.
+/// Like `base64offsetdecode` and `urldecode`, the values of the event are decoded and each
+/// decoded variant is matched against the values of the rule. Built-in modifiers take
+/// precedence over decoders of the same name.
+///
+/// # Example
+/// ```rust
+/// use sigma_rust::{rule_from_yaml, rule_from_yaml_with_decoders, Decoder, Decoders, Event};
+/// use std::sync::Arc;
+/// let yaml = r#"
+/// title: Some test title
+/// logsource:
+///     category: test
+/// detection:
+///     selection:
+///         Payload|reverse|contains: 'whoami'
+///     condition: selection
+/// "#;
+/// assert!(rule_from_yaml(yaml).is_err());
+/// let reverse: Decoder = Arc::new(|s| vec![s.chars().rev().collect()]);
+/// let decoders = Decoders::from([("reverse".to_string(), reverse)]);
+/// let rule = rule_from_yaml_with_decoders(yaml, &decoders).unwrap();
+/// assert!(rule.is_match(&Event::from([("Payload", "lla/ imaohw c/ dmc")])));
+/// ```
+pub fn rule_from_yaml_with_decoders(
+    yaml: &str,
+    decoders: &Decoders,
+) -> Result<Rule, serde_yml::Error> {
+    field::with_decoders(decoders, || rule_from_yaml(yaml))
 }
 
 /// Parse an event from a JSON string
@@ -22,6 +213,33 @@ pub fn event_from_json(json: &str) -> Result<Event, serde_json::Error> {
     serde_json::from_str(json)
 }
 
+/// Parse an event from a JSON string keeping all values of duplicate keys.
+///
+/// JSON parsers usually keep only the last value of a key that occurs more than once
+/// in an object, which can be abused to hide a value from a detection. Here the values
+/// of a duplicate key are collected in a list in the order of the source, so a rule
+/// matches if any of them matches. Keys occurring once are parsed like with [`event_from_json`].
+///
+/// # Example
+/// ```rust
+/// use sigma_rust::{event_from_json, event_from_json_raw, rule_from_yaml};
+/// let rule = rule_from_yaml(r#"
+/// title: Calculator
+/// logsource:
+/// detection:
+///     selection:
+///         Image|endswith: '\calc.exe'
+///     condition: selection
+/// "#).unwrap();
+/// let json = r#"{"Image": "C:\\calc.exe", "Image": "C:\\cmd.exe"}"#;
+/// assert!(!rule.is_match(&event_from_json(json).unwrap()));
+/// assert!(rule.is_match(&event_from_json_raw(json).unwrap()));
+/// ```
+#[cfg(feature = "serde_json")]
+pub fn event_from_json_raw(json: &str) -> Result<Event, serde_json::Error> {
+    Event::from_json_raw(json)
+}
+
 /// Parse a list of events from a JSON string
 #[cfg(feature = "serde_json")]
 pub fn events_from_json(json: &str) -> Result<Vec<Event>, serde_json::Error> {
@@ -0,0 +1,211 @@
+use crate::field::MatchModifier;
+use std::fmt;
+
+/// A possible improvement of a rule reported by [`Rule::lint`](crate::Rule::lint)
+#[derive(Debug, Clone, PartialEq)]
+pub enum LintWarning {
+    /// A `re` pattern which is equivalent to a cheaper string modifier,
+    /// e.g. `.*foo.*` is the same as `contains|cased: foo`.
+    SimplifiableRegex {
+        selection: String,
+        field: String,
+        pattern: String,
+        /// The suggested modifiers, e.g. `contains|cased`, or only `cased`
+        /// if a plain equality check is sufficient
+        modifier: String,
+        /// The literal value to use with the suggested modifier
+        value: String,
+    },
+    /// A tag that does not follow the tag syntax of the specification, i.e. it is not
+    /// namespaced or contains characters other than lowercase letters, numerals,
+    /// underscores, hyphens and dots.
+    MalformedTag { tag: String },
+    /// A pattern of `1 of` or `all of` in the condition that matches no selection,
+    /// e.g. because of a typo. `1 of` such a pattern is always false, `all of` always true.
+    /// Only reported for rules parsed with [`rule_from_yaml_lenient`](crate::rule_from_yaml_lenient)
+    /// or [`with_lenient_conditions`](crate::with_lenient_conditions).
+    UnmatchedQuantifier { pattern: String },
+    /// A selection that the condition does not refer to, neither by name nor by a pattern,
+    /// e.g. a filter that was forgotten. It never influences whether the rule matches.
+    UnusedSelection { selection: String },
+}
+
+impl fmt::Display for LintWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::SimplifiableRegex {
+                selection,
+                field,
+                pattern,
+                modifier,
+                value,
+            } => {
+                let separator = if modifier.is_empty() { "" } else { "|" };
+                write!(
+                    f,
+                    "Selection '{}': the regex '{}' of field '{}' can be replaced by '{}{}{}: {}'",
+                    selection, pattern, field, field, separator, modifier, value
+                )
+            }
+            Self::MalformedTag { tag } => write!(f, "The tag '{}' is malformed", tag),
+            Self::UnmatchedQuantifier { pattern } => write!(
+                f,
+                "The pattern '{}' in the condition matches no selection",
+                pattern
+            ),
+            Self::UnusedSelection { selection } => write!(
+                f,
+                "The selection '{}' is not used in the condition",
+                selection
+            ),
+        }
+    }
+}
+
+/// Checks whether the tag is namespaced and only uses the allowed characters.
+pub(crate) fn is_valid_tag(tag: &str) -> bool {
+    let Some((namespace, value)) = tag.split_once('.') else {
+        return false;
+    };
+    !namespace.is_empty()
+        && !value.is_empty()
+        && tag
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || matches!(c, '_' | '-' | '.'))
+}
+
+#[derive(Debug, PartialEq)]
+enum Token {
+    Literal(char),
+    Any,
+    Star,
+    Start,
+    End,
+}
+
+fn tokenize(pattern: &str) -> Option<Vec<Token>> {
+    let mut result = vec![];
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        let token = match c {
+            // only escaped punctuation is a literal, escapes like \d are character classes
+            '\\' => match chars.next() {
+                Some(e) if e.is_ascii_punctuation() => Token::Literal(e),
+                _ => return None,
+            },
+            '.' => Token::Any,
+            '*' => Token::Star,
+            '^' => Token::Start,
+            '$' => Token::End,
+            '+' | '?' | '(' | ')' | '[' | ']' | '{' | '}' | '|' => return None,
+            c => Token::Literal(c),
+        };
+        result.push(token);
+    }
+    Some(result)
+}
+
+/// Checks whether the regex pattern is a literal string, optionally wrapped in anchors or `.*`.
+/// Returns the match modifier with the same semantics (`None` for plain equality) and the literal.
+pub(crate) fn simplify_regex(pattern: &str) -> Option<(Option<MatchModifier>, String)> {
+    let mut tokens = tokenize(pattern)?;
+
+    let mut open_start = true;
+    if tokens.first() == Some(&Token::Start) {
+        tokens.remove(0);
+        open_start = false;
+    }
+    if tokens.starts_with(&[Token::Any, Token::Star]) {
+        tokens.drain(..2);
+        open_start = true;
+    }
+
+    let mut open_end = true;
+    if tokens.last() == Some(&Token::End) {
+        tokens.pop();
+        open_end = false;
+    }
+    if tokens.ends_with(&[Token::Any, Token::Star]) {
+        tokens.truncate(tokens.len() - 2);
+        open_end = true;
+    }
+
+    let mut literal = String::with_capacity(tokens.len());
+    for token in tokens {
+        match token {
+            // Sigma values treat * and ? as wildcards
+            Token::Literal('*' | '?') => return None,
+            Token::Literal(c) => literal.push(c),
+            _ => return None,
+        }
+    }
+    if literal.is_empty() {
+        return None;
+    }
+
+    let modifier = match (open_start, open_end) {
+        (true, true) => Some(MatchModifier::Contains),
+        (false, true) => Some(MatchModifier::StartsWith),
+        (true, false) => Some(MatchModifier::EndsWith),
+        (false, false) => None,
+    };
+    Some((modifier, literal))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_simplify_regex() {
+        let cases = [
+            (".*foo.*", Some(MatchModifier::Contains), "foo"),
+            ("foo", Some(MatchModifier::Contains), "foo"),
+            ("^foo", Some(MatchModifier::StartsWith), "foo"),
+            ("^foo.*", Some(MatchModifier::StartsWith), "foo"),
+            (".*foo$", Some(MatchModifier::EndsWith), "foo"),
+            (r".*\\cmd\.exe$", Some(MatchModifier::EndsWith), r"\cmd.exe"),
+            ("^foo bar$", None, "foo bar"),
+            ("^.*foo", Some(MatchModifier::Contains), "foo"),
+        ];
+        for (pattern, modifier, literal) in cases {
+            assert_eq!(
+                simplify_regex(pattern),
+                Some((modifier, literal.to_string())),
+                "{}",
+                pattern
+            );
+        }
+    }
+
+    #[test]
+    fn test_is_valid_tag() {
+        for tag in [
+            "attack.t1059.001",
+            "attack.execution",
+            "cve.2021-44228",
+            "tlp.amber",
+        ] {
+            assert!(is_valid_tag(tag), "{}", tag);
+        }
+        for tag in [
+            "attack",
+            "attack.T1059",
+            "attack.command and control",
+            ".t1059",
+            "attack.",
+        ] {
+            assert!(!is_valid_tag(tag), "{}", tag);
+        }
+    }
+
+    #[test]
+    fn test_simplify_regex_not_flagged() {
+        for pattern in [
+            ".*", "^$", "foo.bar", "fo+", "colou?r", "(a|b)", "[a-z]+", r"\d{4}", r".*\*.*",
+            r"foo\.*", "a{2}",
+        ] {
+            assert_eq!(simplify_regex(pattern), None, "{}", pattern);
+        }
+    }
+}
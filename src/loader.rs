@@ -0,0 +1,136 @@
+use crate::error::ParserError;
+use crate::rule::Rule;
+use crate::rule_from_yaml;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+/// A rule together with the file it was loaded from, so that matches can be traced
+/// back to their source, see [`load_rules_from_dir`].
+#[derive(Debug, Clone)]
+pub struct LoadedRule {
+    pub rule: Rule,
+    pub source: PathBuf,
+}
+
+/// Load all rules from the `.yml` and `.yaml` files in the directory and its
+/// subdirectories, ordered by path. Every file must contain a single rule.
+///
+/// A file that cannot be read or parsed results in an error with its path, so that
+/// a broken rule does not prevent loading the others. Requires the `loader` feature.
+/// Call it within [`with_lenient_conditions`](crate::with_lenient_conditions) to load rules
+/// whose conditions contain patterns of `1 of` or `all of` that match no selection.
+///
+/// # Example
+/// ```no_run
+/// use sigma_rust::load_rules_from_dir;
+/// for result in load_rules_from_dir("sigma/rules") {
+///     match result {
+///         Ok(loaded) => println!("{}: {}", loaded.source.display(), loaded.rule.title),
+///         Err((path, err)) => eprintln!("{}: {}", path.display(), err),
+///     }
+/// }
+/// ```
+pub fn load_rules_from_dir<P: AsRef<Path>>(
+    path: P,
+) -> Vec<Result<LoadedRule, (PathBuf, ParserError)>> {
+    let root = path.as_ref();
+    WalkDir::new(root)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_map(|entry| match entry {
+            Ok(entry) if entry.file_type().is_file() && is_yaml(entry.path()) => {
+                Some(load_rule(entry.into_path()))
+            }
+            Ok(_) => None,
+            Err(err) => {
+                let path = err.path().unwrap_or(root).to_path_buf();
+                Some(Err((path, ParserError::ReadingFile(io::Error::from(err)))))
+            }
+        })
+        .collect()
+}
+
+fn is_yaml(path: &Path) -> bool {
+    matches!(
+        path.extension().and_then(|e| e.to_str()),
+        Some("yml") | Some("yaml")
+    )
+}
+
+fn load_rule(source: PathBuf) -> Result<LoadedRule, (PathBuf, ParserError)> {
+    let yaml = match fs::read_to_string(&source) {
+        Ok(yaml) => yaml,
+        Err(err) => return Err((source, ParserError::ReadingFile(err))),
+    };
+    match rule_from_yaml(&yaml) {
+        Ok(rule) => Ok(LoadedRule { rule, source }),
+        Err(err) => Err((source, ParserError::YamlParsing(err))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Event;
+    use std::env;
+
+    #[test]
+    fn test_load_rules_from_dir() {
+        let dir = env::temp_dir().join(format!("sigma-rust-loader-{}", std::process::id()));
+        fs::create_dir_all(dir.join("nested")).unwrap();
+        fs::write(
+            dir.join("nested").join("good.yml"),
+            r#"
+title: Good rule
+logsource:
+    category: test
+detection:
+    selection:
+        Image|endswith: \cmd.exe
+    condition: selection
+"#,
+        )
+        .unwrap();
+        fs::write(
+            dir.join("bad.yaml"),
+            r#"
+title: Bad rule
+logsource:
+    category: test
+detection:
+    selection:
+        Image|unknown: cmd.exe
+    condition: selection
+"#,
+        )
+        .unwrap();
+        fs::write(dir.join("README.md"), "not a rule").unwrap();
+
+        let results = load_rules_from_dir(&dir);
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(results.len(), 2);
+        let (path, err) = results[0].as_ref().unwrap_err();
+        assert_eq!(path, &dir.join("bad.yaml"));
+        assert!(matches!(err, ParserError::YamlParsing(_)));
+
+        let loaded = results[1].as_ref().unwrap();
+        assert_eq!(loaded.source, dir.join("nested").join("good.yml"));
+        assert_eq!(loaded.rule.title, "Good rule");
+        assert!(loaded
+            .rule
+            .is_match(&Event::from([("Image", "C:\\Windows\\cmd.exe")])));
+    }
+
+    #[test]
+    fn test_load_rules_from_missing_dir() {
+        let dir = env::temp_dir().join("sigma-rust-loader-does-not-exist");
+        let results = load_rules_from_dir(&dir);
+        assert_eq!(results.len(), 1);
+        let (path, err) = results[0].as_ref().unwrap_err();
+        assert_eq!(path, &dir);
+        assert!(matches!(err, ParserError::ReadingFile(_)));
+    }
+}
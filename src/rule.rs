@@ -1,10 +1,16 @@
 use crate::detection::Detection;
-use crate::event::Event;
+use crate::error::ParserError;
+use crate::event::{Event, EventValue, MultiEvent};
+use crate::lint::{is_valid_tag, LintWarning};
+use chrono::NaiveDate;
 use serde::Deserialize;
 use std::collections::HashMap;
+#[cfg(feature = "serde_json")]
+use std::io::BufRead;
+use std::str::FromStr;
 
 /// Declares the status of the rule
-#[derive(Deserialize, PartialEq, Debug)]
+#[derive(Deserialize, PartialEq, Debug, Clone)]
 #[serde(rename_all = "snake_case")]
 pub enum Status {
     /// the rule is considered as stable and may be used in production systems or dashboards.
@@ -28,7 +34,7 @@ pub enum Status {
 ///   - id: 929a690e-bef0-4204-a928-ef5e620d6fcc
 ///     type: obsolete
 /// ```
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, Clone)]
 pub struct Related {
     pub id: String,
     #[serde(rename = "type")]
@@ -36,7 +42,7 @@ pub struct Related {
 }
 
 /// The related type describes the relationship between the rule and the referred rule.
-#[derive(Deserialize, PartialEq, Debug)]
+#[derive(Deserialize, PartialEq, Debug, Clone)]
 #[serde(rename_all = "snake_case")]
 pub enum RelatedType {
     /// The rule was derived from the referred rule or rules, which may remain active.
@@ -53,7 +59,7 @@ pub enum RelatedType {
 
 /// The logsource describes the log data on which the detection is meant to be applied to.
 /// It describes the log source, the platform, the application and the type that is required in the detection.
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, Clone)]
 pub struct Logsource {
     /// The category value is used to select all log files written of a logical group.
     /// This may cover one or more sources of information depending on the system.
@@ -70,10 +76,123 @@ pub struct Logsource {
     pub definition: Option<String>,
 }
 
+/// Defines which event fields encode the logsource of an event.
+///
+/// A field set to `None` is not checked.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogsourceMapping {
+    /// The event field holding the logsource category
+    pub category: Option<String>,
+    /// The event field holding the logsource product
+    pub product: Option<String>,
+    /// The event field holding the logsource service
+    pub service: Option<String>,
+}
+
+impl Default for LogsourceMapping {
+    fn default() -> Self {
+        Self {
+            category: Some("category".to_string()),
+            product: Some("product".to_string()),
+            service: Some("service".to_string()),
+        }
+    }
+}
+
+impl Logsource {
+    /// Merge this logsource with the given override. The values set in the override take
+    /// precedence, values not set in the override are taken from this logsource.
+    pub fn merge(&self, other: &Logsource) -> Logsource {
+        Logsource {
+            category: other.category.clone().or_else(|| self.category.clone()),
+            product: other.product.clone().or_else(|| self.product.clone()),
+            service: other.service.clone().or_else(|| self.service.clone()),
+            definition: other.definition.clone().or_else(|| self.definition.clone()),
+        }
+    }
+
+    /// Returns false if one of the event fields defined by the mapping contradicts the
+    /// logsource. Missing event fields or logsource values do not cause a mismatch.
+    fn applies_to(&self, event: &Event, mapping: &LogsourceMapping) -> bool {
+        let pairs = [
+            (&self.category, &mapping.category),
+            (&self.product, &mapping.product),
+            (&self.service, &mapping.service),
+        ];
+        pairs.into_iter().all(|(expected, field)| {
+            let (Some(expected), Some(field)) = (expected, field) else {
+                return true;
+            };
+            match event.get(field) {
+                Some(EventValue::Value(v)) => v.value_to_string().eq_ignore_ascii_case(expected),
+                _ => true,
+            }
+        })
+    }
+}
+
+/// A query for the logsource of a rule, created with [`Rule::logsource_matches`].
+///
+/// Values not set in the query match any logsource, values not set in the logsource
+/// of the rule match any query value. Values are compared case-insensitive.
+#[derive(Debug, Clone)]
+pub struct LogsourceQuery<'a> {
+    logsource: &'a Logsource,
+    category: Option<&'a str>,
+    product: Option<&'a str>,
+    service: Option<&'a str>,
+}
+
+impl<'a> LogsourceQuery<'a> {
+    fn new(logsource: &'a Logsource) -> Self {
+        Self {
+            logsource,
+            category: None,
+            product: None,
+            service: None,
+        }
+    }
+
+    /// Require the logsource category of the rule to be the given category
+    pub fn category(mut self, category: &'a str) -> Self {
+        self.category = Some(category);
+        self
+    }
+
+    /// Require the logsource product of the rule to be the given product
+    pub fn product(mut self, product: &'a str) -> Self {
+        self.product = Some(product);
+        self
+    }
+
+    /// Require the logsource service of the rule to be the given service
+    pub fn service(mut self, service: &'a str) -> Self {
+        self.service = Some(service);
+        self
+    }
+
+    /// Returns true if the logsource of the rule matches the query
+    pub fn eval(&self) -> bool {
+        let pairs = [
+            (&self.logsource.category, self.category),
+            (&self.logsource.product, self.product),
+            (&self.logsource.service, self.service),
+        ];
+        pairs
+            .into_iter()
+            .all(|(actual, query)| match (actual, query) {
+                (Some(actual), Some(query)) => actual.eq_ignore_ascii_case(query),
+                _ => true,
+            })
+    }
+}
+
 /// The level describes the criticality of a triggered rule.
 /// While low and medium level events have an informative character,
 /// events with high and critical level should lead to immediate reviews by security analysts.
-#[derive(Deserialize, PartialEq, Debug)]
+///
+/// Levels are ordered by their criticality, from `Informational` to `Critical`.
+#[derive(Deserialize, PartialEq, Eq, PartialOrd, Ord, Debug, Clone, Copy)]
 #[serde(rename_all = "snake_case")]
 pub enum Level {
     /// Rule is intended for enrichment of events, e.g. by tagging them. No case or alerting should be triggered by such rules because it is expected that a huge amount of events will match these rules.
@@ -88,11 +207,38 @@ pub enum Level {
     Critical,
 }
 
+/// Reusable state for matching many events against rules.
+///
+/// Holds the buffer of intermediate selection results so that matching
+/// with [`Rule::is_match_with`] does not allocate a new one for every event.
+/// [`Rule::is_match`] reuses a buffer of the current thread instead.
+/// The buffer only grows, one context can be used for rules of any size.
+#[derive(Debug, Default)]
+pub struct MatchContext {
+    lookup: Vec<Option<bool>>,
+}
+
+impl MatchContext {
+    /// Create a new, empty match context
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+/// Keys of the Sigma specification that have no dedicated field in [`Rule`]
+const SPECIFIED_CUSTOM_KEYS: [&str; 1] = ["scope"];
+
+/// Prefix of custom keys that are accepted in strict parsing mode
+const EXTENSION_KEY_PREFIX: &str = "x-";
+
 /// The `Rule` struct implements the Sigma rule specification 2.0.0 released 08.08.2024.
 ///
 /// The full specification can be found at:
 /// <https://github.com/SigmaHQ/sigma-specification/blob/main/specification/sigma-rules-specification.md>
-#[derive(Deserialize, Debug)]
+///
+/// A `Rule` is `Send + Sync`, a parsed rule can be shared between threads and evaluated
+/// concurrently without cloning it.
+#[derive(Deserialize, Debug, Clone)]
 pub struct Rule {
     /// A brief title for the rule that should contain what the rule is supposed to detect (max. 256 characters)
     pub title: String,
@@ -148,6 +294,33 @@ pub struct Rule {
     pub custom_fields: HashMap<String, serde_yml::Value>,
 }
 
+/// Parse a rule from a YAML string like [`rule_from_yaml`](crate::rule_from_yaml)
+///
+/// # Example
+/// ```rust
+/// use sigma_rust::{Event, Rule};
+/// let rule = r#"
+/// title: Some test title
+/// logsource:
+///     category: test
+/// detection:
+///     selection:
+///         EventID: 4104
+///     condition: selection
+/// "#
+/// .parse::<Rule>()
+/// .unwrap();
+/// assert!(rule.is_match(&Event::from([("EventID", 4104)])));
+/// assert!("title: [".parse::<Rule>().is_err());
+/// ```
+impl FromStr for Rule {
+    type Err = serde_yml::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        crate::rule_from_yaml(s)
+    }
+}
+
 impl Rule {
     /// Check if the event matches the rule
     ///
@@ -175,6 +348,559 @@ impl Rule {
     pub fn is_match(&self, event: &Event) -> bool {
         self.detection.evaluate(event)
     }
+
+    /// Check if the events match the rule. Fields of the events are referenced
+    /// in the rule with the name of their slot as prefix, e.g. `parent.Image`.
+    ///
+    /// # Example
+    /// ```rust
+    /// use sigma_rust::{rule_from_yaml, Event, MultiEvent};
+    /// let rule = rule_from_yaml(r#"
+    /// title: Some test title
+    /// logsource:
+    ///     category: test
+    /// detection:
+    ///     selection:
+    ///         parent.Image|endswith: '\winword.exe'
+    ///         child.Image|endswith: '\cmd.exe'
+    ///     condition: selection
+    /// "#).unwrap();
+    /// let mut events = MultiEvent::new();
+    /// events.insert("parent", Event::from([("Image", "C:\\Office\\winword.exe")]));
+    /// events.insert("child", Event::from([("Image", "C:\\Windows\\cmd.exe")]));
+    /// assert!(rule.is_match_multi(&events));
+    /// ```
+    pub fn is_match_multi(&self, events: &MultiEvent) -> bool {
+        self.is_match(events.as_event())
+    }
+
+    /// Check if the event matches the rule reusing the buffers of the given context.
+    /// Like [`Rule::is_match`] this does not allocate a buffer for every event, but the buffer
+    /// is owned by the caller, e.g. to keep it with the other state of a worker.
+    ///
+    /// # Example
+    /// ```rust
+    /// use sigma_rust::{rule_from_yaml, Event, MatchContext};
+    /// let rule_yaml = r#"
+    /// title: Some test title
+    /// logsource:
+    ///     category: test
+    /// detection:
+    ///     selection:
+    ///         EventID: 4104
+    ///     condition: selection
+    /// "#;
+    /// let rule = rule_from_yaml(rule_yaml).unwrap();
+    /// let mut context = MatchContext::new();
+    /// for id in [4103, 4104, 4105] {
+    ///     let event = Event::from([("EventID", id)]);
+    ///     assert_eq!(rule.is_match_with(&event, &mut context), id == 4104);
+    /// }
+    /// ```
+    pub fn is_match_with(&self, event: &Event, context: &mut MatchContext) -> bool {
+        self.detection.evaluate_with(event, &mut context.lookup)
+    }
+
+    /// Match every line of the reader, parsed as a JSON event, against the rule, e.g. to scan
+    /// a file of newline-delimited JSON without loading it at once. Yields the line number,
+    /// starting at 1, with the result of the match or the error parsing the line. Empty lines
+    /// are skipped. The iteration ends after an error reading from the reader.
+    ///
+    /// # Example
+    /// ```rust
+    /// use sigma_rust::rule_from_yaml;
+    /// let rule = rule_from_yaml(r#"
+    /// title: Some test title
+    /// logsource:
+    ///     category: test
+    /// detection:
+    ///     selection:
+    ///         EventID: 4104
+    ///     condition: selection
+    /// "#).unwrap();
+    /// let log = "{\"EventID\": 4104}\n{\"EventID\": 1}\n";
+    /// let matches: Vec<usize> = rule
+    ///     .match_reader(log.as_bytes())
+    ///     .filter(|(_, result)| matches!(result, Ok(true)))
+    ///     .map(|(line, _)| line)
+    ///     .collect();
+    /// assert_eq!(matches, vec![1]);
+    /// ```
+    #[cfg(feature = "serde_json")]
+    pub fn match_reader<'a, R: BufRead + 'a>(
+        &'a self,
+        reader: R,
+    ) -> impl Iterator<Item = (usize, Result<bool, serde_json::Error>)> + 'a {
+        let mut lines = reader.lines().enumerate();
+        let mut context = MatchContext::new();
+        let mut failed = false;
+        std::iter::from_fn(move || loop {
+            if failed {
+                return None;
+            }
+            let (i, line) = lines.next()?;
+            let line = match line {
+                Ok(line) => line,
+                Err(err) => {
+                    failed = true;
+                    return Some((i + 1, Err(serde_json::Error::io(err))));
+                }
+            };
+            if line.trim().is_empty() {
+                continue;
+            }
+            let result = serde_json::from_str::<Event>(&line)
+                .map(|event| self.is_match_with(&event, &mut context));
+            return Some((i + 1, result));
+        })
+    }
+
+    /// Check if the event matches the rule, but return false early if the event belongs to
+    /// another logsource. The logsource of the event is read from the fields `category`,
+    /// `product` and `service`, use [`Rule::is_match_with_logsource_mapping`] for other fields.
+    pub fn is_match_with_logsource(&self, event: &Event) -> bool {
+        self.is_match_with_logsource_mapping(event, &LogsourceMapping::default())
+    }
+
+    /// Like [`Rule::is_match_with_logsource`] but the event fields encoding
+    /// the logsource are defined by the given mapping.
+    pub fn is_match_with_logsource_mapping(
+        &self,
+        event: &Event,
+        mapping: &LogsourceMapping,
+    ) -> bool {
+        self.logsource.applies_to(event, mapping) && self.is_match(event)
+    }
+
+    /// Query the logsource of this rule, e.g. to select the rules for an event stream.
+    ///
+    /// # Example
+    /// ```rust
+    /// use sigma_rust::rule_from_yaml;
+    /// let rule = rule_from_yaml(r#"
+    /// title: Some test title
+    /// logsource:
+    ///     category: process_creation
+    ///     product: windows
+    /// detection:
+    ///     selection:
+    ///         EventID: 4104
+    ///     condition: selection
+    /// "#).unwrap();
+    /// assert!(rule
+    ///     .logsource_matches()
+    ///     .category("process_creation")
+    ///     .product("windows")
+    ///     .eval());
+    /// assert!(!rule.logsource_matches().product("linux").eval());
+    /// ```
+    pub fn logsource_matches(&self) -> LogsourceQuery<'_> {
+        LogsourceQuery::new(&self.logsource)
+    }
+
+    /// Create a variant of this rule that evaluates the same selections with another condition.
+    /// All other attributes of the rule are copied as is.
+    ///
+    /// # Example
+    /// ```rust
+    /// use sigma_rust::{rule_from_yaml, Event};
+    /// let rule = rule_from_yaml(r#"
+    /// title: Some test title
+    /// logsource:
+    ///     category: test
+    /// detection:
+    ///     selection:
+    ///         EventID: 4104
+    ///     filter:
+    ///         User: admin
+    ///     condition: selection and not filter
+    /// "#).unwrap();
+    /// let variant = rule.with_condition("selection").unwrap();
+    /// let mut event = Event::from([("User", "admin")]);
+    /// event.insert("EventID", 4104);
+    /// assert!(!rule.is_match(&event));
+    /// assert!(variant.is_match(&event));
+    /// ```
+    pub fn with_condition(&self, new_condition: &str) -> Result<Rule, ParserError> {
+        Ok(Rule {
+            detection: self.detection.with_condition(new_condition)?,
+            ..self.clone()
+        })
+    }
+
+    /// Reject rules with `re` patterns which are likely slow to match. The complexity of
+    /// a pattern grows with its length, the nesting of groups and especially with nested
+    /// repetitions like `(a+)+`, which are prone to catastrophic backtracking.
+    /// Returns [`ParserError::RegexTooComplex`] if a pattern exceeds `max_regex_complexity`.
+    ///
+    /// # Example
+    /// ```rust
+    /// use sigma_rust::rule_from_yaml;
+    /// let rule = rule_from_yaml(r#"
+    /// title: Some test title
+    /// logsource:
+    ///     category: test
+    /// detection:
+    ///     selection:
+    ///         CommandLine|re: '^(\w+\s?)+$'
+    ///     condition: selection
+    /// "#).unwrap();
+    /// assert!(rule.validate(1000).is_err());
+    /// ```
+    pub fn validate(&self, max_regex_complexity: usize) -> Result<(), ParserError> {
+        self.detection
+            .validate_regex_complexity(max_regex_complexity)
+    }
+
+    /// Reject rules whose `windash` or `base64offset` modifiers expand the values of a field
+    /// to more than `max_variants` values, e.g. a command line with many flags.
+    /// While parsing, rules are always limited to 10,000 values per field.
+    /// Returns [`ParserError::TooManyValueVariants`] if the limit is exceeded.
+    ///
+    /// # Example
+    /// ```rust
+    /// use sigma_rust::rule_from_yaml;
+    /// let rule = rule_from_yaml(r#"
+    /// title: Some test title
+    /// logsource:
+    ///     category: test
+    /// detection:
+    ///     selection:
+    ///         CommandLine|windash|contains: ' -a -b -c '
+    ///     condition: selection
+    /// "#).unwrap();
+    /// assert!(rule.validate_value_variants(13).is_ok());
+    /// assert!(rule.validate_value_variants(12).is_err());
+    /// ```
+    pub fn validate_value_variants(&self, max_variants: usize) -> Result<(), ParserError> {
+        self.detection.validate_value_variants(max_variants)
+    }
+
+    /// Report parts of the rule which can be expressed in a simpler and faster way,
+    /// e.g. a `re` pattern like `.*foo.*` that is equivalent to `contains|cased: foo`.
+    ///
+    /// # Example
+    /// ```rust
+    /// use sigma_rust::rule_from_yaml;
+    /// let rule = rule_from_yaml(r#"
+    /// title: Some test title
+    /// logsource:
+    ///     category: test
+    /// detection:
+    ///     selection:
+    ///         CommandLine|re: '.*whoami.*'
+    ///     condition: selection
+    /// "#).unwrap();
+    /// let warnings = rule.lint();
+    /// assert_eq!(warnings.len(), 1);
+    /// assert_eq!(
+    ///     warnings[0].to_string(),
+    ///     "Selection 'selection': the regex '.*whoami.*' of field 'CommandLine' can be replaced by 'CommandLine|contains|cased: whoami'"
+    /// );
+    /// ```
+    pub fn lint(&self) -> Vec<LintWarning> {
+        let mut warnings = self.detection.lint();
+        for tag in self.tags.iter().flatten() {
+            if !is_valid_tag(tag) {
+                warnings.push(LintWarning::MalformedTag { tag: tag.clone() });
+            }
+        }
+        warnings
+    }
+
+    /// Returns the patterns of all regexes the rule compiles, sorted and without duplicates.
+    /// These are the values of `re` fields and the regexes that values with wildcards
+    /// (`*` and `?`) are converted to, which are compiled when they are first matched.
+    /// Useful to find expensive patterns before evaluating any event.
+    ///
+    /// # Example
+    /// ```rust
+    /// use sigma_rust::rule_from_yaml;
+    /// let rule = rule_from_yaml(r#"
+    /// title: Some test title
+    /// logsource:
+    ///     category: test
+    /// detection:
+    ///     selection:
+    ///         CommandLine|contains: 'who*mi'
+    ///         Image|endswith: '\cmd.exe'
+    ///     filter:
+    ///         User|re: '^SYSTEM$'
+    ///     condition: selection and not filter
+    /// "#).unwrap();
+    /// assert_eq!(rule.compiled_patterns(), vec!["(?i)who.*mi", "^SYSTEM$"]);
+    /// ```
+    pub fn compiled_patterns(&self) -> Vec<String> {
+        self.detection.compiled_patterns()
+    }
+
+    /// Returns the fingerprints of all fields of the rule, sorted and without duplicates.
+    /// Fields have the same fingerprint if they have the same name, modifiers and values,
+    /// regardless of the order of the values unless they are a `sequence`. Values are
+    /// compared as they are matched, e.g. ignoring case unless the field is `cased`.
+    /// Useful to find fields shared by rules, e.g. to deduplicate their evaluation.
+    /// The fingerprints are stable across platforms and releases and can be persisted.
+    ///
+    /// # Example
+    /// ```rust
+    /// use sigma_rust::rule_from_yaml;
+    /// let rule_yaml = |values: &str| format!(r#"
+    /// title: Some test title
+    /// logsource:
+    ///     category: test
+    /// detection:
+    ///     selection:
+    ///         Image|endswith: {}
+    ///     condition: selection
+    /// "#, values);
+    /// let a = rule_from_yaml(&rule_yaml(r"['\cmd.exe', '\powershell.exe']")).unwrap();
+    /// let b = rule_from_yaml(&rule_yaml(r"['\PowerShell.exe', '\cmd.exe']")).unwrap();
+    /// let c = rule_from_yaml(&rule_yaml(r"['\cmd.exe']")).unwrap();
+    /// assert_eq!(a.field_fingerprints(), b.field_fingerprints());
+    /// assert_ne!(a.field_fingerprints(), c.field_fingerprints());
+    /// ```
+    pub fn field_fingerprints(&self) -> Vec<u64> {
+        self.detection.field_fingerprints()
+    }
+
+    /// Returns true if the level of the rule is at least the given level,
+    /// e.g. to only alert on rules with level `High` and `Critical`.
+    /// Rules without a level are below every level.
+    ///
+    /// # Example
+    /// ```rust
+    /// use sigma_rust::{rule_from_yaml, Level};
+    /// let rule = rule_from_yaml(r#"
+    /// title: Some test title
+    /// logsource:
+    ///     category: test
+    /// detection:
+    ///     selection:
+    ///         EventID: 4104
+    ///     condition: selection
+    /// level: high
+    /// "#).unwrap();
+    /// assert!(rule.level_at_least(Level::Medium));
+    /// assert!(rule.level_at_least(Level::High));
+    /// assert!(!rule.level_at_least(Level::Critical));
+    /// ```
+    pub fn level_at_least(&self, min: Level) -> bool {
+        self.level.is_some_and(|level| level >= min)
+    }
+
+    /// Returns false if the rule is `deprecated` or `unsupported` and should not be used
+    /// for detection. Rules without a status are active.
+    pub fn is_active(&self) -> bool {
+        !matches!(
+            self.status,
+            Some(Status::Deprecated) | Some(Status::Unsupported)
+        )
+    }
+
+    /// Returns true if the rule has the tag or a tag below it, ignoring ASCII case.
+    /// `attack.t1059` matches the tags `attack.t1059` and `attack.t1059.001` but not
+    /// `attack.t1059001`. A tag ending with a dot, e.g. `attack.`, matches every tag
+    /// of that namespace.
+    ///
+    /// # Example
+    /// ```rust
+    /// use sigma_rust::rule_from_yaml;
+    /// let rule = rule_from_yaml(r#"
+    /// title: Some test title
+    /// logsource:
+    ///     category: test
+    /// detection:
+    ///     selection:
+    ///         EventID: 4104
+    ///     condition: selection
+    /// tags:
+    ///     - attack.execution
+    ///     - attack.t1059.001
+    /// "#).unwrap();
+    /// assert!(rule.tags_contain("attack.execution"));
+    /// assert!(rule.tags_contain("attack.t1059"));
+    /// assert!(rule.tags_contain("attack."));
+    /// assert!(!rule.tags_contain("tlp."));
+    /// ```
+    pub fn tags_contain(&self, tag: &str) -> bool {
+        self.tags.iter().flatten().any(|t| {
+            t.len() >= tag.len()
+                && t.is_char_boundary(tag.len())
+                && t[..tag.len()].eq_ignore_ascii_case(tag)
+                && (t.len() == tag.len() || tag.ends_with('.') || t[tag.len()..].starts_with('.'))
+        })
+    }
+
+    /// The tags of the rule grouped by their namespace, i.e. the part before the first dot.
+    /// The values keep nested namespaces, e.g. `attack.t1059.001` results in the value
+    /// `t1059.001` in the namespace `attack`. Tags without a namespace are ignored.
+    ///
+    /// # Example
+    /// ```rust
+    /// use sigma_rust::rule_from_yaml;
+    /// let rule = rule_from_yaml(r#"
+    /// title: Some test title
+    /// logsource:
+    ///     category: test
+    /// detection:
+    ///     selection:
+    ///         EventID: 4104
+    ///     condition: selection
+    /// tags:
+    ///     - attack.execution
+    ///     - attack.t1059.001
+    ///     - tlp.amber
+    /// "#).unwrap();
+    /// let namespaces = rule.tag_namespaces();
+    /// assert_eq!(namespaces["attack"], vec!["execution", "t1059.001"]);
+    /// assert_eq!(namespaces["tlp"], vec!["amber"]);
+    /// ```
+    pub fn tag_namespaces(&self) -> HashMap<String, Vec<String>> {
+        let mut result: HashMap<String, Vec<String>> = HashMap::new();
+        for tag in self.tags.iter().flatten() {
+            if let Some((namespace, value)) = tag.split_once('.') {
+                result
+                    .entry(namespace.to_string())
+                    .or_default()
+                    .push(value.to_string());
+            }
+        }
+        result
+    }
+
+    /// The MITRE ATT&CK techniques and sub-techniques referenced by the `attack` tags
+    /// of the rule, e.g. `t1059` or `t1059.001`. Tactics like `attack.execution` are skipped.
+    /// Tags are matched ignoring case and the IDs are lowercase and without duplicates,
+    /// in the order of the tags.
+    pub fn mitre_techniques(&self) -> Vec<String> {
+        let mut result: Vec<String> = vec![];
+        for tag in self.tags.iter().flatten() {
+            let Some((namespace, value)) = tag.split_once('.') else {
+                continue;
+            };
+            if namespace.eq_ignore_ascii_case("attack") && is_technique_id(value) {
+                let id = value.to_ascii_lowercase();
+                if !result.contains(&id) {
+                    result.push(id);
+                }
+            }
+        }
+        result
+    }
+
+    /// Fill in the logsource values that are not set in this rule from the given defaults.
+    /// Values already set in the rule are kept.
+    pub fn with_logsource_defaults(&mut self, defaults: &Logsource) {
+        self.logsource = defaults.merge(&self.logsource);
+    }
+
+    /// Check if a match of this rule on the event is suppressed by any of the given filter rules.
+    /// A filter rule suppresses the event if it matches it, e.g. a global allowlist of
+    /// known benign processes.
+    ///
+    /// # Example
+    /// ```rust
+    /// use sigma_rust::{rule_from_yaml, Event};
+    /// let rule = rule_from_yaml(r#"
+    /// title: Suspicious shell
+    /// logsource:
+    ///     category: test
+    /// detection:
+    ///     selection:
+    ///         Image|endswith: '/bash'
+    ///     condition: selection
+    /// "#).unwrap();
+    /// let filter = rule_from_yaml(r#"
+    /// title: Allowlisted users
+    /// logsource:
+    ///     category: test
+    /// detection:
+    ///     selection:
+    ///         User: backup
+    ///     condition: selection
+    /// "#).unwrap();
+    /// let event = Event::from([("Image", "/bin/bash"), ("User", "backup")]);
+    /// assert!(rule.is_match(&event));
+    /// assert!(rule.is_suppressed_by(&event, &[&filter]));
+    /// ```
+    pub fn is_suppressed_by(&self, event: &Event, filters: &[&Rule]) -> bool {
+        filters.iter().any(|filter| filter.is_match(event))
+    }
+
+    /// Cheap pre-filter to run before [`Rule::is_match`].
+    ///
+    /// Collects the literal strings of the rule (e.g. values of `contains` fields or keywords)
+    /// and searches them in all event values at once. Returns false only if the event cannot
+    /// match the rule; a return value of true does not imply a match.
+    ///
+    /// # Example
+    /// ```rust
+    /// use sigma_rust::{rule_from_yaml, Event};
+    /// let rule_yaml = r#"
+    /// title: Some test title
+    /// logsource:
+    ///     category: test
+    /// detection:
+    ///     selection:
+    ///         CommandLine|contains:
+    ///             - mimikatz
+    ///             - sekurlsa
+    ///     condition: selection
+    /// "#;
+    /// let rule = rule_from_yaml(rule_yaml).unwrap();
+    /// let event = Event::from([("CommandLine", "notepad.exe")]);
+    /// assert!(!rule.quick_screen(&event));
+    /// ```
+    pub fn quick_screen(&self, event: &Event) -> bool {
+        self.detection.quick_screen(event)
+    }
+
+    /// Returns an error listing all top-level keys that are neither defined by the
+    /// Sigma specification nor prefixed with `x-`.
+    pub(crate) fn check_unknown_keys(&self) -> Result<(), ParserError> {
+        let mut unknown: Vec<String> = self
+            .custom_fields
+            .keys()
+            .filter(|k| {
+                !SPECIFIED_CUSTOM_KEYS.contains(&k.as_str()) && !k.starts_with(EXTENSION_KEY_PREFIX)
+            })
+            .cloned()
+            .collect();
+        if unknown.is_empty() {
+            return Ok(());
+        }
+        unknown.sort();
+        Err(ParserError::UnknownRuleKeys(unknown))
+    }
+
+    /// The creation date of the rule parsed from the `date` field.
+    /// Returns `None` if the field is missing or not a valid `YYYY-MM-DD` date.
+    pub fn created_date(&self) -> Option<NaiveDate> {
+        parse_date(self.date.as_deref())
+    }
+
+    /// The last modification date of the rule parsed from the `modified` field.
+    /// Returns `None` if the field is missing or not a valid `YYYY-MM-DD` date.
+    pub fn modified_date(&self) -> Option<NaiveDate> {
+        parse_date(self.modified.as_deref())
+    }
+}
+
+/// Checks for a technique id like `t1059` or a sub-technique id like `t1059.001`
+fn is_technique_id(value: &str) -> bool {
+    let (technique, sub_technique) = match value.split_once('.') {
+        Some((technique, sub_technique)) => (technique, Some(sub_technique)),
+        None => (value, None),
+    };
+    let is_digits = |s: &str, len: usize| s.len() == len && s.chars().all(|c| c.is_ascii_digit());
+    technique
+        .strip_prefix(['t', 'T'])
+        .is_some_and(|id| is_digits(id, 4))
+        && sub_technique.map_or(true, |id| is_digits(id, 3))
+}
+
+fn parse_date(date: Option<&str>) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(date?.trim(), "%Y-%m-%d").ok()
 }
 
 #[cfg(test)]
@@ -267,4 +993,607 @@ mod tests {
         let event = Event::from([("field_name", "this")]);
         assert!(rule.is_match(&event));
     }
+
+    #[test]
+    fn test_match_with_logsource() {
+        let rule_yaml = r#"
+        title: Logsource
+        logsource:
+            category: process_creation
+            product: windows
+        detection:
+          selection:
+            Image|endswith: cmd.exe
+          condition: selection
+        "#;
+        let rule: Rule = serde_yml::from_str(rule_yaml).unwrap();
+
+        let event = Event::from([("Image", "cmd.exe"), ("product", "Linux")]);
+        assert!(rule.is_match(&event));
+        assert!(!rule.is_match_with_logsource(&event));
+
+        let event = Event::from([("Image", "cmd.exe"), ("product", "Windows")]);
+        assert!(rule.is_match_with_logsource(&event));
+        // events without logsource fields are evaluated
+        let event = Event::from([("Image", "cmd.exe")]);
+        assert!(rule.is_match_with_logsource(&event));
+
+        let mapping = LogsourceMapping {
+            category: Some("event.category".to_string()),
+            product: None,
+            service: None,
+        };
+        let event = Event::from([
+            ("Image", "cmd.exe"),
+            ("product", "Linux"),
+            ("event.category", "process_creation"),
+        ]);
+        assert!(rule.is_match_with_logsource_mapping(&event, &mapping));
+        let event = Event::from([("Image", "cmd.exe"), ("event.category", "file_event")]);
+        assert!(!rule.is_match_with_logsource_mapping(&event, &mapping));
+    }
+
+    #[test]
+    fn test_with_condition() {
+        let rule_yaml = r#"
+        title: Variant
+        level: high
+        logsource:
+            category: process_creation
+        detection:
+          selection_img:
+            Image|endswith: '\cmd.exe'
+          selection_cli:
+            CommandLine|contains: whoami
+          condition: selection_img and selection_cli
+        "#;
+        let rule: Rule = serde_yml::from_str(rule_yaml).unwrap();
+        let looser = rule
+            .with_condition("selection_img or selection_cli")
+            .unwrap();
+        assert_eq!(looser.title, rule.title);
+        assert_eq!(looser.level, Some(Level::High));
+        assert_eq!(
+            looser.detection.get_condition(),
+            "selection_img or selection_cli"
+        );
+        assert_eq!(
+            rule.detection.get_condition(),
+            "selection_img and selection_cli"
+        );
+
+        let event = Event::from([("Image", "C:\\Windows\\System32\\cmd.exe")]);
+        assert!(!rule.is_match(&event));
+        assert!(looser.is_match(&event));
+
+        let err = rule.with_condition("selection_img and filter").unwrap_err();
+        assert!(matches!(err, ParserError::UndefinedIdentifiers(ref ids) if ids == &["filter"]));
+        assert!(rule.with_condition("selection_img and").is_err());
+    }
+
+    #[test]
+    fn test_lint() {
+        let rule_yaml = r#"
+        title: Lint
+        logsource:
+            category: process_creation
+        detection:
+          selection_re:
+            Image|re: '.*\\cmd\.exe$'
+            CommandLine|re|cased:
+              - '^whoami'
+              - '^net (user|group)'
+          filter:
+            User|re: '^SYSTEM$'
+          selection_plain:
+            Image|endswith: 'cmd.exe'
+          condition: selection_re and selection_plain and not filter
+        "#;
+        let rule: Rule = serde_yml::from_str(rule_yaml).unwrap();
+        let warnings = rule.lint();
+        assert_eq!(
+            warnings,
+            vec![
+                LintWarning::SimplifiableRegex {
+                    selection: "filter".to_string(),
+                    field: "User".to_string(),
+                    pattern: "^SYSTEM$".to_string(),
+                    modifier: "cased".to_string(),
+                    value: "SYSTEM".to_string(),
+                },
+                LintWarning::SimplifiableRegex {
+                    selection: "selection_re".to_string(),
+                    field: "Image".to_string(),
+                    pattern: ".*\\\\cmd\\.exe$".to_string(),
+                    modifier: "endswith|cased".to_string(),
+                    value: "\\cmd.exe".to_string(),
+                },
+                LintWarning::SimplifiableRegex {
+                    selection: "selection_re".to_string(),
+                    field: "CommandLine".to_string(),
+                    pattern: "^whoami".to_string(),
+                    modifier: "startswith|cased".to_string(),
+                    value: "whoami".to_string(),
+                },
+            ]
+        );
+        assert_eq!(
+            warnings[0].to_string(),
+            "Selection 'filter': the regex '^SYSTEM$' of field 'User' can be replaced by 'User|cased: SYSTEM'"
+        );
+    }
+
+    #[test]
+    fn test_logsource_merge() {
+        let base = Logsource {
+            category: Some("process_creation".to_string()),
+            product: Some("windows".to_string()),
+            service: Some("sysmon".to_string()),
+            definition: None,
+        };
+        let merged = base.merge(&Logsource {
+            category: Some("file_event".to_string()),
+            product: None,
+            service: None,
+            definition: Some("Requires file auditing".to_string()),
+        });
+        assert_eq!(merged.category.as_deref(), Some("file_event"));
+        assert_eq!(merged.product.as_deref(), Some("windows"));
+        assert_eq!(merged.service.as_deref(), Some("sysmon"));
+        assert_eq!(merged.definition.as_deref(), Some("Requires file auditing"));
+    }
+
+    #[test]
+    fn test_logsource_query() {
+        let rule_yaml = r#"
+        title: Query
+        logsource:
+            category: process_creation
+            product: Windows
+        detection:
+          selection:
+            Image|endswith: cmd.exe
+          condition: selection
+        "#;
+        let rule: Rule = serde_yml::from_str(rule_yaml).unwrap();
+        assert!(rule.logsource_matches().eval());
+        assert!(rule.logsource_matches().category("process_creation").eval());
+        assert!(rule.logsource_matches().product("windows").eval());
+        assert!(rule
+            .logsource_matches()
+            .category("PROCESS_CREATION")
+            .product("windows")
+            .eval());
+        // the rule has no service, any service matches
+        assert!(rule
+            .logsource_matches()
+            .category("process_creation")
+            .product("windows")
+            .service("sysmon")
+            .eval());
+        assert!(!rule.logsource_matches().category("file_event").eval());
+        assert!(!rule
+            .logsource_matches()
+            .category("process_creation")
+            .product("linux")
+            .eval());
+    }
+
+    #[test]
+    fn test_with_logsource_defaults() {
+        let rule_yaml = r#"
+        title: Defaults
+        logsource:
+            category: process_creation
+        detection:
+          selection:
+            Image|endswith: cmd.exe
+          condition: selection
+        "#;
+        let mut rule: Rule = serde_yml::from_str(rule_yaml).unwrap();
+        rule.with_logsource_defaults(&Logsource {
+            category: Some("file_event".to_string()),
+            product: Some("windows".to_string()),
+            service: Some("security".to_string()),
+            definition: None,
+        });
+        assert_eq!(rule.logsource.category.as_deref(), Some("process_creation"));
+        assert_eq!(rule.logsource.product.as_deref(), Some("windows"));
+        assert_eq!(rule.logsource.service.as_deref(), Some("security"));
+        assert!(rule.logsource.definition.is_none());
+    }
+
+    #[test]
+    fn test_strict_parsing() {
+        let rule_yaml = r#"
+        title: Strict
+        logsource:
+            category: test
+        detection:
+          selection:
+            field_name: this
+          condition: selection
+        scope:
+            - server
+        x-custom: some value
+        "#;
+        let rule = crate::rule_from_yaml_strict(rule_yaml).unwrap();
+        assert_eq!(rule.custom_fields.len(), 2);
+
+        let rule_yaml = format!("{}\n        levle: high\n        x-other: 1", rule_yaml);
+        let rule = crate::rule_from_yaml(&rule_yaml).unwrap();
+        assert!(matches!(
+            rule.check_unknown_keys(),
+            Err(ParserError::UnknownRuleKeys(ref keys)) if keys == &["levle"]
+        ));
+
+        let rule_yaml = r#"
+        title: Strict
+        logsource:
+            category: test
+        detection:
+          selection:
+            field_name: this
+          condition: selection
+        detectoin:
+          selection:
+            field_name: that
+        "#;
+        let err = crate::rule_from_yaml_strict(rule_yaml).unwrap_err();
+        assert!(err.to_string().contains("detectoin"));
+    }
+
+    #[test]
+    fn test_parse_dates() {
+        let rule_yaml = r#"
+        title: Dates
+        date: 2020-12-30
+        modified: 2024/01/02
+        logsource:
+            category: test
+        detection:
+          selection:
+            field_name: this
+          condition: selection
+        "#;
+        let mut rule: Rule = serde_yml::from_str(rule_yaml).unwrap();
+        assert_eq!(rule.created_date(), NaiveDate::from_ymd_opt(2020, 12, 30));
+        // malformed dates do not fail the rule parsing and the raw value is kept
+        assert!(rule.modified_date().is_none());
+        assert_eq!(rule.modified, Some("2024/01/02".to_string()));
+
+        rule.modified = Some("2024-02-30".to_string());
+        assert!(rule.modified_date().is_none());
+        rule.modified = Some("2024-02-29".to_string());
+        assert_eq!(rule.modified_date(), NaiveDate::from_ymd_opt(2024, 2, 29));
+        rule.date = None;
+        assert!(rule.created_date().is_none());
+    }
+
+    #[test]
+    fn test_rules_from_yaml() {
+        let rules_yaml = r#"
+title: First
+logsource:
+    category: test
+detection:
+    selection:
+        Image|endswith: '\cmd.exe'
+    condition: selection
+---
+title: Second
+logsource:
+    category: test
+detection:
+    selection:
+        Image|endswith: '\powershell.exe'
+    condition: selection
+---
+"#;
+        let rules = crate::rules_from_yaml(rules_yaml).unwrap();
+        let titles: Vec<&str> = rules.iter().map(|r| r.title.as_str()).collect();
+        assert_eq!(titles, vec!["First", "Second"]);
+        let event = Event::from([("Image", "C:\\Windows\\System32\\powershell.exe")]);
+        assert!(!rules[0].is_match(&event));
+        assert!(rules[1].is_match(&event));
+
+        let collection_yaml = r#"
+action: global
+title: Global
+logsource:
+    category: test
+---
+detection:
+    selection:
+        Image|endswith: '\cmd.exe'
+    condition: selection
+"#;
+        let err = crate::rules_from_yaml(collection_yaml).unwrap_err();
+        assert!(err.to_string().contains("action: global"));
+
+        let err = crate::rules_from_yaml(&format!("{}---\ntitle: [", rules_yaml)).unwrap_err();
+        assert!(!err.to_string().is_empty());
+    }
+
+    #[test]
+    fn test_lenient_conditions_with_all_loaders() {
+        let yaml = r#"
+title: Unmatched pattern
+logsource:
+    category: test
+detection:
+    selection:
+        EventID: 4104
+    condition: selection or 1 of filter_*
+"#;
+        assert!(crate::rules_from_yaml(yaml).is_err());
+        assert!(crate::parse_rule_collection(yaml).is_err());
+        assert!(crate::rule_from_yaml_strict(yaml).is_err());
+
+        let (rules, collection, strict) = crate::with_lenient_conditions(|| {
+            (
+                crate::rules_from_yaml(yaml).unwrap(),
+                crate::parse_rule_collection(yaml).unwrap(),
+                crate::rule_from_yaml_strict(yaml).unwrap(),
+            )
+        });
+        for rule in rules.iter().chain(collection.iter()).chain([&strict]) {
+            assert!(rule.is_match(&Event::from([("EventID", 4104)])));
+            assert_eq!(
+                rule.lint(),
+                vec![LintWarning::UnmatchedQuantifier {
+                    pattern: "filter_*".to_string()
+                }]
+            );
+        }
+
+        // the conditions are strict again afterwards
+        assert!(crate::rule_from_yaml(yaml).is_err());
+        assert!(crate::rule_from_yaml_lenient(yaml).is_ok());
+        assert!(crate::rule_from_yaml(yaml).is_err());
+    }
+
+    #[test]
+    fn test_rule_with_bom_and_crlf() {
+        let rule_yaml = "title: Windows\r\nlogsource:\r\n    category: test\r\ndetection:\r\n    selection:\r\n        CommandLine: |\r\n            a\r\n            b\r\n    condition: selection\r\n";
+        let with_bom = format!("\u{feff}{}", rule_yaml);
+
+        for yaml in [rule_yaml, with_bom.as_str()] {
+            let rule = crate::rule_from_yaml(yaml).unwrap();
+            assert_eq!(rule.title, "Windows");
+            // line breaks of block scalars are normalized to \n
+            let event = Event::from([("CommandLine", "a\nb\n")]);
+            assert!(rule.is_match(&event));
+
+            assert!(crate::rule_from_yaml_strict(yaml).is_ok());
+            assert!(crate::rule_from_yaml_lenient(yaml).is_ok());
+            assert_eq!(crate::rules_from_yaml(yaml).unwrap().len(), 1);
+            assert_eq!(crate::parse_rule_collection(yaml).unwrap().len(), 1);
+        }
+    }
+
+    #[cfg(feature = "serde_json")]
+    #[test]
+    fn test_match_reader() {
+        let rule = crate::rule_from_yaml(
+            r#"
+title: Reader
+logsource:
+    category: test
+detection:
+    selection:
+        Image|endswith: '\cmd.exe'
+    condition: selection
+"#,
+        )
+        .unwrap();
+        let log = r#"{"Image": "C:\\Windows\\cmd.exe"}
+{"Image": "C:\\Windows\\notepad.exe"}
+
+{"Image": "C:\\Windows\\
+["C:\\Windows\\cmd.exe"]
+{"Image": "D:\\cmd.exe"}
+"#;
+        let results: Vec<(usize, Result<bool, String>)> = rule
+            .match_reader(log.as_bytes())
+            .map(|(line, result)| (line, result.map_err(|e| e.to_string())))
+            .collect();
+        assert_eq!(results.len(), 5);
+        assert_eq!(results[0], (1, Ok(true)));
+        assert_eq!(results[1], (2, Ok(false)));
+        // parse errors are reported with the line number and do not end the iteration
+        assert!(matches!(results[2], (4, Err(_))));
+        assert!(matches!(results[3], (5, Err(_))));
+        assert_eq!(results[4], (6, Ok(true)));
+
+        // an error reading the input ends the iteration
+        let invalid_utf8: &[u8] = b"{\"Image\": \"cmd.exe\"}\n\xff\n{}\n";
+        let results: Vec<_> = rule.match_reader(invalid_utf8).collect();
+        assert_eq!(results.len(), 2);
+        assert!(matches!(results[1], (2, Err(ref e)) if e.is_io()));
+    }
+
+    #[test]
+    fn test_mitre_techniques_ignore_case() {
+        let rule_yaml = r#"
+        title: Tags
+        logsource:
+            category: process_creation
+        detection:
+          selection:
+            Image|endswith: '\powershell.exe'
+          condition: selection
+        tags:
+            - attack.T1059
+            - attack.t1059
+            - ATTACK.t1027
+            - Attack.T1059.001
+            - attack.Execution
+            - attacks.t1105
+        "#;
+        let rule: Rule = serde_yml::from_str(rule_yaml).unwrap();
+        assert_eq!(rule.mitre_techniques(), vec!["t1059", "t1027", "t1059.001"]);
+    }
+
+    #[test]
+    fn test_tags() {
+        let rule_yaml = r#"
+        title: Tags
+        logsource:
+            category: process_creation
+        detection:
+          selection:
+            Image|endswith: '\powershell.exe'
+          condition: selection
+        tags:
+            - attack.execution
+            - attack.t1059.001
+            - attack.defense-evasion
+            - attack.t1027
+            - attack.g0016
+            - attack.T1105
+            - car.2016-03-001
+            - detection.threat-hunting
+            - Command and Control
+        "#;
+        let rule: Rule = serde_yml::from_str(rule_yaml).unwrap();
+        assert_eq!(rule.mitre_techniques(), vec!["t1059.001", "t1027", "t1105"]);
+
+        let namespaces = rule.tag_namespaces();
+        assert_eq!(namespaces.len(), 3);
+        assert_eq!(
+            namespaces["attack"],
+            vec![
+                "execution",
+                "t1059.001",
+                "defense-evasion",
+                "t1027",
+                "g0016",
+                "T1105"
+            ]
+        );
+        assert_eq!(namespaces["car"], vec!["2016-03-001"]);
+        assert_eq!(namespaces["detection"], vec!["threat-hunting"]);
+
+        assert_eq!(
+            rule.lint(),
+            vec![
+                LintWarning::MalformedTag {
+                    tag: "attack.T1105".to_string()
+                },
+                LintWarning::MalformedTag {
+                    tag: "Command and Control".to_string()
+                },
+            ]
+        );
+
+        let rule = Rule { tags: None, ..rule };
+        assert!(rule.mitre_techniques().is_empty());
+        assert!(rule.tag_namespaces().is_empty());
+    }
+
+    fn assert_sync<T: Sync>() {}
+    fn assert_send<T: Send>() {}
+
+    #[test]
+    fn test_rule_is_send_and_sync() {
+        assert_sync::<Rule>();
+        assert_send::<Rule>();
+        assert_sync::<crate::RuleSet>();
+        assert_sync::<crate::Event>();
+    }
+
+    #[test]
+    fn test_evaluate_shared_rule() {
+        let rule: Rule = serde_yml::from_str(
+            r#"
+        title: Shared
+        logsource:
+        detection:
+          selection:
+            Image|endswith: '*ll32.exe'
+          condition: selection
+        "#,
+        )
+        .unwrap();
+        let event = crate::Event::from([("Image", "C:\\Windows\\rundll32.exe")]);
+        std::thread::scope(|scope| {
+            let handles: Vec<_> = (0..4)
+                .map(|_| scope.spawn(|| rule.is_match(&event)))
+                .collect();
+            for handle in handles {
+                assert!(handle.join().unwrap());
+            }
+        });
+    }
+
+    #[test]
+    fn test_level_ordering() {
+        let levels = [
+            Level::Informational,
+            Level::Low,
+            Level::Medium,
+            Level::High,
+            Level::Critical,
+        ];
+        for pair in levels.windows(2) {
+            assert!(pair[0] < pair[1], "{:?} < {:?}", pair[0], pair[1]);
+        }
+        assert_eq!(levels.iter().max(), Some(&Level::Critical));
+
+        let rule_with_level = |level: &str| -> Rule {
+            serde_yml::from_str(&format!(
+                r#"
+        title: Level
+        logsource:
+            category: test
+        detection:
+          selection:
+            field_name: this
+          condition: selection
+        {}
+        "#,
+                level
+            ))
+            .unwrap()
+        };
+        for (i, name) in ["informational", "low", "medium", "high", "critical"]
+            .iter()
+            .enumerate()
+        {
+            let rule = rule_with_level(&format!("level: {}", name));
+            for (j, min) in levels.iter().enumerate() {
+                assert_eq!(rule.level_at_least(*min), i >= j, "{} >= {:?}", name, min);
+            }
+        }
+        let rule = rule_with_level("");
+        assert!(levels.iter().all(|min| !rule.level_at_least(*min)));
+    }
+
+    #[test]
+    fn test_is_active() {
+        let mut rule: Rule = serde_yml::from_str(
+            r#"
+        title: Status
+        logsource:
+            category: test
+        detection:
+          selection:
+            field_name: this
+          condition: selection
+        "#,
+        )
+        .unwrap();
+        assert!(rule.is_active());
+        for (status, expected) in [
+            (Status::Stable, true),
+            (Status::Test, true),
+            (Status::Experimental, true),
+            (Status::Deprecated, false),
+            (Status::Unsupported, false),
+        ] {
+            rule.status = Some(status.clone());
+            assert_eq!(rule.is_active(), expected, "{:?}", status);
+        }
+    }
 }
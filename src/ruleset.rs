@@ -0,0 +1,566 @@
+use crate::event::Event;
+use crate::rule::Rule;
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// A collection of rules that are matched together against events.
+///
+/// The rules are indexed by the `category` of their logsource, so that
+/// [`RuleSet::matches_for_logsource`] only evaluates rules applicable to an event.
+#[derive(Debug, Default)]
+pub struct RuleSet {
+    rules: Vec<Rule>,
+    /// Indices of rules by their lowercased logsource category
+    by_category: HashMap<String, Vec<usize>>,
+    /// Indices of rules without a logsource category
+    any_category: Vec<usize>,
+}
+
+impl From<Vec<Rule>> for RuleSet {
+    fn from(rules: Vec<Rule>) -> Self {
+        Self::new(rules)
+    }
+}
+
+impl RuleSet {
+    /// Create a new rule set from the given rules
+    pub fn new(rules: Vec<Rule>) -> Self {
+        let mut by_category: HashMap<String, Vec<usize>> = HashMap::new();
+        let mut any_category = vec![];
+        for (i, rule) in rules.iter().enumerate() {
+            match &rule.logsource.category {
+                Some(category) => by_category
+                    .entry(category.to_lowercase())
+                    .or_default()
+                    .push(i),
+                None => any_category.push(i),
+            }
+        }
+        Self {
+            rules,
+            by_category,
+            any_category,
+        }
+    }
+
+    /// Create a new rule set from the given rules without those that are `deprecated`
+    /// or `unsupported`, see [`Rule::is_active`]
+    pub fn new_active(rules: Vec<Rule>) -> Self {
+        Self::new(rules.into_iter().filter(Rule::is_active).collect())
+    }
+
+    /// Create a new rule set from the rules of this one with the given tag or a tag below it,
+    /// e.g. `attack.` for all rules with ATT&CK tags, see [`Rule::tags_contain`]
+    pub fn filter_by_tag(&self, tag: &str) -> Self {
+        Self::new(
+            self.rules
+                .iter()
+                .filter(|rule| rule.tags_contain(tag))
+                .cloned()
+                .collect(),
+        )
+    }
+
+    /// The rules contained in this rule set
+    pub fn rules(&self) -> &[Rule] {
+        &self.rules
+    }
+
+    /// The number of rules in this rule set
+    pub fn len(&self) -> usize {
+        self.rules.len()
+    }
+
+    /// Returns true if the rule set contains no rules
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Iterate over all rules matching the event
+    ///
+    /// # Example
+    /// ```rust
+    /// use sigma_rust::{rule_from_yaml, Event, RuleSet};
+    /// let rule = rule_from_yaml(r#"
+    /// title: Some test title
+    /// logsource:
+    ///     category: test
+    /// detection:
+    ///     selection:
+    ///         EventID: 4104
+    ///     condition: selection
+    /// "#).unwrap();
+    /// let ruleset = RuleSet::new(vec![rule]);
+    /// let event = Event::from([("EventID", 4104)]);
+    /// assert_eq!(ruleset.matches(&event).count(), 1);
+    /// ```
+    pub fn matches<'a>(&'a self, event: &'a Event) -> impl Iterator<Item = &'a Rule> {
+        self.rules.iter().filter(|rule| rule.is_match(event))
+    }
+
+    /// Returns the `id` of each rule matching the event, or its `title` if the rule has no `id`
+    ///
+    /// # Example
+    /// ```rust
+    /// use sigma_rust::{rule_from_yaml, Event, RuleSet};
+    /// let rule = rule_from_yaml(r#"
+    /// title: Some test title
+    /// id: 929a690e-bef0-4204-a928-ef5e620d6fcc
+    /// logsource:
+    ///     category: test
+    /// detection:
+    ///     selection:
+    ///         EventID: 4104
+    ///     condition: selection
+    /// "#).unwrap();
+    /// let ruleset = RuleSet::new(vec![rule]);
+    /// let event = Event::from([("EventID", 4104)]);
+    /// assert_eq!(ruleset.match_ids(&event), vec!["929a690e-bef0-4204-a928-ef5e620d6fcc"]);
+    /// ```
+    pub fn match_ids(&self, event: &Event) -> Vec<&str> {
+        self.rules
+            .iter()
+            .filter(|rule| rule.is_match(event))
+            .map(|rule| rule.id.as_deref().unwrap_or(&rule.title))
+            .collect()
+    }
+
+    /// Counts how many of the events each rule matches, e.g. to find noisy rules. The counts
+    /// are keyed like [`RuleSet::match_ids`] and include rules matching no event with a count
+    /// of zero. The counts of rules with the same key are added up.
+    ///
+    /// # Example
+    /// ```rust
+    /// use sigma_rust::{rule_from_yaml, Event, RuleSet};
+    /// let rule = rule_from_yaml(r#"
+    /// title: Some test title
+    /// logsource:
+    ///     category: test
+    /// detection:
+    ///     selection:
+    ///         EventID: 4104
+    ///     condition: selection
+    /// "#).unwrap();
+    /// let ruleset = RuleSet::new(vec![rule]);
+    /// let events = vec![Event::from([("EventID", 4104)]), Event::from([("EventID", 1)])];
+    /// assert_eq!(ruleset.count_matches(&events)["Some test title"], 1);
+    /// ```
+    pub fn count_matches(&self, events: &[Event]) -> HashMap<&str, usize> {
+        let mut result = HashMap::new();
+        for rule in &self.rules {
+            let count = events.iter().filter(|event| rule.is_match(event)).count();
+            *result
+                .entry(rule.id.as_deref().unwrap_or(&rule.title))
+                .or_default() += count;
+        }
+        result
+    }
+
+    /// Evaluate the rules against the event until the time budget is exhausted.
+    ///
+    /// The elapsed time is checked before each rule, so a single slow rule is not interrupted
+    /// but no further rules are evaluated once the budget is exceeded. Returns the matching
+    /// rules found so far and whether all rules were evaluated.
+    ///
+    /// # Example
+    /// ```rust
+    /// use std::time::Duration;
+    /// use sigma_rust::{rule_from_yaml, Event, RuleSet};
+    /// let rule = rule_from_yaml(r#"
+    /// title: Some test title
+    /// logsource:
+    ///     category: test
+    /// detection:
+    ///     selection:
+    ///         EventID: 4104
+    ///     condition: selection
+    /// "#).unwrap();
+    /// let ruleset = RuleSet::new(vec![rule]);
+    /// let event = Event::from([("EventID", 4104)]);
+    /// let (matches, completed) = ruleset.matches_within(&event, Duration::from_secs(1));
+    /// assert_eq!(matches.len(), 1);
+    /// assert!(completed);
+    /// ```
+    pub fn matches_within(&self, event: &Event, budget: Duration) -> (Vec<&Rule>, bool) {
+        let start = Instant::now();
+        let mut result = vec![];
+        for rule in &self.rules {
+            if start.elapsed() >= budget {
+                return (result, false);
+            }
+            if rule.is_match(event) {
+                result.push(rule);
+            }
+        }
+        (result, true)
+    }
+
+    /// Match the rules against a batch of events in parallel. Returns the matching rules
+    /// for each event, in the order of the events.
+    ///
+    /// Rules are `Send + Sync`: the only state mutated during evaluation is the cache of
+    /// compiled wildcard patterns of each field, which is guarded by a read-write lock.
+    /// A rule set can therefore be shared by reference between threads.
+    ///
+    /// # Example
+    /// ```rust
+    /// use sigma_rust::{rule_from_yaml, Event, RuleSet};
+    /// let rule = rule_from_yaml(r#"
+    /// title: Some test title
+    /// logsource:
+    ///     category: test
+    /// detection:
+    ///     selection:
+    ///         EventID: 4104
+    ///     condition: selection
+    /// "#).unwrap();
+    /// let ruleset = RuleSet::new(vec![rule]);
+    /// let events = vec![Event::from([("EventID", 4104)]), Event::from([("EventID", 1)])];
+    /// let matches = ruleset.matches_batch(&events);
+    /// assert_eq!(matches[0].len(), 1);
+    /// assert!(matches[1].is_empty());
+    /// ```
+    #[cfg(feature = "rayon")]
+    pub fn matches_batch(&self, events: &[Event]) -> Vec<Vec<&Rule>> {
+        events
+            .par_iter()
+            .map(|event| {
+                self.rules
+                    .iter()
+                    .filter(|rule| rule.is_match(event))
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Iterate over all rules matching the event, skipping rules whose logsource
+    /// does not apply to the event. A rule applies if its `category` and `product`
+    /// are either not set or equal (case-insensitive) to the given values.
+    /// Passing `None` for `category` or `product` does not restrict the rules by that value.
+    pub fn matches_for_logsource<'a>(
+        &'a self,
+        event: &'a Event,
+        category: Option<&str>,
+        product: Option<&str>,
+    ) -> impl Iterator<Item = &'a Rule> {
+        let mut candidates: Vec<usize> = match category {
+            Some(category) => {
+                let mut result = self.any_category.clone();
+                if let Some(indices) = self.by_category.get(&category.to_lowercase()) {
+                    result.extend(indices);
+                }
+                // keep the order in which the rules were added
+                result.sort_unstable();
+                result
+            }
+            None => (0..self.rules.len()).collect(),
+        };
+        if let Some(product) = product {
+            candidates.retain(|&i| match &self.rules[i].logsource.product {
+                Some(p) => p.eq_ignore_ascii_case(product),
+                None => true,
+            });
+        }
+
+        candidates
+            .into_iter()
+            .map(|i| &self.rules[i])
+            .filter(|rule| rule.is_match(event))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rule_from_yaml;
+    use crate::rule::Status;
+
+    fn rule(title: &str, category: Option<&str>, product: Option<&str>, image: &str) -> Rule {
+        let mut logsource = String::new();
+        if let Some(c) = category {
+            logsource.push_str(&format!("\n    category: {}", c));
+        }
+        if let Some(p) = product {
+            logsource.push_str(&format!("\n    product: {}", p));
+        }
+        rule_from_yaml(&format!(
+            r#"
+title: {}
+logsource:{}
+detection:
+    selection:
+        Image|endswith: '{}'
+    condition: selection
+"#,
+            title, logsource, image
+        ))
+        .unwrap()
+    }
+
+    fn titles<'a>(rules: impl Iterator<Item = &'a Rule>) -> Vec<&'a str> {
+        rules.map(|r| r.title.as_str()).collect()
+    }
+
+    #[test]
+    fn test_matches() {
+        let ruleset = RuleSet::new(vec![
+            rule("a", Some("process_creation"), Some("windows"), "\\cmd.exe"),
+            rule("no match", None, None, "\\powershell.exe"),
+            rule("b", None, None, "cmd.exe"),
+        ]);
+        assert_eq!(ruleset.len(), 3);
+        assert!(!ruleset.is_empty());
+
+        let event = Event::from([("Image", "C:\\Windows\\System32\\cmd.exe")]);
+        assert_eq!(titles(ruleset.matches(&event)), vec!["a", "b"]);
+
+        let event = Event::from([("Image", "C:\\Windows\\System32\\calc.exe")]);
+        assert_eq!(ruleset.matches(&event).count(), 0);
+    }
+
+    #[test]
+    fn test_filter_by_tag() {
+        let with_tags = |title: &str, tags: &[&str]| {
+            let mut result = rule(title, None, None, "\\cmd.exe");
+            result.tags = Some(tags.iter().map(|t| t.to_string()).collect());
+            result
+        };
+        let mut untagged = rule("untagged", None, None, "\\cmd.exe");
+        untagged.tags = None;
+        let ruleset = RuleSet::new(vec![
+            with_tags("execution", &["attack.execution", "attack.t1059.001"]),
+            with_tags("discovery", &["attack.discovery", "attack.t1033"]),
+            with_tags("tlp", &["tlp.amber"]),
+            with_tags("uppercase", &["attack.T1059"]),
+            untagged,
+        ]);
+
+        let attack = ruleset.filter_by_tag("attack.");
+        assert_eq!(
+            titles(attack.rules().iter()),
+            vec!["execution", "discovery", "uppercase"]
+        );
+        let event = Event::from([("Image", "C:\\Windows\\System32\\cmd.exe")]);
+        assert_eq!(attack.matches(&event).count(), 3);
+
+        assert_eq!(
+            titles(ruleset.filter_by_tag("attack.t1059").rules().iter()),
+            vec!["execution", "uppercase"]
+        );
+        assert_eq!(
+            titles(ruleset.filter_by_tag("attack.discovery").rules().iter()),
+            vec!["discovery"]
+        );
+        assert!(ruleset.filter_by_tag("attack.t105").is_empty());
+        assert!(ruleset.filter_by_tag("attack.exec").is_empty());
+        assert!(ruleset.filter_by_tag("car.").is_empty());
+        assert_eq!(ruleset.filter_by_tag("tlp").len(), 1);
+    }
+
+    #[test]
+    fn test_new_active() {
+        let with_status = |title: &str, status: Option<Status>| {
+            let mut result = rule(title, None, None, "\\cmd.exe");
+            result.status = status;
+            result
+        };
+        let ruleset = RuleSet::new_active(vec![
+            with_status("stable", Some(Status::Stable)),
+            with_status("deprecated", Some(Status::Deprecated)),
+            with_status("none", None),
+            with_status("unsupported", Some(Status::Unsupported)),
+            with_status("experimental", Some(Status::Experimental)),
+        ]);
+        assert_eq!(
+            titles(ruleset.rules().iter()),
+            vec!["stable", "none", "experimental"]
+        );
+        let event = Event::from([("Image", "C:\\Windows\\System32\\cmd.exe")]);
+        assert_eq!(ruleset.matches(&event).count(), 3);
+    }
+
+    #[test]
+    fn test_match_ids() {
+        let mut with_id = rule("a", None, None, "\\cmd.exe");
+        with_id.id = Some("5fd3a4c9-13ff-4e2a-9c85-e2c2e6b0d3a1".to_string());
+        let ruleset = RuleSet::new(vec![
+            with_id,
+            rule("no match", None, None, "\\powershell.exe"),
+            rule("b", None, None, "cmd.exe"),
+        ]);
+
+        let event = Event::from([("Image", "C:\\Windows\\System32\\cmd.exe")]);
+        assert_eq!(
+            ruleset.match_ids(&event),
+            vec!["5fd3a4c9-13ff-4e2a-9c85-e2c2e6b0d3a1", "b"]
+        );
+
+        let event = Event::from([("Image", "C:\\Windows\\System32\\calc.exe")]);
+        assert!(ruleset.match_ids(&event).is_empty());
+    }
+
+    #[test]
+    fn test_count_matches() {
+        let mut with_id = rule("a", None, None, "\\cmd.exe");
+        with_id.id = Some("5fd3a4c9-13ff-4e2a-9c85-e2c2e6b0d3a1".to_string());
+        let ruleset = RuleSet::new(vec![
+            with_id,
+            rule("exe", None, None, ".exe"),
+            rule("no match", None, None, "\\powershell.exe"),
+            rule("sys", None, None, "\\sys*info.exe"),
+        ]);
+        let events: Vec<Event> = [
+            "C:\\Windows\\System32\\cmd.exe",
+            "C:\\Windows\\System32\\calc.exe",
+            "C:\\Windows\\System32\\cmd.exe",
+            "C:\\Windows\\System32\\systeminfo.exe",
+            "/usr/bin/bash",
+        ]
+        .into_iter()
+        .map(|image| Event::from([("Image", image)]))
+        .collect();
+
+        let counts = ruleset.count_matches(&events);
+        assert_eq!(
+            counts,
+            HashMap::from([
+                ("5fd3a4c9-13ff-4e2a-9c85-e2c2e6b0d3a1", 2),
+                ("exe", 4),
+                ("no match", 0),
+                ("sys", 1),
+            ])
+        );
+        assert!(ruleset.count_matches(&[]).values().all(|c| *c == 0));
+    }
+
+    #[test]
+    fn test_matches_for_logsource() {
+        let ruleset = RuleSet::from(vec![
+            rule(
+                "windows process",
+                Some("process_creation"),
+                Some("windows"),
+                "cmd.exe",
+            ),
+            rule(
+                "linux process",
+                Some("process_creation"),
+                Some("linux"),
+                "cmd.exe",
+            ),
+            rule("any process", Some("process_creation"), None, "cmd.exe"),
+            rule(
+                "windows file",
+                Some("file_event"),
+                Some("windows"),
+                "cmd.exe",
+            ),
+            rule("anything", None, None, "cmd.exe"),
+        ]);
+        let event = Event::from([("Image", "C:\\Windows\\System32\\cmd.exe")]);
+
+        assert_eq!(
+            titles(ruleset.matches_for_logsource(
+                &event,
+                Some("process_creation"),
+                Some("windows")
+            )),
+            vec!["windows process", "any process", "anything"]
+        );
+        assert_eq!(
+            titles(ruleset.matches_for_logsource(&event, Some("Process_Creation"), None)),
+            vec![
+                "windows process",
+                "linux process",
+                "any process",
+                "anything"
+            ]
+        );
+        assert_eq!(
+            titles(ruleset.matches_for_logsource(&event, None, Some("windows"))),
+            vec!["windows process", "any process", "windows file", "anything"]
+        );
+        assert_eq!(
+            titles(ruleset.matches_for_logsource(&event, Some("dns"), Some("windows"))),
+            vec!["anything"]
+        );
+    }
+
+    #[test]
+    fn test_matches_within() {
+        // a rule with many regexes evaluated against a long value
+        let patterns: Vec<String> = (1..=50)
+            .map(|i| format!("        - '\\w+\\d{{{}}}'", i))
+            .collect();
+        let slow_rule = rule_from_yaml(&format!(
+            r#"
+title: slow
+logsource:
+detection:
+    selection:
+        Image|re:
+{}
+    condition: selection
+"#,
+            patterns.join("\n")
+        ))
+        .unwrap();
+        let ruleset = RuleSet::new(vec![
+            rule("a", None, None, "x.exe"),
+            slow_rule,
+            rule("b", None, None, "x.exe"),
+        ]);
+        let image = format!("{}x.exe", "a".repeat(100_000));
+        let event = Event::from([("Image", image.as_str())]);
+
+        let (matches, completed) = ruleset.matches_within(&event, Duration::from_secs(60));
+        assert_eq!(titles(matches.into_iter()), vec!["a", "b"]);
+        assert!(completed);
+
+        // evaluating the slow rule always exceeds the budget, the last rule is skipped
+        let (matches, completed) = ruleset.matches_within(&event, Duration::from_nanos(1));
+        assert!(titles(matches.into_iter()).len() <= 1);
+        assert!(!completed);
+
+        let (matches, completed) = ruleset.matches_within(&event, Duration::ZERO);
+        assert!(matches.is_empty());
+        assert!(!completed);
+
+        let empty = RuleSet::default();
+        let (matches, completed) = empty.matches_within(&event, Duration::ZERO);
+        assert!(matches.is_empty());
+        assert!(completed);
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn test_matches_batch() {
+        // the wildcard patterns are compiled and cached concurrently by the threads
+        let ruleset = RuleSet::new(vec![
+            rule("a", None, None, "\\sys*.exe"),
+            rule("b", None, None, "*32.exe"),
+            rule("c", None, None, "x.exe"),
+        ]);
+        let events: Vec<Event> = (0..1000)
+            .map(|i| match i % 3 {
+                0 => Event::from([("Image", "C:\\system32.exe")]),
+                1 => Event::from([("Image", "C:\\x.exe")]),
+                _ => Event::from([("Image", "C:\\other.exe")]),
+            })
+            .collect();
+
+        let matches = ruleset.matches_batch(&events);
+        assert_eq!(matches.len(), events.len());
+        for (i, rules) in matches.into_iter().enumerate() {
+            let expected = match i % 3 {
+                0 => vec!["a", "b"],
+                1 => vec!["c"],
+                _ => vec![],
+            };
+            assert_eq!(titles(rules.into_iter()), expected, "{}", i);
+        }
+        assert!(ruleset.matches_batch(&[]).is_empty());
+    }
+}
@@ -0,0 +1,68 @@
+use crate::event::{Event, EventValue};
+use aho_corasick::AhoCorasick;
+
+/// Literal strings of which at least one must occur in an event for a rule to match.
+#[derive(Debug, Default)]
+pub(crate) struct Terms {
+    /// Terms that must be found as is
+    pub(crate) cased: Vec<String>,
+    /// Lowercased terms that must be found in the lowercased event values
+    pub(crate) uncased: Vec<String>,
+}
+
+impl Terms {
+    pub(crate) fn merge(&mut self, other: Terms) {
+        self.cased.extend(other.cased);
+        self.uncased.extend(other.uncased);
+    }
+}
+
+/// A cheap pre-filter that checks whether any of the literal terms of a rule occur in an event.
+#[derive(Debug, Clone)]
+pub(crate) struct Screen {
+    cased: Option<AhoCorasick>,
+    uncased: Option<AhoCorasick>,
+}
+
+impl Screen {
+    pub(crate) fn new(terms: Terms) -> Option<Self> {
+        let build = |patterns: Vec<String>| {
+            if patterns.is_empty() {
+                Ok(None)
+            } else {
+                AhoCorasick::new(patterns).map(Some)
+            }
+        };
+        // If the automaton cannot be built we simply do not screen
+        Some(Self {
+            cased: build(terms.cased).ok()?,
+            uncased: build(terms.uncased).ok()?,
+        })
+    }
+
+    /// Returns false only if the event definitely contains none of the terms
+    pub(crate) fn is_candidate(&self, event: &Event) -> bool {
+        event.values().any(|v| self.is_candidate_value(v))
+    }
+
+    fn is_candidate_value(&self, value: &EventValue) -> bool {
+        match value {
+            EventValue::Value(v) => {
+                let s = v.value_to_string();
+                if let Some(ac) = &self.cased {
+                    if ac.is_match(&s) {
+                        return true;
+                    }
+                }
+                if let Some(ac) = &self.uncased {
+                    if ac.is_match(&s.to_lowercase()) {
+                        return true;
+                    }
+                }
+                false
+            }
+            EventValue::Sequence(seq) => seq.iter().any(|v| self.is_candidate_value(v)),
+            EventValue::Map(m) => m.values().any(|v| self.is_candidate_value(v)),
+        }
+    }
+}
@@ -4,14 +4,16 @@ use crate::error::SelectionError::{
     SelectionContainsNoFields,
 };
 use crate::event::Event;
-use crate::field::Field;
+use crate::field::{Field, RegexLimits};
+use crate::lint::LintWarning;
+use crate::screen::Terms;
 use serde::Deserialize;
 use serde_yml::Value;
 use serde_yml::Value::{Mapping, Sequence};
 
 /// A field group is a collection of fields that are to be combined with AND
 /// In other words a fields group translates to a YAML dictionary
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct FieldGroup {
     pub fields: Vec<Field>,
 }
@@ -20,6 +22,11 @@ impl FieldGroup {
     fn evaluate(&self, event: &Event) -> bool {
         self.fields.iter().all(|field| field.evaluate(event))
     }
+
+    fn literal_terms(&self) -> Option<Terms> {
+        // All fields must match, so the terms of any single field are sufficient
+        self.fields.iter().find_map(|field| field.literal_terms())
+    }
 }
 
 impl TryFrom<serde_yml::Mapping> for FieldGroup {
@@ -42,9 +49,11 @@ struct SelectionProxy {
     value: Value,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize)]
 #[serde(try_from = "SelectionProxy")]
 pub enum Selection {
+    /// Keywords are matched ignoring case like plain field values, so they are lowercased once
+    /// when the selection is parsed
     Keyword(Vec<String>),
     Field(Vec<FieldGroup>),
 }
@@ -73,7 +82,7 @@ impl TryFrom<Value> for Selection {
                     let mut keywords = vec![];
                     for value in seq.iter() {
                         match value {
-                            Value::String(s) => keywords.push(s.to_string()),
+                            Value::String(s) => keywords.push(s.to_lowercase()),
                             Value::Number(n) => keywords.push(n.to_string()),
                             Value::Bool(b) => keywords.push(b.to_string()),
                             _ => {
@@ -116,14 +125,125 @@ impl TryFrom<Value> for Selection {
 }
 
 impl Selection {
-    pub(crate) fn evaluate(&self, event: &Event) -> bool {
+    /// Evaluate the selection, if `whole_words` is set keywords only match on word boundaries.
+    /// Keywords always match ignoring case.
+    pub(crate) fn evaluate(&self, event: &Event, whole_words: bool) -> bool {
         match &self {
             Self::Keyword(keywords) => event
                 .values()
-                .any(|v| keywords.iter().any(|kw| v.contains(kw))),
+                .any(|v| v.contains_keyword(keywords, whole_words)),
             Self::Field(field_groups) => field_groups.iter().any(|g| g.evaluate(event)),
         }
     }
+
+    /// The fields of all field groups, none for keyword selections
+    pub(crate) fn fields(&self) -> impl Iterator<Item = &Field> {
+        let field_groups = match self {
+            Self::Keyword(_) => &[][..],
+            Self::Field(field_groups) => &field_groups[..],
+        };
+        field_groups.iter().flat_map(|g| g.fields.iter())
+    }
+
+    pub(crate) fn set_regex_limits(&mut self, limits: RegexLimits) -> Result<(), ParserError> {
+        if let Self::Field(field_groups) = self {
+            for field in field_groups.iter_mut().flat_map(|g| g.fields.iter_mut()) {
+                field.set_regex_limits(limits)?;
+            }
+        }
+        Ok(())
+    }
+
+    pub(crate) fn set_coerce_booleans(&mut self, enabled: bool) {
+        if let Self::Field(field_groups) = self {
+            for field in field_groups.iter_mut().flat_map(|g| g.fields.iter_mut()) {
+                field.coerce_booleans = enabled;
+            }
+        }
+    }
+
+    pub(crate) fn validate_regex_complexity(
+        &self,
+        max_complexity: usize,
+    ) -> Result<(), ParserError> {
+        if let Self::Field(field_groups) = self {
+            for field in field_groups.iter().flat_map(|g| g.fields.iter()) {
+                field.validate_regex_complexity(max_complexity)?;
+            }
+        }
+        Ok(())
+    }
+
+    pub(crate) fn validate_value_variants(&self, max_variants: usize) -> Result<(), ParserError> {
+        if let Self::Field(field_groups) = self {
+            for field in field_groups.iter().flat_map(|g| g.fields.iter()) {
+                field.validate_value_variants(max_variants)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Write the fields of the selection as lines indented by `depth` levels
+    pub(crate) fn write_tree(&self, depth: usize, out: &mut String) {
+        let count = |n: usize| format!("{} value{}", n, if n == 1 { "" } else { "s" });
+        let write_fields = |group: &FieldGroup, depth: usize, out: &mut String| {
+            for field in group.fields.iter() {
+                out.push_str(&format!(
+                    "{}{}{} ({})\n",
+                    "  ".repeat(depth),
+                    field.name,
+                    field.modifier,
+                    count(field.values.len())
+                ));
+            }
+        };
+        match &self {
+            Self::Keyword(keywords) => out.push_str(&format!(
+                "{}keywords ({})\n",
+                "  ".repeat(depth),
+                count(keywords.len())
+            )),
+            Self::Field(field_groups) if field_groups.len() == 1 => {
+                write_fields(&field_groups[0], depth, out)
+            }
+            Self::Field(field_groups) => {
+                for (i, group) in field_groups.iter().enumerate() {
+                    out.push_str(&format!("{}field group {}\n", "  ".repeat(depth), i + 1));
+                    write_fields(group, depth + 1, out);
+                }
+            }
+        }
+    }
+
+    /// Reports possible improvements of the fields of this selection
+    pub(crate) fn lint(&self, name: &str) -> Vec<LintWarning> {
+        match &self {
+            Self::Keyword(_) => vec![],
+            Self::Field(field_groups) => field_groups
+                .iter()
+                .flat_map(|g| g.fields.iter())
+                .flat_map(|field| field.lint(name))
+                .collect(),
+        }
+    }
+
+    /// Returns the literal strings of which at least one must occur in the event
+    /// for this selection to match, or `None` if the selection may match without any of them.
+    pub(crate) fn literal_terms(&self) -> Option<Terms> {
+        match &self {
+            Self::Keyword(keywords) => Some(Terms {
+                uncased: keywords.clone(),
+                ..Default::default()
+            }),
+            Self::Field(field_groups) => {
+                let mut result = Terms::default();
+                for group in field_groups.iter() {
+                    result.merge(group.literal_terms()?);
+                }
+                Some(result)
+            }
+        }
+    }
 }
 
 #[cfg(test)]
@@ -142,19 +262,62 @@ mod tests {
         ]);
 
         let event = Event::from([("key", "zsh shutdown test")]);
-        assert!(selection.evaluate(&event));
+        assert!(selection.evaluate(&event, false));
 
         let event = Event::from([("nomatch", "zsh shutdown".to_string())]);
-        assert!(!selection.evaluate(&event));
+        assert!(!selection.evaluate(&event, false));
 
         let event = Event::from([("some", "the arch is on".to_string())]);
-        assert!(selection.evaluate(&event));
+        assert!(selection.evaluate(&event, false));
 
         let event = Event::from([("some", "linux is best".to_string())]);
-        assert!(selection.evaluate(&event));
+        assert!(selection.evaluate(&event, false));
 
         let event = Event::from([("some", " arch linux ".to_string())]);
-        assert!(selection.evaluate(&event));
+        assert!(selection.evaluate(&event, false));
+    }
+
+    #[test]
+    fn test_keyword_selection_independent_of_field_order() {
+        let selection = Selection::Keyword(vec!["whoami".to_string(), "certutil".to_string()]);
+        let fields = [
+            ("CommandLine", "certutil -urlcache"),
+            ("ParentCommandLine", "cmd /c whoami"),
+            ("Image", "C:\\Windows\\System32\\certutil.exe"),
+        ];
+        for whole_words in [false, true] {
+            for rotation in 0..fields.len() {
+                let mut event = Event::new();
+                for (key, value) in fields.iter().cycle().skip(rotation).take(fields.len()) {
+                    event.insert(*key, *value);
+                }
+                assert!(selection.evaluate(&event, whole_words));
+            }
+        }
+    }
+
+    #[test]
+    fn test_keyword_selection_whole_words() {
+        let selection = Selection::Keyword(vec!["cat".to_string(), "-enc".to_string()]);
+
+        let event = Event::from([("key", "certificate concatenated")]);
+        assert!(selection.evaluate(&event, false));
+        assert!(!selection.evaluate(&event, true));
+
+        let event = Event::from([("key", "/usr/bin/cat /etc/passwd")]);
+        assert!(selection.evaluate(&event, true));
+
+        let event = Event::from([("key", "cat")]);
+        assert!(selection.evaluate(&event, true));
+
+        let event = Event::from([("key", "powershell.exe -encodedcommand")]);
+        assert!(!selection.evaluate(&event, true));
+
+        let event = Event::from([("key", "powershell.exe -enc abc")]);
+        assert!(selection.evaluate(&event, true));
+
+        let event = Event::from([("key", "cat_file")]);
+        assert!(!selection.evaluate(&event, true));
     }
 
     #[test]
@@ -171,10 +334,10 @@ mod tests {
         }]);
 
         let event = Event::from([("name1", "the world is big"), ("name2", "10.0.43.44")]);
-        assert!(selection.evaluate(&event));
+        assert!(selection.evaluate(&event, false));
 
         let event = Event::from([("nomatch", "the world is big"), ("name2", "10.42.43.44")]);
-        assert!(!selection.evaluate(&event));
+        assert!(!selection.evaluate(&event, false));
     }
 
     #[test]
@@ -193,6 +356,27 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_keyword_selection_ignores_case() {
+        let value: Value = serde_yml::from_str("[WhoAmI, '-EncodedCommand']").unwrap();
+        let selection = Selection::try_from(value).unwrap();
+
+        let event = Event::from([("CommandLine", "cmd.exe /c WHOAMI /all")]);
+        assert!(selection.evaluate(&event, false));
+        assert!(selection.evaluate(&event, true));
+
+        let event = Event::from([("CommandLine", "powershell -encodedcommand SQBFAFgA")]);
+        assert!(selection.evaluate(&event, false));
+
+        let event = Event::from([("CommandLine", "cmd.exe /c WhoAmIs")]);
+        assert!(selection.evaluate(&event, false));
+        assert!(!selection.evaluate(&event, true));
+
+        let terms = selection.literal_terms().unwrap();
+        assert!(terms.cased.is_empty());
+        assert_eq!(terms.uncased, vec!["whoami", "-encodedcommand"]);
+    }
+
     #[test]
     fn test_mixed_keyword_selection() {
         let yaml = r#"
@@ -0,0 +1,199 @@
+use crate::error::ParserError;
+use std::cmp::Ordering;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::str::FromStr;
+use std::time::Duration;
+
+/// The time span of a correlation, e.g. `30m`, written as a positive number followed
+/// by one of the units `s` (seconds), `m` (minutes), `h` (hours) or `d` (days).
+/// Timeframes are compared by their duration, e.g. `60m` equals `1h` and `2h` is longer
+/// than `30m`, but keep the unit they are written in.
+///
+/// # Example
+/// ```rust
+/// use sigma_rust::Timeframe;
+/// use std::time::Duration;
+/// let timeframe: Timeframe = "2h".parse().unwrap();
+/// assert_eq!(timeframe.duration(), Duration::from_secs(7200));
+/// assert!("2w".parse::<Timeframe>().is_err());
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct Timeframe {
+    value: u64,
+    unit: TimeUnit,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum TimeUnit {
+    Seconds,
+    Minutes,
+    Hours,
+    Days,
+}
+
+impl TimeUnit {
+    fn seconds(&self) -> u64 {
+        match self {
+            Self::Seconds => 1,
+            Self::Minutes => 60,
+            Self::Hours => 60 * 60,
+            Self::Days => 24 * 60 * 60,
+        }
+    }
+
+    fn suffix(&self) -> char {
+        match self {
+            Self::Seconds => 's',
+            Self::Minutes => 'm',
+            Self::Hours => 'h',
+            Self::Days => 'd',
+        }
+    }
+}
+
+impl Timeframe {
+    /// The length of the timeframe
+    pub fn duration(&self) -> Duration {
+        Duration::from_secs(self.value * self.unit.seconds())
+    }
+}
+
+impl PartialEq for Timeframe {
+    fn eq(&self, other: &Self) -> bool {
+        self.duration() == other.duration()
+    }
+}
+
+impl Eq for Timeframe {}
+
+impl PartialOrd for Timeframe {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Timeframe {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.duration().cmp(&other.duration())
+    }
+}
+
+impl Hash for Timeframe {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.duration().hash(state);
+    }
+}
+
+impl From<Timeframe> for Duration {
+    fn from(timeframe: Timeframe) -> Self {
+        timeframe.duration()
+    }
+}
+
+impl FromStr for Timeframe {
+    type Err = ParserError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || ParserError::InvalidTimeframe(s.to_string());
+        let trimmed = s.trim();
+        let Some(suffix) = trimmed.chars().last() else {
+            return Err(invalid());
+        };
+        let unit = match suffix {
+            's' => TimeUnit::Seconds,
+            'm' => TimeUnit::Minutes,
+            'h' => TimeUnit::Hours,
+            'd' => TimeUnit::Days,
+            _ => return Err(invalid()),
+        };
+        let number = &trimmed[..trimmed.len() - 1];
+        // u64::from_str accepts a leading +, which is not part of the notation
+        if number.is_empty() || !number.chars().all(|c| c.is_ascii_digit()) {
+            return Err(invalid());
+        }
+        let value: u64 = number.parse().map_err(|_| invalid())?;
+        // the duration must not overflow
+        if value == 0 || value.checked_mul(unit.seconds()).is_none() {
+            return Err(invalid());
+        }
+        Ok(Self { value, unit })
+    }
+}
+
+impl fmt::Display for Timeframe {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.value, self.unit.suffix())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_timeframe() {
+        for (s, seconds) in [
+            ("30s", 30),
+            ("30m", 30 * 60),
+            ("2h", 2 * 60 * 60),
+            ("1d", 24 * 60 * 60),
+            (" 15m ", 15 * 60),
+        ] {
+            let timeframe = Timeframe::from_str(s).unwrap();
+            assert_eq!(timeframe.duration(), Duration::from_secs(seconds), "{}", s);
+            assert_eq!(timeframe.to_string(), s.trim());
+        }
+        assert_eq!(
+            Duration::from(Timeframe::from_str("1h").unwrap()),
+            Duration::from_secs(3600)
+        );
+    }
+
+    #[test]
+    fn test_compare_timeframes() {
+        let parse = |s| Timeframe::from_str(s).unwrap();
+        assert!(parse("2h") > parse("30m"));
+        assert!(parse("90s") < parse("2m"));
+        assert!(parse("1d") > parse("23h"));
+        assert_eq!(parse("60m"), parse("1h"));
+        assert_eq!(parse("86400s").cmp(&parse("1d")), Ordering::Equal);
+        assert_ne!(parse("61m"), parse("1h"));
+
+        let mut timeframes = [parse("1d"), parse("30m"), parse("2h"), parse("45s")];
+        timeframes.sort();
+        let sorted: Vec<String> = timeframes.iter().map(|t| t.to_string()).collect();
+        assert_eq!(sorted, vec!["45s", "30m", "2h", "1d"]);
+
+        let unique: std::collections::HashSet<Timeframe> =
+            [parse("60m"), parse("1h"), parse("3600s"), parse("2h")].into();
+        assert_eq!(unique.len(), 2);
+    }
+
+    #[test]
+    fn test_invalid_timeframe() {
+        for s in [
+            "",
+            "m",
+            "30",
+            "30x",
+            "30M",
+            "1w",
+            "-1h",
+            "+1h",
+            "1.5h",
+            "0s",
+            "1 h",
+            "h1",
+            "99999999999999999999d",
+            "999999999999999999d",
+        ] {
+            let err = Timeframe::from_str(s).unwrap_err();
+            assert!(
+                matches!(err, ParserError::InvalidTimeframe(ref a) if a == s),
+                "{}",
+                s
+            );
+        }
+    }
+}
@@ -1,7 +1,9 @@
 #[cfg(feature = "serde_json")]
 use serde_json::json;
 #[cfg(feature = "serde_json")]
-use sigma_rust::{check_rule, event_from_json, events_from_json, rule_from_yaml, Event};
+use sigma_rust::{
+    check_rule, event_from_json, event_from_json_raw, events_from_json, rule_from_yaml, Event,
+};
 
 #[cfg(feature = "serde_json")]
 #[test]
@@ -133,6 +135,55 @@ fn test_match_nested_event() {
     assert!(!check_rule(&rule, &event));
 }
 
+#[cfg(feature = "serde_json")]
+#[test]
+fn test_flatten_nested_event() {
+    let mut event: Event = json!({
+        "Image": "test",
+        "Image.source": "somewhere",
+        "User": {
+            "Name": {
+                "First": "Chuck",
+                "Last": "Norris",
+            },
+            "Mobile.phone": "1",
+            "Age": 42,
+        },
+        "Args": ["-c", {"Script": "run.sh"}],
+        "Empty": {},
+    })
+    .try_into()
+    .unwrap();
+
+    let flat = event.flatten();
+    let mut keys: Vec<&String> = flat.keys().collect();
+    keys.sort();
+    assert_eq!(
+        keys,
+        vec![
+            "Args.0",
+            "Args.1.Script",
+            "Image",
+            "Image.source",
+            "User.Age",
+            "User.Mobile.phone",
+            "User.Name.First",
+            "User.Name.Last",
+        ]
+    );
+    assert_eq!(flat["User.Name.First"], "Chuck".into());
+    assert_eq!(flat["User.Age"], 42.into());
+    assert_eq!(flat["Args.1.Script"], "run.sh".into());
+
+    // the keys of nested maps are joined like they are accessed
+    for key in ["Image.source", "User.Name.Last", "User.Mobile.phone"] {
+        assert!(event.get(key).is_some(), "{}", key);
+    }
+
+    event.set_separator('/');
+    assert!(event.flatten().contains_key("User/Name/First"));
+}
+
 #[cfg(feature = "serde_json")]
 #[test]
 fn test_match_fieldref() {
@@ -178,6 +229,58 @@ fn test_match_fieldref() {
     assert!(!check_rule(&rule, &event));
 }
 
+#[cfg(feature = "serde_json")]
+#[test]
+fn test_match_fieldref_nested() {
+    let event: Event = json!({
+        "Image": "testing",
+        "User": {
+            "Name": {
+                "First": "Chuck",
+                "Last": "Norris",
+            },
+            "Alias": {
+                "First": "chuck",
+            },
+            "Images": ["cmd", "testing"],
+        },
+        "reference": "testing",
+    })
+    .try_into()
+    .unwrap();
+
+    let cases = [
+        // dotted paths on both sides
+        ("User.Name.First|fieldref: User.Alias.First", true),
+        ("User.Name.First|fieldref|cased: User.Alias.First", false),
+        // a referenced list matches if any element matches
+        ("Image|fieldref: User.Images", true),
+        ("User.Name.First|fieldref: User.Images", false),
+        // a referenced map has no value to compare with
+        ("Image|fieldref: User.Name", false),
+        ("Image|fieldref|contains: User", false),
+        // with `all` every referenced field must exist and match
+        ("Image|fieldref|all: [reference, User.Images]", true),
+        ("Image|fieldref|all: [reference, field_not_in_event]", false),
+        ("Image|fieldref|all: [reference, User.Name]", false),
+        ("Image|fieldref: [field_not_in_event, reference]", true),
+    ];
+    for (selection, expected) in cases {
+        let rule = format!(
+            r#"
+        title: Fieldref test
+        logsource:
+        detection:
+            selection:
+                {}
+            condition: selection"#,
+            selection
+        );
+        let rule = rule_from_yaml(&rule).unwrap();
+        assert_eq!(check_rule(&rule, &event), expected, "{}", selection);
+    }
+}
+
 #[cfg(feature = "serde_json")]
 #[test]
 fn test_nested_exists() {
@@ -206,3 +309,304 @@ fn test_nested_exists() {
     let rule = rule_from_yaml(matching_rule).unwrap();
     assert!(check_rule(&rule, &event));
 }
+
+#[cfg(feature = "serde_json")]
+#[test]
+fn test_match_whole_float_value() {
+    let event = event_from_json(r#"{"ratio": 1.0, "Message": "done"}"#).unwrap();
+
+    let numeric_rule = r#"
+        title: Float test
+        logsource:
+        detection:
+            selection:
+                ratio: 1.0
+            condition: selection"#;
+    let rule = rule_from_yaml(numeric_rule).unwrap();
+    assert!(check_rule(&rule, &event));
+
+    let string_rule = r#"
+        title: Float test
+        logsource:
+        detection:
+            selection:
+                ratio|re: '^1\.0$'
+            condition: selection"#;
+    let rule = rule_from_yaml(string_rule).unwrap();
+    assert!(check_rule(&rule, &event));
+
+    let keyword_rule = r#"
+        title: Float test
+        logsource:
+        detection:
+            keywords:
+                - '1.0'
+            condition: keywords"#;
+    let rule = rule_from_yaml(keyword_rule).unwrap();
+    assert!(check_rule(&rule, &event));
+}
+
+#[cfg(feature = "serde_json")]
+#[test]
+fn test_match_nested_event_with_custom_separator() {
+    let mut event: Event = json!({
+        "User": {
+            "Name": {
+                "First": "Chuck",
+                "Last": "Norris",
+            },
+        },
+        "Image.Path": "C:\\cmd.exe",
+    })
+    .try_into()
+    .unwrap();
+    event.set_separator('/');
+
+    let matching_rule = r#"
+        title: Separator test
+        logsource:
+        detection:
+            selection:
+                User/Name/First: 'Chuck'
+                /User/Name/Last: 'Norris'
+                Image.Path|endswith: 'cmd.exe'
+            condition: selection"#;
+    let dotted_rule = r#"
+        title: Separator test
+        logsource:
+        detection:
+            selection:
+                User.Name.First: 'Chuck'
+            condition: selection"#;
+
+    let rule = rule_from_yaml(matching_rule).unwrap();
+    assert!(check_rule(&rule, &event));
+    let rule = rule_from_yaml(dotted_rule).unwrap();
+    assert!(!check_rule(&rule, &event));
+}
+
+#[cfg(feature = "serde_json")]
+#[test]
+fn test_match_sequence() {
+    let rule = r#"
+        title: Command shell execution
+        logsource:
+        detection:
+            selection:
+                Args|sequence:
+                    - 'cmd'
+                    - '/c'
+            condition: selection"#;
+    let rule = rule_from_yaml(rule).unwrap();
+    for (args, expected) in [
+        (json!(["cmd", "/c", "whoami"]), true),
+        (json!(["cmd", "/q", "/c", "whoami"]), false),
+        (json!(["/c", "cmd"]), false),
+    ] {
+        let event = Event::try_from(json!({ "Args": args })).unwrap();
+        assert_eq!(rule.is_match(&event), expected, "{}", args);
+    }
+}
+
+#[cfg(feature = "serde_json")]
+#[test]
+fn test_match_duplicate_keys() {
+    let rule = r#"
+        title: Encoded PowerShell
+        logsource:
+        detection:
+            selection:
+                CommandLine|contains: '-enc'
+            condition: selection"#;
+    let rule = rule_from_yaml(rule).unwrap();
+    let json = r#"{"CommandLine": "powershell -enc ZQBjAGgAbwA=", "CommandLine": "notepad.exe"}"#;
+
+    // serde_json keeps the last value only
+    assert!(!rule.is_match(&event_from_json(json).unwrap()));
+    assert!(rule.is_match(&event_from_json_raw(json).unwrap()));
+}
+
+#[cfg(feature = "serde_json")]
+#[test]
+fn test_match_json_encoded_field() {
+    let rule = r#"
+        title: Logon of admin
+        logsource:
+        detection:
+            selection:
+                Details.user|json: 'admin'
+                Details.session.type|json: 'interactive'
+            condition: selection"#;
+    let rule = rule_from_yaml(rule).unwrap();
+    for (details, expected) in [
+        (
+            json!(r#"{"user": "admin", "session": {"type": "interactive"}}"#),
+            true,
+        ),
+        // nested JSON strings are decoded as well
+        (
+            json!(r#"{"user": "admin", "session": "{\"type\": \"interactive\"}"}"#),
+            true,
+        ),
+        (
+            json!(r#"{"user": "bob", "session": {"type": "interactive"}}"#),
+            false,
+        ),
+        // not valid JSON
+        (json!(r#"{"user": "admin", "session": "#), false),
+        (json!("user=admin"), false),
+        // already structured values match without decoding
+        (
+            json!({"user": "admin", "session": {"type": "interactive"}}),
+            true,
+        ),
+    ] {
+        let event = Event::try_from(json!({ "Details": details })).unwrap();
+        assert_eq!(rule.is_match(&event), expected, "{}", details);
+    }
+
+    // without the modifier, the string is not decoded
+    let rule = r#"
+        title: Logon of admin
+        logsource:
+        detection:
+            selection:
+                Details.user: 'admin'
+            condition: selection"#;
+    let rule = rule_from_yaml(rule).unwrap();
+    let event = Event::try_from(json!({ "Details": r#"{"user": "admin"}"# })).unwrap();
+    assert!(!rule.is_match(&event));
+}
+
+#[cfg(feature = "serde_json")]
+#[test]
+fn test_match_list_of_maps() {
+    let rule = r#"
+        title: Known bad hash
+        logsource:
+        detection:
+            selection:
+                Hashes.value: 'e3b0c44298fc1c149afbf4c8996fb924'
+                Hashes.type: 'SHA256'
+            condition: selection"#;
+    let rule = rule_from_yaml(rule).unwrap();
+    let event = event_from_json(
+        r#"{"Hashes": [
+            {"type": "MD5", "value": "d41d8cd98f00b204e9800998ecf8427e"},
+            {"type": "SHA256", "value": "e3b0c44298fc1c149afbf4c8996fb924"},
+            "not a map"
+        ]}"#,
+    )
+    .unwrap();
+    assert!(rule.is_match(&event));
+
+    let event = event_from_json(
+        r#"{"Hashes": [{"type": "MD5", "value": "d41d8cd98f00b204e9800998ecf8427e"}]}"#,
+    )
+    .unwrap();
+    assert!(!rule.is_match(&event));
+
+    // lists of maps in nested maps and lists of maps in lists of maps
+    let rule = r#"
+        title: Suspicious module
+        logsource:
+        detection:
+            selection:
+                Process.Modules.Exports.Name|endswith: 'MiniDumpWriteDump'
+            filter:
+                Process.Modules.Signed: true
+            condition: selection and not filter"#;
+    let rule = rule_from_yaml(rule).unwrap();
+    let event = event_from_json(
+        r#"{"Process": {"Modules": [
+            {"Signed": false, "Exports": [{"Name": "DllMain"}]},
+            {"Signed": false, "Exports": [{"Name": "DllMain"}, {"Name": "MiniDumpWriteDump"}]}
+        ]}}"#,
+    )
+    .unwrap();
+    assert!(rule.is_match(&event));
+    let event = event_from_json(
+        r#"{"Process": {"Modules": [
+            {"Signed": true, "Exports": [{"Name": "MiniDumpWriteDump"}]}
+        ]}}"#,
+    )
+    .unwrap();
+    assert!(!rule.is_match(&event));
+    let event = event_from_json(r#"{"Process": {"Modules": []}}"#).unwrap();
+    assert!(!rule.is_match(&event));
+}
+
+#[cfg(feature = "serde_json")]
+#[test]
+fn test_match_map_keys() {
+    let rule = r#"
+        title: Run key value names
+        logsource:
+        detection:
+            selection:
+                RegistryValues|keys|contains:
+                    - 'updater'
+                    - 'svchost'
+            condition: selection"#;
+    let rule = rule_from_yaml(rule).unwrap();
+    for (values, expected) in [
+        (
+            json!({"OneDrive": "C:\\onedrive.exe", "UpdaterTask": "C:\\temp\\a.exe"}),
+            true,
+        ),
+        (json!({"OneDrive": "C:\\onedrive.exe"}), false),
+        // the values of the map are not matched
+        (json!({"OneDrive": "C:\\updater.exe"}), false),
+        (json!({}), false),
+        // only maps have keys
+        (json!("updater"), false),
+        (json!(["updater"]), false),
+    ] {
+        let event = Event::try_from(json!({ "RegistryValues": values })).unwrap();
+        assert_eq!(rule.is_match(&event), expected, "{}", values);
+    }
+
+    let rule = r#"
+        title: Run key value names
+        logsource:
+        detection:
+            selection:
+                RegistryValues|keys|startswith|all:
+                    - 'One'
+                    - 'Upd'
+            condition: selection"#;
+    let rule = rule_from_yaml(rule).unwrap();
+    let event = Event::try_from(json!({ "RegistryValues": {"OneDrive": 1, "UpdaterTask": 2} }));
+    assert!(rule.is_match(&event.unwrap()));
+    let event = Event::try_from(json!({ "RegistryValues": {"OneDrive": 1, "Task": 2} }));
+    assert!(!rule.is_match(&event.unwrap()));
+}
+
+#[cfg(feature = "serde_json")]
+#[test]
+fn test_match_null_filter_with_lists() {
+    let rule = r#"
+        title: Rundll32 without arguments
+        logsource:
+        detection:
+            selection:
+                Image|endswith: '\rundll32.exe'
+            filter_main_null:
+                CommandLine: null
+            condition: selection and not filter_main_null"#;
+    let rule = rule_from_yaml(rule).unwrap();
+    for (command_line, expected) in [
+        (json!(null), false),
+        (json!([]), false),
+        (json!(["rundll32.exe", null]), false),
+        (json!(["rundll32.exe", "shell32.dll"]), true),
+        (json!({}), true),
+    ] {
+        let event = json!({ "Image": "C:\\rundll32.exe", "CommandLine": command_line });
+        let event = Event::try_from(event).unwrap();
+        assert_eq!(rule.is_match(&event), expected, "{}", command_line);
+    }
+    // a missing field is null as well
+    let event = Event::try_from(json!({ "Image": "C:\\rundll32.exe" })).unwrap();
+    assert!(!rule.is_match(&event));
+}
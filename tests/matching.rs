@@ -1,4 +1,11 @@
-use sigma_rust::{rule_from_yaml, Event, Rule};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use sigma_rust::{
+    rule_from_yaml, rule_from_yaml_with_decoders, Decoder, Decoders, Event, MultiEvent, Rule,
+};
+use std::net::IpAddr;
+use std::str::FromStr;
+use std::sync::Arc;
 
 #[test]
 fn test_match_rule_with_keywords() {
@@ -113,13 +120,19 @@ fn test_match_null_fields() {
     "#;
 
     let rule = rule_from_yaml(yaml).unwrap();
-    let event_1 = Event::from([("OriginalFileName", "RUNDLL32.EXE")]);
+    let event_1 = Event::from([
+        ("OriginalFileName", "RUNDLL32.EXE"),
+        ("CommandLine", "rundll32.exe shell32.dll"),
+    ]);
     let mut event_2 = Event::new();
     event_2.insert("Image", "c:\\rundll32.exe");
     event_2.insert("CommandLine", None);
+    // a missing field is null as well
+    let event_3 = Event::from([("OriginalFileName", "RUNDLL32.EXE")]);
 
     assert!(rule.is_match(&event_1));
     assert!(!rule.is_match(&event_2));
+    assert!(!rule.is_match(&event_3));
 }
 
 #[test]
@@ -179,4 +192,566 @@ fn test_match_exists_modifier() {
     assert!(rule.is_match(&event_1));
     assert!(!rule.is_match(&event_2));
     assert!(!rule.is_match(&event_3));
-}
\ No newline at end of file
+}
+#[test]
+fn test_quick_screen_has_no_false_negatives() {
+    let yaml = r#"
+        title: Quick screen test
+        logsource:
+        detection:
+            selection_img:
+                Image|endswith:
+                    - '\powershell.exe'
+                    - '\pwsh.exe'
+            selection_cli:
+                CommandLine|contains|all:
+                    - ' -enc'
+                    - 'bypass'
+            keywords:
+                - 'FromBase64String'
+            condition: (selection_img and selection_cli) or keywords
+    "#;
+    let rule = rule_from_yaml(yaml).unwrap();
+    let events = [
+        Event::from([
+            ("Image", "C:\\Windows\\PowerShell.exe"),
+            ("CommandLine", "powershell -ENC abc -ep Bypass"),
+        ]),
+        Event::from([("Image", "C:\\pwsh.exe"), ("CommandLine", "pwsh -c dir")]),
+        Event::from([("Payload", "[Convert]::FromBase64String('abc')")]),
+        Event::from([("Image", "C:\\cmd.exe"), ("CommandLine", "cmd /c dir")]),
+        Event::from([("Image", "C:\\notepad.exe"), ("CommandLine", "notepad")]),
+        Event::from([("EventID", 4104)]),
+    ];
+
+    let mut screened_out = 0;
+    for event in events.iter() {
+        if rule.is_match(event) {
+            assert!(rule.quick_screen(event));
+        }
+        if !rule.quick_screen(event) {
+            assert!(!rule.is_match(event));
+            screened_out += 1;
+        }
+    }
+    assert_eq!(screened_out, 3);
+}
+
+#[test]
+fn test_suppress_with_filter_rules() {
+    let rule = rule_from_yaml(
+        r#"
+        title: Rundll32 execution
+        logsource:
+        detection:
+            selection:
+                Image|endswith: '\rundll32.exe'
+            condition: selection
+    "#,
+    )
+    .unwrap();
+    let filter_1 = rule_from_yaml(
+        r#"
+        title: Known benign dll
+        logsource:
+        detection:
+            selection:
+                CommandLine|contains: 'shell32.dll,Control_RunDLL'
+            condition: selection
+    "#,
+    )
+    .unwrap();
+    let filter_2 = rule_from_yaml(
+        r#"
+        title: Admin hosts
+        logsource:
+        detection:
+            selection:
+                Computer|startswith: 'admin-'
+            condition: selection
+    "#,
+    )
+    .unwrap();
+    let filters = [&filter_1, &filter_2];
+
+    let benign = Event::from([
+        ("Image", "C:\\Windows\\System32\\rundll32.exe"),
+        (
+            "CommandLine",
+            "rundll32.exe shell32.dll,Control_RunDLL desk.cpl",
+        ),
+        ("Computer", "ws-01"),
+    ]);
+    let suspicious = Event::from([
+        ("Image", "C:\\Windows\\System32\\rundll32.exe"),
+        ("CommandLine", "rundll32.exe evil.dll,Start"),
+        ("Computer", "ws-01"),
+    ]);
+
+    assert!(rule.is_match(&benign));
+    assert!(rule.is_suppressed_by(&benign, &filters));
+    assert!(rule.is_match(&suspicious));
+    assert!(!rule.is_suppressed_by(&suspicious, &filters));
+    assert!(!rule.is_suppressed_by(&benign, &[]));
+}
+
+#[test]
+fn test_match_keywords_on_word_boundaries() {
+    let yaml = r#"title: A rule with keywords
+logsource:
+    service: test
+detection:
+    keywords:
+        - 'cat'
+    condition: keywords
+"#;
+    let mut rule = rule_from_yaml(yaml).unwrap();
+    let event_1 = Event::from([("a", "invalid certificate")]);
+    let event_2 = Event::from([("a", "cat /etc/shadow")]);
+
+    assert!(rule.is_match(&event_1));
+    assert!(rule.is_match(&event_2));
+
+    rule.detection.set_whole_word_keywords(true);
+    assert!(!rule.is_match(&event_1));
+    assert!(rule.is_match(&event_2));
+}
+
+#[test]
+fn test_match_date_modifier() {
+    let yaml = r#"
+        title: Date test
+        logsource:
+        detection:
+            selection:
+                Timestamp|gte|date: "2024-01-01T00:00:00Z"
+                Timestamp|lt|date: "2024-02-01"
+            condition: selection
+    "#;
+    let rule = rule_from_yaml(yaml).unwrap();
+    let event_1 = Event::from([("Timestamp", "2024-01-15T08:30:00+02:00")]);
+    let event_2 = Event::from([("Timestamp", "2023-12-31T23:00:00-02:00")]);
+    let event_3 = Event::from([("Timestamp", "2024-02-01T00:00:00Z")]);
+    let event_4 = Event::from([("Timestamp", "garbage")]);
+    assert!(rule.is_match(&event_1));
+    assert!(rule.is_match(&event_2));
+    assert!(!rule.is_match(&event_3));
+    assert!(!rule.is_match(&event_4));
+}
+
+#[test]
+fn test_match_multi_event_slots() {
+    let yaml = r#"
+        title: Multi event test
+        logsource:
+        detection:
+            selection:
+                parent.Image|endswith: '\explorer.exe'
+                child.ParentProcessId|fieldref: parent.ProcessId
+                child.User|fieldref: parent.User
+            condition: selection
+    "#;
+    let rule = rule_from_yaml(yaml).unwrap();
+
+    let mut parent = Event::from([("Image", "C:\\Windows\\explorer.exe"), ("User", "alice")]);
+    parent.insert("ProcessId", 4242);
+    let mut child = Event::from([("Image", "C:\\Windows\\cmd.exe"), ("User", "alice")]);
+    child.insert("ParentProcessId", 4242);
+
+    let mut events = MultiEvent::new();
+    events.insert("parent", parent);
+    events.insert("child", child);
+    assert!(rule.is_match_multi(&events));
+    assert!(events.get("child.User").is_some());
+    assert!(events.get("User").is_none());
+
+    let mut other_child = Event::from([("User", "bob")]);
+    other_child.insert("ParentProcessId", 4242);
+    events.insert("child", other_child);
+    assert!(!rule.is_match_multi(&events));
+}
+
+#[test]
+fn test_match_trim_modifier() {
+    let yaml = r#"
+        title: Trim test
+        logsource:
+        detection:
+            selection:
+                OriginalFileName|trim: 'RUNDLL32.EXE'
+            condition: selection
+    "#;
+    let rule = rule_from_yaml(yaml).unwrap();
+    let untrimmed_rule = rule_from_yaml(&yaml.replace("|trim", "")).unwrap();
+
+    for value in ["RUNDLL32.EXE ", " rundll32.exe\r\n", "\tRUNDLL32.EXE"] {
+        let event = Event::from([("OriginalFileName", value)]);
+        assert!(rule.is_match(&event), "{:?}", value);
+        assert!(!untrimmed_rule.is_match(&event), "{:?}", value);
+    }
+    let event = Event::from([("OriginalFileName", "RUNDLL32 .EXE")]);
+    assert!(!rule.is_match(&event));
+}
+
+#[test]
+fn test_match_base64offset_decode() {
+    let yaml = r#"
+        title: Decode test
+        logsource:
+        detection:
+            selection:
+                Payload|base64offsetdecode|contains: 'whoami /priv'
+            condition: selection
+    "#;
+    let rule = rule_from_yaml(yaml).unwrap();
+
+    // the command is at a non-zero offset of the decoded content
+    let encoded = STANDARD.encode("cmd.exe /c whoami /priv && exit");
+    let event = Event::from([("Payload", encoded.as_str())]);
+    assert!(rule.is_match(&event));
+    assert!(rule.quick_screen(&event));
+
+    // a fragment of a larger encoded stream which is not aligned to a 4 character group
+    let event = Event::from([("Payload", &encoded[5..])]);
+    assert!(rule.is_match(&event));
+
+    let encoded = STANDARD.encode("cmd.exe /c whoami /groups");
+    let event = Event::from([("Payload", encoded.as_str())]);
+    assert!(!rule.is_match(&event));
+    let event = Event::from([("Payload", "whoami /priv")]);
+    assert!(!rule.is_match(&event));
+}
+
+#[test]
+fn test_match_urldecode() {
+    let yaml = r#"
+        title: Path traversal
+        logsource:
+        detection:
+            selection:
+                uri|urldecode|contains: '../'
+            condition: selection
+    "#;
+    let rule = rule_from_yaml(yaml).unwrap();
+
+    for uri in [
+        "/static/../../etc/passwd",
+        "/static/%2e%2e%2f%2e%2e%2fetc/passwd",
+        "/static/%2E%2E%2Fetc/passwd",
+        "/static/%252e%252e%252fetc/passwd",
+        "/static/..%2fetc/passwd",
+    ] {
+        let event = Event::from([("uri", uri)]);
+        assert!(rule.is_match(&event), "{}", uri);
+        assert!(rule.quick_screen(&event));
+    }
+    for uri in ["/static/index.html", "/static/%2e%2e%zzetc/passwd"] {
+        let event = Event::from([("uri", uri)]);
+        assert!(!rule.is_match(&event), "{}", uri);
+    }
+}
+
+#[test]
+fn test_match_urldecode_malformed_escapes() {
+    let yaml = r#"
+        title: Path traversal
+        logsource:
+        detection:
+            selection:
+                uri|urldecode|contains|all:
+                    - '../../'
+                    - 'file=%zz%4'
+            condition: selection
+    "#;
+    let rule = rule_from_yaml(yaml).unwrap();
+
+    // malformed escapes are kept literally while the valid ones are decoded
+    let event = Event::from([("uri", "/get?path=..%2f..%2f&file=%zz%4")]);
+    assert!(rule.is_match(&event));
+    let event = Event::from([("uri", "/get?path=..%2f..%2g&file=%zz%4")]);
+    assert!(!rule.is_match(&event));
+}
+
+#[test]
+fn test_match_string_modifiers_on_numbers() {
+    let yaml = r#"
+        title: Alternative HTTP ports
+        logsource:
+        detection:
+            selection:
+                DestinationPort|startswith: '80'
+                DestinationPort|endswith: '80'
+            condition: selection
+    "#;
+    let rule = rule_from_yaml(yaml).unwrap();
+    for (port, expected) in [(8080, true), (80, true), (8081, false), (443, false)] {
+        let event = Event::from([("DestinationPort", port)]);
+        assert_eq!(rule.is_match(&event), expected, "{}", port);
+    }
+}
+
+#[test]
+fn test_match_path_separators() {
+    let yaml = r#"
+        title: Rundll32 execution
+        logsource:
+        detection:
+            selection:
+                Image|endswith|path: '\rundll32.exe'
+            condition: selection
+    "#;
+    let rule = rule_from_yaml(yaml).unwrap();
+    for (image, expected) in [
+        ("C:/rundll32.exe", true),
+        ("C:\\Windows\\System32\\rundll32.exe", true),
+        ("C:/Windows/notrundll32.exe", false),
+    ] {
+        let event = Event::from([("Image", image)]);
+        assert_eq!(rule.is_match(&event), expected, "{}", image);
+        if expected {
+            assert!(rule.quick_screen(&event));
+        }
+    }
+}
+
+#[test]
+fn test_match_yaml_number_notations() {
+    let yaml = r#"
+        title: Number notations
+        logsource:
+        detection:
+            selection:
+                Offset|gt: -1
+                Size|gte: 1e6
+                Flags|lte: 0xFF
+            condition: selection
+    "#;
+    let rule = rule_from_yaml(yaml).unwrap();
+    let event = |offset: i64, size: f64, flags: i64| {
+        let mut event = Event::new();
+        event.insert("Offset", offset);
+        event.insert("Size", size);
+        event.insert("Flags", flags);
+        event
+    };
+    assert!(rule.is_match(&event(0, 1e6, 255)));
+    assert!(rule.is_match(&event(1, 2.5e6, 0)));
+    assert!(!rule.is_match(&event(-2, 1e6, 255)));
+    assert!(!rule.is_match(&event(0, 999_999.9, 255)));
+    assert!(!rule.is_match(&event(0, 1e6, 256)));
+
+    let yaml = r#"
+        title: NaN
+        logsource:
+        detection:
+            selection:
+                Size: .nan
+            condition: selection
+    "#;
+    assert!(rule_from_yaml(yaml).is_err());
+}
+
+#[test]
+fn test_match_regex_capture_fieldref() {
+    let yaml = r#"
+        title: Request to a host other than the URL host
+        logsource:
+        detection:
+            selection:
+                Url|re|fieldref: '^https?://(?P<Host>[^/:]+)'
+            condition: not selection
+    "#;
+    let rule = rule_from_yaml(yaml).unwrap();
+    let event = |url: &str, host: &str| Event::from([("Url", url), ("Host", host)]);
+    assert!(!rule.is_match(&event("https://example.com/login", "example.com")));
+    assert!(rule.is_match(&event("https://example.com/login", "evil.com")));
+}
+
+#[test]
+fn test_match_windash_with_all() {
+    let yaml = r#"
+        title: Encoded PowerShell command
+        logsource:
+        detection:
+            selection:
+                CommandLine|windash|contains|all:
+                    - ' -nop '
+                    - ' -enc '
+            condition: selection
+    "#;
+    let rule = rule_from_yaml(yaml).unwrap();
+    for (command_line, expected) in [
+        ("powershell.exe -nop -enc ZQBjAGgAbwA=", true),
+        ("powershell.exe /nop /enc ZQBjAGgAbwA=", true),
+        ("powershell.exe -nop /enc ZQBjAGgAbwA=", true),
+        ("powershell.exe -nop -e ZQBjAGgAbwA=", false),
+    ] {
+        let event = Event::from([("CommandLine", command_line)]);
+        assert_eq!(rule.is_match(&event), expected, "{}", command_line);
+    }
+}
+
+#[cfg(feature = "unicode-normalization")]
+#[test]
+fn test_match_unicode_normalization() {
+    let yaml = r#"
+        title: Mimikatz command
+        logsource:
+        detection:
+            selection:
+                CommandLine|normalize|contains: 'sekurlsa::logonpasswords'
+            condition: selection
+    "#;
+    let rule = rule_from_yaml(yaml).unwrap();
+    for command_line in [
+        "mimikatz.exe sekurlsa::logonpasswords",
+        "mimikatz.exe ｓｅｋｕｒｌｓａ：：ｌｏｇｏｎｐａｓｓｗｏｒｄｓ",
+        "mimikatz.exe SEKURLSA::LOGONPASSWORDS",
+    ] {
+        let event = Event::from([("CommandLine", command_line)]);
+        assert!(rule.is_match(&event), "{}", command_line);
+    }
+}
+
+#[test]
+fn test_match_base64offset_with_all() {
+    let yaml = r#"
+        title: Encoded discovery commands
+        logsource:
+        detection:
+            selection:
+                Cmd|base64offset|all|contains:
+                    - 'whoami'
+                    - 'net user'
+            condition: selection
+    "#;
+    let rule = rule_from_yaml(yaml).unwrap();
+    // base64 of "cmd /c whoami && net user admin"
+    let event = Event::from([("Cmd", "Y21kIC9jIHdob2FtaSAmJiBuZXQgdXNlciBhZG1pbg==")]);
+    assert!(rule.is_match(&event));
+    // base64 of "cmd /c whoami"
+    let event = Event::from([("Cmd", "Y21kIC9jIHdob2FtaQ==")]);
+    assert!(!rule.is_match(&event));
+}
+
+/// Decodes `%XX` escapes, other characters and malformed escapes are kept as they are
+fn percent_decode(s: &str) -> Vec<String> {
+    let bytes = s.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = bytes
+            .get(i + 1..i + 3)
+            .filter(|_| bytes[i] == b'%')
+            .and_then(|hex| u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok());
+        match escaped {
+            Some(b) => {
+                decoded.push(b);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    vec![String::from_utf8_lossy(&decoded).into_owned()]
+}
+
+#[test]
+fn test_match_custom_decoder() {
+    let yaml = r#"
+        title: Path traversal
+        logsource:
+        detection:
+            selection:
+                Url|percentdecode|contains|all:
+                    - '../'
+                    - '/etc/passwd'
+            condition: selection
+    "#;
+    assert!(rule_from_yaml(yaml).is_err());
+
+    let decoder: Decoder = Arc::new(percent_decode);
+    let decoders = Decoders::from([("PercentDecode".to_string(), decoder)]);
+    let rule = rule_from_yaml_with_decoders(yaml, &decoders).unwrap();
+    let event = Event::from([("Url", "/download?file=..%2F..%2Fetc%2Fpasswd")]);
+    assert!(rule.is_match(&event));
+    let event = Event::from([("Url", "/download?file=%2Fetc%2Fhosts%zz")]);
+    assert!(!rule.is_match(&event));
+
+    // the decoders are only available while the rule is parsed
+    assert!(rule_from_yaml(yaml).is_err());
+
+    // a custom decoder conflicts with other decoders
+    let yaml = yaml.replace("percentdecode", "percentdecode|urldecode");
+    assert!(rule_from_yaml_with_decoders(&yaml, &decoders).is_err());
+}
+
+#[test]
+fn test_match_event_with_ip_address() {
+    let rule = rule_from_yaml(
+        r#"
+title: Internal address
+logsource:
+detection:
+    selection_plain:
+        SourceIp: 10.0.0.1
+    selection_prefix:
+        DestinationIp|startswith: '192.168.'
+    selection_network:
+        ClientIp|cidr: 172.16.0.0/12
+    condition: 1 of selection_*
+"#,
+    )
+    .unwrap();
+    let ip = |s: &str| IpAddr::from_str(s).unwrap();
+
+    assert!(rule.is_match(&Event::from([("SourceIp", ip("10.0.0.1"))])));
+    assert!(!rule.is_match(&Event::from([("SourceIp", ip("10.0.0.2"))])));
+    assert!(rule.is_match(&Event::from([("DestinationIp", ip("192.168.1.1"))])));
+    assert!(rule.is_match(&Event::from([("ClientIp", ip("172.20.0.1"))])));
+    assert!(!rule.is_match(&Event::from([("ClientIp", ip("172.32.0.1"))])));
+}
+
+#[test]
+fn test_match_rule_within_decoder() {
+    // a decoder matching another rule evaluates it while the outer rule is evaluated
+    let inner = Arc::new(
+        rule_from_yaml(
+            r#"
+title: Inner
+logsource:
+detection:
+    selection:
+        Value: whoami
+    filter:
+        Value: id
+    condition: selection and not filter
+"#,
+        )
+        .unwrap(),
+    );
+    let matches: Decoder = Arc::new(move |s| {
+        let event = Event::from([("Value", s)]);
+        vec![inner.is_match(&event).to_string()]
+    });
+    let decoders = Decoders::from([("inner".to_string(), matches)]);
+    let rule = rule_from_yaml_with_decoders(
+        r#"
+title: Outer
+logsource:
+detection:
+    selection:
+        Payload|inner: 'true'
+    filter:
+        Payload: ignored
+    condition: selection and not filter
+"#,
+        &decoders,
+    )
+    .unwrap();
+
+    assert!(rule.is_match(&Event::from([("Payload", "whoami")])));
+    assert!(!rule.is_match(&Event::from([("Payload", "id")])));
+}